@@ -0,0 +1,174 @@
+use crate::game::BettingRoundName;
+use crate::player::Action;
+
+// How much a player may bet or raise. Fixed limit bets the big blind before
+// the turn and twice that from the turn on, with the blind itself counting as
+// the first bet before the flop; the cap is how many bets a street allows,
+// and is lifted once only two players are left in the hand. Stud bets the
+// big blind on third and fourth street and twice that after, and the first
+// bet on third street completes the bring-in to the big blind. Draw games
+// switch to the big bet halfway: five-card draw after its one draw, triple
+// draw after the second.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum BettingStructure {
+    NoLimit,
+    PotLimit,
+    FixedLimit { cap: u32 },
+}
+
+// the usual cap of a bet and three raises
+pub const DEFAULT_CAP: u32 = 4;
+
+// Where the betting stands as a player is about to act.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct BettingState {
+    pub street: BettingRoundName,
+    // how many times the game draws, none outside the draw games
+    pub draws: usize,
+    pub big_blind: u32,
+    // everything put in so far, antes and bets on this street included
+    pub pot: u32,
+    pub to_call: u32,
+    // the player's stack before calling
+    pub chips: u32,
+    // the last full bet or raise on this street, which the next must match
+    pub last_raise: u32,
+    // bets and raises on this street, counting the blinds before the flop
+    pub raises: u32,
+    pub heads_up: bool,
+}
+
+// The smallest and largest raise on top of the call. Either may be all in,
+// and the smallest is less than a full raise when that is all the player has.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct RaiseBounds {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl BettingStructure {
+    pub fn name(self) -> &'static str {
+        match self {
+            BettingStructure::NoLimit => "no-limit",
+            BettingStructure::PotLimit => "pot-limit",
+            BettingStructure::FixedLimit { .. } => "fixed-limit",
+        }
+    }
+
+    // by name, fixed limit with the usual cap
+    pub fn from_name(name: &str) -> Option<BettingStructure> {
+        match name {
+            "no-limit" => Some(BettingStructure::NoLimit),
+            "pot-limit" => Some(BettingStructure::PotLimit),
+            "fixed-limit" => Some(BettingStructure::FixedLimit { cap: DEFAULT_CAP }),
+            _ => None,
+        }
+    }
+
+    // None when the player cannot raise at all, because they can only call
+    // or the street is capped.
+    pub fn raise_bounds(self, state: &BettingState) -> Option<RaiseBounds> {
+        let behind = state.chips.checked_sub(state.to_call).filter(|&behind| behind > 0)?;
+        let (min, max) = match self {
+            BettingStructure::NoLimit => (state.last_raise, behind),
+            // a pot sized raise is the pot after calling
+            BettingStructure::PotLimit => (state.last_raise, state.pot + state.to_call),
+            BettingStructure::FixedLimit { cap } => {
+                if state.raises >= cap && !state.heads_up {
+                    return None;
+                }
+                let bet = match state.street {
+                    BettingRoundName::Preflop | BettingRoundName::Flop | BettingRoundName::ThirdStreet | BettingRoundName::FourthStreet => state.big_blind,
+                    BettingRoundName::FirstDraw if state.draws > 1 => state.big_blind,
+                    _ => 2 * state.big_blind,
+                };
+                // only a bring-in is called before the street's first bet
+                let bet = if state.raises == 0 { bet.saturating_sub(state.to_call) } else { bet };
+                (bet, bet)
+            },
+        };
+        let max = max.min(behind).max(1);
+        Some(RaiseBounds { min: min.clamp(1, max), max })
+    }
+
+    // Sizes a decided raise into the bounds, and makes it a call when no raise
+    // is allowed. Anything else is left for Player::apply to carry out or refuse.
+    pub fn fit(self, action: Action, state: &BettingState) -> Action {
+        let bounds = self.raise_bounds(state);
+        match (action, bounds) {
+            (Action::Raise(raise), Some(bounds)) if raise > 0 => Action::Raise(raise.clamp(bounds.min, bounds.max)),
+            (Action::Raise(raise), None) if raise > 0 => Action::Call,
+            (Action::AllIn(_), Some(bounds)) if state.chips > state.to_call + bounds.max => Action::Raise(bounds.max),
+            (Action::AllIn(_), None) if state.chips > state.to_call => Action::Call,
+            (action, _) => action,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(street: BettingRoundName, pot: u32, to_call: u32, chips: u32) -> BettingState {
+        BettingState { street, draws: 0, big_blind: 2, pot, to_call, chips, last_raise: 2, raises: 1, heads_up: false }
+    }
+
+    #[test]
+    fn test_no_limit() {
+        let preflop = state(BettingRoundName::Preflop, 3, 2, 100);
+        assert_eq!(BettingStructure::NoLimit.raise_bounds(&preflop), Some(RaiseBounds { min: 2, max: 98 }));
+        assert_eq!(BettingStructure::NoLimit.fit(Action::Raise(1), &preflop), Action::Raise(2));
+        assert_eq!(BettingStructure::NoLimit.fit(Action::AllIn(100), &preflop), Action::AllIn(100));
+        // short of a full raise, only all in is left
+        let short = BettingState { last_raise: 10, ..state(BettingRoundName::Flop, 40, 10, 15) };
+        assert_eq!(BettingStructure::NoLimit.raise_bounds(&short), Some(RaiseBounds { min: 5, max: 5 }));
+        assert_eq!(BettingStructure::NoLimit.raise_bounds(&state(BettingRoundName::Flop, 40, 10, 10)), None);
+        assert_eq!(BettingStructure::NoLimit.fit(Action::Raise(5), &state(BettingRoundName::Flop, 40, 10, 10)), Action::Call);
+    }
+
+    #[test]
+    fn test_pot_limit() {
+        // blinds of 1 and 2: calling 2 makes the pot 5, so the raise is 5 more, to 7
+        let preflop = state(BettingRoundName::Preflop, 3, 2, 100);
+        assert_eq!(BettingStructure::PotLimit.raise_bounds(&preflop), Some(RaiseBounds { min: 2, max: 5 }));
+        assert_eq!(BettingStructure::PotLimit.fit(Action::AllIn(100), &preflop), Action::Raise(5));
+        // a bet of 10 into 20 is called for 10, making 40 to raise by
+        let flop = BettingState { last_raise: 10, ..state(BettingRoundName::Flop, 30, 10, 200) };
+        assert_eq!(BettingStructure::PotLimit.raise_bounds(&flop), Some(RaiseBounds { min: 10, max: 40 }));
+        assert_eq!(BettingStructure::PotLimit.fit(Action::Raise(100), &flop), Action::Raise(40));
+    }
+
+    #[test]
+    fn test_fixed_limit() {
+        let limit = BettingStructure::FixedLimit { cap: DEFAULT_CAP };
+        assert_eq!(limit.raise_bounds(&state(BettingRoundName::Flop, 10, 0, 100)), Some(RaiseBounds { min: 2, max: 2 }));
+        assert_eq!(limit.fit(Action::Raise(7), &state(BettingRoundName::Turn, 10, 0, 100)), Action::Raise(4));
+        assert_eq!(limit.fit(Action::AllIn(3), &state(BettingRoundName::River, 10, 0, 3)), Action::AllIn(3));
+        // completing a bring-in of 1 to the small bet of 2, then raising it
+        let bring_in = BettingState { raises: 0, ..state(BettingRoundName::ThirdStreet, 9, 1, 100) };
+        assert_eq!(limit.raise_bounds(&bring_in), Some(RaiseBounds { min: 1, max: 1 }));
+        let completed = BettingState { raises: 1, ..state(BettingRoundName::ThirdStreet, 10, 2, 100) };
+        assert_eq!(limit.raise_bounds(&completed), Some(RaiseBounds { min: 2, max: 2 }));
+        assert_eq!(limit.fit(Action::Raise(9), &state(BettingRoundName::FifthStreet, 20, 0, 100)), Action::Raise(4));
+        // the big bet comes after five-card draw's only draw, and after
+        // triple draw's second
+        let five_card = |street| BettingState { draws: 1, ..state(street, 20, 0, 100) };
+        assert_eq!(limit.fit(Action::Raise(9), &five_card(BettingRoundName::FirstDraw)), Action::Raise(4));
+        let triple = |street| BettingState { draws: 3, ..state(street, 20, 0, 100) };
+        assert_eq!(limit.fit(Action::Raise(9), &triple(BettingRoundName::FirstDraw)), Action::Raise(2));
+        assert_eq!(limit.fit(Action::Raise(9), &triple(BettingRoundName::SecondDraw)), Action::Raise(4));
+        let capped = BettingState { raises: 4, ..state(BettingRoundName::Turn, 40, 4, 100) };
+        assert_eq!(limit.raise_bounds(&capped), None);
+        assert_eq!(limit.fit(Action::Raise(4), &capped), Action::Call);
+        let heads_up = BettingState { heads_up: true, ..capped };
+        assert_eq!(limit.raise_bounds(&heads_up), Some(RaiseBounds { min: 4, max: 4 }));
+    }
+
+    #[test]
+    fn test_names() {
+        for structure in [BettingStructure::NoLimit, BettingStructure::PotLimit, BettingStructure::FixedLimit { cap: 4 }] {
+            assert_eq!(BettingStructure::from_name(structure.name()), Some(structure));
+        }
+        assert_eq!(BettingStructure::from_name("spread-limit"), None);
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use crate::history::HandRecord;
+
+// What knocking a player out of a bounty tournament pays. In a knockout
+// tournament the whole bounty on their head is paid out; in a progressive
+// knockout half is paid out and the other half goes onto the bounty of
+// whoever knocked them out, with an odd chip paid out.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum BountyFormat {
+    Knockout,
+    Progressive,
+}
+
+// One player knocking out another, or a share of it when the pot with the
+// last of their chips was split.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Knockout {
+    pub player_id: usize,
+    pub by: usize,
+    pub hand_id: u64,
+    // paid out to the player who knocked them out
+    pub won: u32,
+    // added to the bounty on their own head
+    pub added: u32,
+}
+
+// The bounties of a tournament, in the same money as its prizes.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Bounties {
+    format: BountyFormat,
+    // on each player's head by player id
+    bounties: HashMap<usize, u32>,
+    // paid out to each player by player id
+    won: HashMap<usize, u32>,
+    knockouts: Vec<Knockout>,
+}
+
+impl Bounties {
+    // everyone starts with the same bounty
+    pub fn new(format: BountyFormat, players: impl IntoIterator<Item = usize>, bounty: u32) -> Bounties {
+        Bounties {
+            format,
+            bounties: players.into_iter().map(|id| (id, bounty)).collect(),
+            won: HashMap::new(),
+            knockouts: Vec::new(),
+        }
+    }
+
+    pub fn format(&self) -> BountyFormat {
+        self.format
+    }
+
+    // on a player's head now, 0 once they are knocked out
+    pub fn bounty(&self, player_id: usize) -> u32 {
+        self.bounties.get(&player_id).copied().unwrap_or(0)
+    }
+
+    // every bounty still to be won by player id
+    pub fn bounties(&self) -> &HashMap<usize, u32> {
+        &self.bounties
+    }
+
+    // paid out to a player so far
+    pub fn won(&self, player_id: usize) -> u32 {
+        self.won.get(&player_id).copied().unwrap_or(0)
+    }
+
+    // in the order they happened
+    pub fn knockouts(&self) -> &[Knockout] {
+        &self.knockouts
+    }
+
+    // Pays out the bounties of the players who busted in this hand to
+    // whoever took the last of their chips. Bounties are split between the
+    // winners of that pot by how much of it each won, odd chips to the first.
+    pub fn knock_out(&mut self, hand: &HandRecord, busted: &[usize]) {
+        // the smaller stacks first, the order they finish in from the bottom
+        let mut busted = busted.to_vec();
+        busted.sort_by_key(|&id| contributed(hand, id));
+        for &player_id in &busted {
+            let bounty = self.bounties.remove(&player_id).unwrap_or(0);
+            let winners = knocked_out_by(hand, player_id);
+            if winners.is_empty() {
+                continue;
+            }
+            let taken: u64 = winners.iter().map(|w| w.1 as u64).sum();
+            let mut shares: Vec<u32> = winners
+                .iter()
+                .map(|&(_, won)| (bounty as u64 * won as u64).checked_div(taken).map_or(bounty / winners.len() as u32, |share| share as u32))
+                .collect();
+            let left = bounty - shares.iter().sum::<u32>();
+            for share in shares.iter_mut().take(left as usize) {
+                *share += 1;
+            }
+            for (&(by, _), share) in winners.iter().zip(shares) {
+                let added = match self.format {
+                    BountyFormat::Knockout => 0,
+                    BountyFormat::Progressive => share / 2,
+                };
+                self.pay(by, share - added);
+                if let Some(own) = self.bounties.get_mut(&by) {
+                    *own += added;
+                }
+                self.knockouts.push(Knockout { player_id, by, hand_id: hand.hand_id, won: share - added, added });
+            }
+        }
+    }
+
+    // the last player left is paid the bounty on their own head
+    pub fn crown(&mut self, player_id: usize) {
+        if let Some(bounty) = self.bounties.remove(&player_id) {
+            self.pay(player_id, bounty);
+        }
+    }
+
+    fn pay(&mut self, player_id: usize, amount: u32) {
+        *self.won.entry(player_id).or_insert(0) += amount;
+    }
+}
+
+// what a player had in the pots once any uncalled bet came back
+fn contributed(hand: &HandRecord, player_id: usize) -> u32 {
+    hand.put_in(player_id) - hand.uncalled.filter(|u| u.0 == player_id).map_or(0, |u| u.1)
+}
+
+// The winners of the highest pot a player was in, which holds the last of
+// their chips, with what each won from it. There is a pot for each level
+// the players who did not fold put in, as Game splits them.
+pub fn knocked_out_by(hand: &HandRecord, player_id: usize) -> Vec<(usize, u32)> {
+    let mut levels: Vec<u32> = hand
+        .seats
+        .iter()
+        .filter(|s| hand.folded_on(s.player_id).is_none())
+        .map(|s| contributed(hand, s.player_id))
+        .collect();
+    levels.sort_unstable();
+    levels.dedup();
+    levels
+        .iter()
+        .position(|&level| level == contributed(hand, player_id))
+        .and_then(|idx| hand.pots.get(idx))
+        .map_or(Vec::new(), |pot| pot.winners.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Card;
+    use crate::game::Game;
+    use crate::player::Player;
+    use crate::strategy::Aggressive;
+
+    fn cards(codes: &str) -> Vec<Card> {
+        codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect()
+    }
+
+    // everyone all in before the flop with these stacks and hole cards
+    fn shove(stacks: &[u32], hole_cards: &[&str], board: &str) -> HandRecord {
+        let players = stacks.iter().enumerate().map(|(id, &chips)| Player::new(id, format!("P{}", id + 1), chips)).collect();
+        let mut game = Game::with_players(players, 1, 2).unwrap();
+        game.set_verbose(false);
+        for id in 0..stacks.len() {
+            game.set_strategy(id, Box::new(Aggressive { pot_fraction: 100.0 }));
+        }
+        let hole_cards = hole_cards.iter().enumerate().map(|(id, codes)| (id, cards(codes))).collect();
+        game.force_cards(hole_cards, cards(board)).unwrap();
+        game.play_round().unwrap();
+        game.last_hand().unwrap().clone()
+    }
+
+    #[test]
+    fn test_side_pots() {
+        // P1 has the best hand but only covers the main pot, so P2 takes
+        // the side pots and knocks out P3 and P4
+        let hand = shove(&[50, 300, 100, 200], &["Ah Ad", "Kh Kd", "7c 2d", "8c 3d"], "As Qs 5h Jd 6c");
+        assert_eq!(knocked_out_by(&hand, 2), [(1, 150)]);
+        assert_eq!(knocked_out_by(&hand, 3), [(1, 200)]);
+
+        let mut bounties = Bounties::new(BountyFormat::Knockout, 0..4, 10);
+        bounties.knock_out(&hand, &[3, 2]);
+        assert_eq!(bounties.knockouts().iter().map(|k| (k.player_id, k.by, k.won)).collect::<Vec<_>>(), [(2, 1, 10), (3, 1, 10)]);
+        assert_eq!((bounties.won(1), bounties.bounty(1), bounties.bounty(2)), (20, 10, 0));
+    }
+
+    #[test]
+    fn test_progressive_and_split() {
+        // P1 and P2 chop the pot P3 busted in
+        let hand = shove(&[100, 100, 60], &["Ah Kd", "Ad Kh", "7c 2d"], "As Ks 5h Jd 6c");
+        let mut bounties = Bounties::new(BountyFormat::Progressive, 0..3, 15);
+        bounties.knock_out(&hand, &[2]);
+        // 8 and 7 of the 15, the first to P2 as first left of the button,
+        // each half paid out with the odd chip
+        assert_eq!(bounties.knockouts().iter().map(|k| (k.by, k.won, k.added)).collect::<Vec<_>>(), [(1, 4, 4), (0, 4, 3)]);
+        assert_eq!((bounties.bounty(0), bounties.bounty(1)), (18, 19));
+        bounties.crown(1);
+        assert_eq!(bounties.won(1), 23);
+        assert_eq!(bounties.bounties().len(), 1);
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use crate::error::PokerError;
+use crate::game::Game;
+use crate::player::Player;
+use crate::rake::RakeAttribution;
+
+// What a player does about a short or empty stack between hands. Amounts are
+// kept within the table's buy-in limits.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Rebuy {
+    // leaves the table once busted
+    Never,
+    // buys in for this much again whenever busted
+    WhenBusted(u32),
+    // tops the stack back up to this much whenever it is below it, busted or not
+    TopUp(u32),
+}
+
+// A player's money over the session. Net results count what they still have
+// in front of them as if they cashed it out now, so are already after rake.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CashResult {
+    pub player_id: usize,
+    pub name: String,
+    // every buy-in, rebuy and top-up
+    pub bought_in: u64,
+    // taken from the table by leaving
+    pub cashed_out: u64,
+    // on the table now
+    pub chips: u32,
+    pub buy_ins: u32,
+    // the rake of the hands they played, shared equally among everyone dealt
+    // in, and by what each put into the pot
+    pub rake_dealt: f64,
+    pub rake_contributed: f64,
+}
+
+impl CashResult {
+    pub fn net(&self) -> i64 {
+        (self.cashed_out + self.chips as u64) as i64 - self.bought_in as i64
+    }
+}
+
+// A cash game played on a Game, where players keep their seats when they
+// bust, buy in between the table's limits, rebuy or top up by their own
+// policy, and come and go between hands. Everyone starts on Rebuy::Never.
+pub struct CashGame {
+    game: Game,
+    min_buy_in: u32,
+    max_buy_in: u32,
+    rebuys: HashMap<usize, Rebuy>,
+    // by player id in the order they first sat down
+    accounts: Vec<CashResult>,
+    hands_played: u64,
+    rake: u64,
+}
+
+impl CashGame {
+    // Everyone already seated bought in for the chips they have, which must
+    // be within the limits.
+    pub fn new(mut game: Game, min_buy_in: u32, max_buy_in: u32) -> Result<CashGame, PokerError> {
+        let max_buy_in = max_buy_in.max(min_buy_in);
+        if let Some((_, player)) = game.table().occupied().find(|(_, p)| p.chips < min_buy_in || p.chips > max_buy_in) {
+            return Err(PokerError::BuyInOutOfRange { chips: player.chips, min: min_buy_in, max: max_buy_in });
+        }
+        game.set_keep_busted(true);
+        let mut cash_game = CashGame { game, min_buy_in, max_buy_in, rebuys: HashMap::new(), accounts: Vec::new(), hands_played: 0, rake: 0 };
+        let seated: Vec<(usize, String, u32)> = cash_game.game.players().iter().map(|p| (p.id, p.name.clone(), p.chips)).collect();
+        for (player_id, name, chips) in seated {
+            cash_game.buy(player_id, &name, chips);
+        }
+        Ok(cash_game)
+    }
+
+    pub fn buy_in_limits(&self) -> (u32, u32) {
+        (self.min_buy_in, self.max_buy_in)
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    // e.g. to set strategies, the blinds or a history writer
+    pub fn game_mut(&mut self) -> &mut Game {
+        &mut self.game
+    }
+
+    pub fn hands_played(&self) -> u64 {
+        self.hands_played
+    }
+
+    // everything the house has taken
+    pub fn rake(&self) -> u64 {
+        self.rake
+    }
+
+    pub fn set_rebuy(&mut self, player_id: usize, rebuy: Rebuy) {
+        self.rebuys.insert(player_id, rebuy);
+    }
+
+    pub fn rebuy(&self, player_id: usize) -> Rebuy {
+        self.rebuys.get(&player_id).copied().unwrap_or(Rebuy::Never)
+    }
+
+    // The player buys in for the chips they bring, joining from the next
+    // hand. A player who left before may come back.
+    pub fn sit(&mut self, seat: usize, player: Player) -> Result<(), PokerError> {
+        if player.chips < self.min_buy_in || player.chips > self.max_buy_in {
+            return Err(PokerError::BuyInOutOfRange { chips: player.chips, min: self.min_buy_in, max: self.max_buy_in });
+        }
+        let (player_id, name, chips) = (player.id, player.name.clone(), player.chips);
+        self.game.sit(seat, player)?;
+        self.buy(player_id, &name, chips);
+        Ok(())
+    }
+
+    // the player cashes out whatever they have left
+    pub fn leave(&mut self, seat: usize) -> Option<Player> {
+        let player = self.game.leave(seat)?;
+        if let Some(account) = self.account_mut(player.id) {
+            account.cashed_out += player.chips as u64;
+            account.chips = 0;
+        }
+        Some(player)
+    }
+
+    // A player sitting out keeps their seat and owes the blinds that pass
+    // them, which they post when they sit in again.
+    pub fn sit_out(&mut self, seat: usize) -> Result<(), PokerError> {
+        self.game.sit_out(seat)
+    }
+
+    pub fn sit_in(&mut self, seat: usize) -> Result<(), PokerError> {
+        self.game.sit_in(seat)
+    }
+
+    // Adds chips to a seated player's stack between hands, up to the most
+    // they may buy in for.
+    pub fn top_up(&mut self, seat: usize, chips: u32) -> Result<(), PokerError> {
+        let player = self.game.table().player(seat).ok_or(PokerError::SeatEmpty(seat))?;
+        let total = player.chips + chips;
+        if total > self.max_buy_in {
+            return Err(PokerError::BuyInOutOfRange { chips: total, min: self.min_buy_in, max: self.max_buy_in });
+        }
+        let (player_id, name) = (player.id, player.name.clone());
+        self.game.add_chips(seat, chips)?;
+        self.buy(player_id, &name, chips);
+        Ok(())
+    }
+
+    // Plays one hand, then lets everyone rebuy or top up by their policy.
+    // Returns false without playing when fewer than two players are sitting in.
+    pub fn play_hand(&mut self) -> Result<bool, PokerError> {
+        if self.game.table().dealt_in().len() < 2 {
+            return Ok(false);
+        }
+        self.game.play_round()?;
+        self.hands_played += 1;
+        if let Some(hand) = self.game.last_hand().cloned() {
+            self.rake += hand.rake as u64;
+            for seat in &hand.seats {
+                if let Some(account) = self.account_mut(seat.player_id) {
+                    account.rake_dealt += hand.rake_paid(seat.player_id, RakeAttribution::Dealt);
+                    account.rake_contributed += hand.rake_paid(seat.player_id, RakeAttribution::Contributed);
+                }
+            }
+        }
+
+        let seated: Vec<(usize, usize, u32)> = self.game.table().occupied().map(|(seat, p)| (seat, p.id, p.chips)).collect();
+        for (seat, player_id, chips) in seated {
+            let target = match self.rebuy(player_id) {
+                Rebuy::Never if chips == 0 => {
+                    self.leave(seat);
+                    continue;
+                },
+                Rebuy::WhenBusted(buy_in) if chips == 0 => buy_in,
+                Rebuy::TopUp(target) if chips < target => target,
+                _ => continue,
+            };
+            let target = target.clamp(if chips == 0 { self.min_buy_in } else { 0 }, self.max_buy_in);
+            if target > chips {
+                self.top_up(seat, target - chips)?;
+            }
+            if chips == 0 {
+                self.game.sit_in(seat)?;
+            }
+        }
+        Ok(true)
+    }
+
+    // plays this many hands, or fewer if the table runs short of players
+    pub fn play(&mut self, hands: u64) -> Result<(), PokerError> {
+        for _ in 0..hands {
+            if !self.play_hand()? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // everyone who has played, in the order they first sat down
+    pub fn results(&self) -> Vec<CashResult> {
+        self.accounts
+            .iter()
+            .map(|account| {
+                let chips = self.game.table().seat_of(account.player_id).and_then(|seat| self.game.table().player(seat)).map_or(0, |p| p.chips);
+                CashResult { chips, ..account.clone() }
+            })
+            .collect()
+    }
+
+    fn account_mut(&mut self, player_id: usize) -> Option<&mut CashResult> {
+        self.accounts.iter_mut().find(|a| a.player_id == player_id)
+    }
+
+    fn buy(&mut self, player_id: usize, name: &str, chips: u32) {
+        if self.account_mut(player_id).is_none() {
+            self.accounts.push(CashResult { player_id, name: name.to_string(), bought_in: 0, cashed_out: 0, chips: 0, buy_ins: 0, rake_dealt: 0.0, rake_contributed: 0.0 });
+        }
+        let account = self.account_mut(player_id).unwrap();
+        account.bought_in += chips as u64;
+        account.buy_ins += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Card;
+    use crate::rake::Rake;
+    use crate::strategy::{Aggressive, CallingStation};
+
+    fn cash_game(stacks: &[u32]) -> CashGame {
+        let players = stacks.iter().enumerate().map(|(id, &chips)| Player::new(id, format!("P{}", id + 1), chips)).collect();
+        let mut game = Game::with_players(players, 1, 2).unwrap();
+        game.set_verbose(false);
+        for id in 0..stacks.len() {
+            game.set_strategy(id, Box::new(CallingStation));
+        }
+        CashGame::new(game, 50, 200).unwrap()
+    }
+
+    #[test]
+    fn test_buy_in_limits() {
+        let game = Game::with_players(vec![Player::new(0, "A".to_string(), 300)], 1, 2).unwrap();
+        assert_eq!(CashGame::new(game, 50, 200).err(), Some(PokerError::BuyInOutOfRange { chips: 300, min: 50, max: 200 }));
+
+        let mut cash_game = cash_game(&[100, 100]);
+        cash_game.game_mut().set_max_seats(3);
+        assert!(cash_game.sit(3, Player::new(2, "C".to_string(), 20)).is_err());
+        cash_game.sit(3, Player::new(2, "C".to_string(), 50)).unwrap();
+        assert!(cash_game.top_up(3, 151).is_err());
+        cash_game.top_up(3, 150).unwrap();
+        assert_eq!(cash_game.game().table().player(3).unwrap().chips, 200);
+        assert_eq!(cash_game.results()[2].bought_in, 200);
+        assert_eq!(cash_game.results()[2].buy_ins, 2);
+    }
+
+    #[test]
+    fn test_rebuys_and_results() {
+        let mut cash_game = cash_game(&[100, 100, 100, 60, 50]);
+        cash_game.set_rebuy(1, Rebuy::WhenBusted(80));
+        cash_game.set_rebuy(3, Rebuy::TopUp(100));
+        cash_game.set_rebuy(4, Rebuy::TopUp(120));
+        // everyone but the sitting out player shoves into aces
+        cash_game.sit_out(5).unwrap();
+        let cards = |codes: &str| codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect::<Vec<_>>();
+        let game = cash_game.game_mut();
+        for id in 0..4 {
+            game.set_strategy(id, Box::new(Aggressive { pot_fraction: 100.0 }));
+        }
+        let hole_cards = HashMap::from([(0, cards("Ah Ad")), (1, cards("7c 2d")), (2, cards("8c 3d")), (3, cards("9c 4d"))]);
+        game.force_cards(hole_cards, cards("Ks Qs 5h Jd 6c")).unwrap();
+        assert!(cash_game.play_hand().unwrap());
+
+        let table = cash_game.game().table();
+        // P2 bought back in, P3 left broke, P4 rebought by topping up and P5
+        // topped up while sitting out
+        assert_eq!(table.player(2).map(|p| p.chips), Some(80));
+        assert!(table.player(3).is_none());
+        assert_eq!(table.player(4).map(|p| p.chips), Some(100));
+        assert_eq!(table.player(5).map(|p| p.chips), Some(120));
+        assert_eq!(table.dealt_in(), [1, 2, 4]);
+
+        let nets: Vec<(usize, i64)> = cash_game.results().iter().map(|r| (r.player_id, r.net())).collect();
+        assert_eq!(nets, [(0, 260), (1, -100), (2, -100), (3, -60), (4, 0)]);
+        assert_eq!(cash_game.results()[2].cashed_out, 0);
+
+        cash_game.game_mut().set_strategy(0, Box::new(CallingStation));
+        cash_game.game_mut().set_strategy(1, Box::new(CallingStation));
+        cash_game.game_mut().set_strategy(3, Box::new(CallingStation));
+        cash_game.play(5).unwrap();
+        assert_eq!(cash_game.hands_played(), 6);
+        // no rake, so the table's results always come to nothing
+        assert_eq!(cash_game.results().iter().map(|r| r.net()).sum::<i64>(), 0);
+    }
+
+    #[test]
+    fn test_rake() {
+        let mut cash_game = cash_game(&[100, 100, 100]);
+        cash_game.game_mut().set_rake(Rake { cap: Some(4), ..Rake::percent(5.0) });
+        cash_game.game_mut().set_strategy(0, Box::new(Aggressive { pot_fraction: 1.0 }));
+        cash_game.set_rebuy(0, Rebuy::TopUp(100));
+        cash_game.set_rebuy(1, Rebuy::TopUp(100));
+        cash_game.set_rebuy(2, Rebuy::TopUp(100));
+        cash_game.play(20).unwrap();
+        let results = cash_game.results();
+        assert!(cash_game.rake() > 0);
+        // whatever the players lost went to the house
+        assert_eq!(results.iter().map(|r| r.net()).sum::<i64>(), -(cash_game.rake() as i64));
+        let dealt: f64 = results.iter().map(|r| r.rake_dealt).sum();
+        let contributed: f64 = results.iter().map(|r| r.rake_contributed).sum();
+        assert!((dealt - cash_game.rake() as f64).abs() < 1e-6);
+        assert!((contributed - cash_game.rake() as f64).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,355 @@
+use std::fs;
+use std::io::Write;
+use std::str::FromStr;
+
+use crate::deck::Card;
+use crate::equity::equity;
+use crate::config::{OutputConfig, SeatConfig, TableConfig};
+use crate::history::{parse_json_hands, HandRecord};
+use crate::phh;
+use crate::pokerstars::{self, describe_hand, format_cards};
+use crate::replay::verify;
+use crate::rng;
+use crate::strategy;
+use crate::utils::HandComparator;
+
+pub const USAGE: &str = "\
+usage: poker <command> [options]
+
+commands:
+  simulate   play hands between simulated players (the default)
+      --config FILE        read the table, seats and outputs from a TOML
+                           file instead of the options below
+      --players N          number of players (5)
+      --stack N            starting stack of every player (10000)
+      --small-blind N      (1)
+      --big-blind N        (2)
+      --rounds N           hands to play (10000)
+      --seed N             repeat the exact same hands on every run
+      --strategy S[,S...]  random, call, raise or tight, optionally with a bet
+                           size as a pot fraction like raise:0.75; one for
+                           everyone or one per player (random)
+      --format F           text, pokerstars, phh, ohh or json (text)
+      --output FILE        write hand histories here instead of stdout
+      --hero N             the seat a pokerstars history is written for, the
+                           only one whose hole cards it deals face up
+  equity HAND HAND... [--board CARDS] [--samples N] [--seed N]
+             each hand's share of the pot, e.g. equity AhAd KcKs --board 2c7d9h
+  eval CARDS
+             the best five card hand, e.g. eval Ah Kh Qh Jh Th 2c 3d
+  replay FILE
+             replay a PokerStars, PHH or JSON hand history file through the
+             engine and check every hand comes out the same
+  help       show this message
+";
+
+// `--name value` or `--name=value` options, and everything else in order
+struct Options {
+    positional: Vec<String>,
+    named: Vec<(String, String)>,
+}
+
+impl Options {
+    fn parse(args: &[String], known: &[&str]) -> Result<Options, String> {
+        let mut options = Options { positional: Vec::new(), named: Vec::new() };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                options.positional.push(arg.clone());
+                continue;
+            };
+            let (name, value) = match name.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None => {
+                    let value = args.next().ok_or_else(|| format!("--{} needs a value", name))?;
+                    (name.to_string(), value.clone())
+                },
+            };
+            if !known.contains(&name.as_str()) {
+                return Err(format!("unknown option --{}", name));
+            }
+            options.named.push((name, value));
+        }
+        Ok(options)
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.named.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    fn number<T: FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.get(name) {
+            Some(value) => value.parse().map_err(|_| format!("--{} expects a number, not '{}'", name, value)),
+            None => Ok(default),
+        }
+    }
+}
+
+// cards written together or apart, e.g. "AhKh", "Ah Kh" or "Ah,Kh"
+pub fn parse_cards(text: &str) -> Result<Vec<Card>, String> {
+    let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace() && *c != ',').collect();
+    if !chars.len().is_multiple_of(2) {
+        return Err(format!("'{}' is not a list of cards like AhKd", text));
+    }
+    chars
+        .chunks(2)
+        .map(|code| {
+            let code: String = code.iter().collect();
+            Card::from_code(&code).ok_or_else(|| format!("invalid card '{}'", code))
+        })
+        .collect()
+}
+
+pub fn run(args: &[String], out: &mut dyn Write) -> Result<(), String> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => ("simulate", args),
+    };
+    match command {
+        "simulate" => simulate(rest, out),
+        "equity" => show_equity(rest, out),
+        "eval" => eval(rest, out),
+        "replay" => replay(rest, out),
+        "help" | "--help" | "-h" => write!(out, "{}", USAGE).map_err(|err| err.to_string()),
+        command => Err(format!("unknown command '{}'\n\n{}", command, USAGE)),
+    }
+}
+
+fn simulate(args: &[String], out: &mut dyn Write) -> Result<(), String> {
+    let options = Options::parse(
+        args,
+        &["config", "players", "stack", "small-blind", "big-blind", "rounds", "seed", "strategy", "format", "output", "hero"],
+    )?;
+    if let Some(arg) = options.positional.first() {
+        return Err(format!("unexpected argument '{}'", arg));
+    }
+    let config = match options.get("config") {
+        Some(path) => {
+            if options.named.len() > 1 {
+                return Err("--config cannot be combined with other options".to_string());
+            }
+            TableConfig::load(path)?
+        },
+        None => table_from_options(&options)?,
+    };
+    let (game, played) = config.run()?;
+
+    // the summary would break a hand history written to stdout
+    if config.outputs.iter().all(|o| o.format == "text" || o.path.is_some()) {
+        writeln!(out, "Played {} hands", played).map_err(|err| err.to_string())?;
+        for player in game.players() {
+            writeln!(out, "{}: {}", player.name, player.chips).map_err(|err| err.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+fn table_from_options(options: &Options) -> Result<TableConfig, String> {
+    let n_players: usize = options.number("players", 5)?;
+    let stack: u32 = options.number("stack", 10000)?;
+    if n_players < 2 {
+        return Err("a game needs at least two players".to_string());
+    }
+    let specs: Vec<&str> = options.get("strategy").unwrap_or("random").split(',').collect();
+    if specs.len() != 1 && specs.len() != n_players {
+        return Err(format!("{} strategies given for {} players", specs.len(), n_players));
+    }
+    let seats = (0..n_players)
+        .map(|idx| {
+            let (strategy, pot_fraction) = strategy::parse_spec(specs[idx % specs.len()])?;
+            Ok(SeatConfig { name: format!("Player {}", idx + 1), stack, strategy: strategy.to_string(), pot_fraction })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let config = TableConfig {
+        small_blind: options.number("small-blind", 1)?,
+        big_blind: options.number("big-blind", 2)?,
+        hands: options.number("rounds", 10000)?,
+        seed: options.get("seed").map(|_| options.number("seed", 0)).transpose()?,
+        seats,
+        outputs: vec![OutputConfig {
+            format: options.get("format").unwrap_or("text").to_string(),
+            path: options.get("output").map(|path| path.to_string()),
+            hero: options.get("hero").map(|_| options.number("hero", 0)).transpose()?,
+        }],
+        ..TableConfig::default()
+    };
+    config.validate()?;
+    Ok(config)
+}
+
+fn show_equity(args: &[String], out: &mut dyn Write) -> Result<(), String> {
+    let options = Options::parse(args, &["board", "samples", "seed"])?;
+    let hands = options
+        .positional
+        .iter()
+        .map(|text| {
+            let cards = parse_cards(text)?;
+            <[Card; 2]>::try_from(cards).map_err(|_| format!("'{}' is not a two card hand", text))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if hands.len() < 2 {
+        return Err("equity needs at least two hands".to_string());
+    }
+    let board = parse_cards(options.get("board").unwrap_or(""))?;
+    let samples: u32 = options.number("samples", 100_000)?;
+    if let Some(seed) = options.get("seed") {
+        rng::seed(seed.parse().map_err(|_| format!("--seed expects a number, not '{}'", seed))?);
+    }
+    let result = equity(&hands, &board, samples);
+    rng::unseed();
+    let shares = result.map_err(|err| err.to_string())?;
+    for (hand, share) in hands.iter().zip(shares) {
+        writeln!(out, "{}  {:6.2}%", format_cards(hand), share * 100.0).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+fn eval(args: &[String], out: &mut dyn Write) -> Result<(), String> {
+    let cards = parse_cards(&args.join(" "))?;
+    if cards.len() < 5 || cards.len() > 7 {
+        return Err(format!("eval takes 5 to 7 cards, not {}", cards.len()));
+    }
+    let mut seen = Vec::new();
+    for card in &cards {
+        if seen.contains(card) {
+            return Err(format!("{} is given twice", card.code()));
+        }
+        seen.push(*card);
+    }
+    let (best, hand) = HandComparator::best_hand(cards);
+    writeln!(out, "{} ({})", format_cards(&best), describe_hand(&best, hand)).map_err(|err| err.to_string())
+}
+
+// PokerStars text, our JSON lines or PHH, judged by how the file starts
+pub fn read_hands(text: &str) -> Result<Vec<HandRecord>, String> {
+    let start = text.trim_start_matches('\u{feff}').trim_start();
+    if start.starts_with("PokerStars") {
+        pokerstars::parse_hands(text)
+    } else if start.starts_with('{') {
+        if start.starts_with("{\"ohh\"") {
+            return Err("OHH files can be written but not replayed".to_string());
+        }
+        parse_json_hands(text)
+    } else {
+        phh::parse_hands(text)
+    }
+}
+
+fn replay(args: &[String], out: &mut dyn Write) -> Result<(), String> {
+    let [path] = args else {
+        return Err("replay takes one hand history file".to_string());
+    };
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    let hands = read_hands(&text)?;
+    let mut failed = 0;
+    for hand in &hands {
+        match verify(hand) {
+            Ok(()) => writeln!(out, "Hand #{}: ok", hand.hand_id),
+            Err(err) => {
+                failed += 1;
+                writeln!(out, "Hand #{}: {}", hand.hand_id, err)
+            },
+        }
+        .map_err(|err| err.to_string())?;
+    }
+    if failed > 0 {
+        return Err(format!("{} of {} hands did not replay the same", failed, hands.len()));
+    }
+    writeln!(out, "All {} hands replayed the same", hands.len()).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_args(args: &str) -> Result<String, String> {
+        let args: Vec<String> = args.split_whitespace().map(|a| a.to_string()).collect();
+        let mut out = Vec::new();
+        run(&args, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir().join(format!("poker-cli-{}-{}", std::process::id(), name)).display().to_string()
+    }
+
+    #[test]
+    fn test_eval() {
+        assert_eq!(run_args("eval Ah Kh Qh Jh Th 2c 3d").unwrap(), "Ah Kh Qh Jh Th (a Royal Flush)\n");
+        assert_eq!(run_args("eval 2c2d7h7s9c").unwrap(), "7h 7s 2c 2d 9c (two pair, Sevens and Twos)\n");
+        assert!(run_args("eval Ah Kh").is_err());
+        assert_eq!(run_args("eval Ah Ah Qh Jh Th").unwrap_err(), "Ah is given twice");
+        assert_eq!(run_args("eval Ah Kh Qh Jh Zz").unwrap_err(), "invalid card 'Zz'");
+    }
+
+    #[test]
+    fn test_equity() {
+        let text = run_args("equity AhAd KhKd --board 2c7d9hJs3s").unwrap();
+        assert_eq!(text, "Ah Ad  100.00%\nKh Kd    0.00%\n");
+        assert!(run_args("equity AhAd --board 2c7d9h").is_err());
+        assert!(run_args("equity AhAd AhKd").unwrap_err().contains("Ah is not in the deck"));
+        let seeded = run_args("equity AhAd 7c2d --samples 500 --seed 3").unwrap();
+        assert_eq!(seeded, run_args("equity AhAd 7c2d --samples 500 --seed 3").unwrap());
+    }
+
+    #[test]
+    fn test_simulate_and_replay() {
+        let path = temp_path("hands.json");
+        let summary = run_args(&format!(
+            "simulate --players 4 --stack 200 --rounds 20 --seed 11 --strategy call,raise:0.75,tight,random --format json --output {}",
+            path
+        ))
+        .unwrap();
+        assert!(summary.starts_with("Played "));
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(!text.is_empty());
+        let replayed = run_args(&format!("replay {}", path)).unwrap();
+        assert!(replayed.ends_with("hands replayed the same\n"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_simulate_formats() {
+        for format in ["pokerstars", "phh", "ohh"] {
+            let path = temp_path(format);
+            run_args(&format!("simulate --players 3 --stack 100 --rounds 3 --format {} --output {}", format, path)).unwrap();
+            let text = fs::read_to_string(&path).unwrap();
+            fs::remove_file(&path).unwrap();
+            if format != "ohh" {
+                assert!(!read_hands(&text).unwrap().is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_simulate_config() {
+        let (config, hands) = (temp_path("table.toml"), temp_path("config.txt"));
+        let table = "hands = 5\nseed = 2\n[[seats]]\nname = \"Ann\"\nstack = 300\nstrategy = \"call\"\n\
+                     [[seats]]\nname = \"Ben\"\nstack = 300\n[[outputs]]\nformat = \"pokerstars\"\npath = \"PATH\"\n";
+        fs::write(&config, table.replace("PATH", &hands)).unwrap();
+        let summary = run_args(&format!("simulate --config {}", config)).unwrap();
+        assert!(summary.starts_with("Played "));
+        let written = read_hands(&fs::read_to_string(&hands).unwrap()).unwrap();
+        assert_eq!(written[0].seats.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["Ann", "Ben"]);
+        assert_eq!(
+            run_args(&format!("simulate --config {} --players 3", config)).unwrap_err(),
+            "--config cannot be combined with other options"
+        );
+        fs::remove_file(&config).unwrap();
+        fs::remove_file(&hands).unwrap();
+        assert!(run_args(&format!("simulate --config {}", config)).unwrap_err().starts_with(&config));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(run_args("shuffle").unwrap_err().starts_with("unknown command 'shuffle'"));
+        assert_eq!(run_args("simulate --players").unwrap_err(), "--players needs a value");
+        assert_eq!(run_args("simulate --players many").unwrap_err(), "--players expects a number, not 'many'");
+        assert_eq!(run_args("simulate --colour red").unwrap_err(), "unknown option --colour");
+        assert_eq!(run_args("simulate --players 3 --strategy call,call").unwrap_err(), "2 strategies given for 3 players");
+        assert_eq!(run_args("simulate --format xml").unwrap_err(), "unknown format 'xml', expected text, pokerstars, phh, ohh or json");
+        assert!(run_args("simulate --players 30").unwrap_err().contains("at most 22"));
+        assert!(run_args("replay").is_err());
+        assert!(run_args("help").unwrap().starts_with("usage: poker"));
+    }
+}
@@ -0,0 +1,536 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+
+use crate::betting::BettingStructure;
+use crate::game::{Ante, ButtonRule, Game, Straddle};
+use crate::history::{HandHistoryWriter, JsonWriter};
+use crate::ohh::OhhWriter;
+use crate::phh::PhhWriter;
+use crate::player::Player;
+use crate::pokerstars::PokerStarsWriter;
+use crate::rake::Rake;
+use crate::rng;
+use crate::strategy;
+use crate::toml::{self, Table, Value};
+use crate::variant::Variant;
+
+// A table definition, usually read from a TOML file such as
+//
+//     hands = 1000
+//     seed = 7
+//
+//     [table]
+//     name = "Home game"
+//     variant = "holdem"     # or "omaha", "omaha-hi-lo", "short-deck", "razz", "2-7-triple-draw", "badugi", ...
+//     betting = "no-limit"    # or "pot-limit", or "fixed-limit"
+//     raise_cap = 4           # bets a street allows in fixed limit
+//     button = "dead"         # or "moving"
+//     run_it = 2              # the most runs of the board when all in
+//
+//     [blinds]
+//     small = 5
+//     big = 10
+//     ante = 1                # or big_blind_ante = 10
+//     straddle = "utg"        # or "button", or "none"
+//
+//     [rake]
+//     percent = 5.0
+//     cap = 3                 # the most taken from a hand
+//     caps_by_players = [[2, 1], [5, 2]]
+//     no_flop_no_drop = true
+//
+//     [[seats]]
+//     name = "Alice"
+//     stack = 1000
+//     strategy = { name = "raise", pot_fraction = 0.75 }
+//
+//     [[seats]]
+//     name = "Bob"
+//     stack = 1000
+//     strategy = "call"
+//
+//     [[outputs]]
+//     format = "pokerstars"
+//     path = "hands.txt"
+//     hero = 1                # whose hole cards the history shows
+//
+// so an experiment can be checked in and rerun exactly.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TableConfig {
+    pub name: String,
+    pub max_seats: Option<usize>,
+    pub variant: String,
+    pub betting: String,
+    pub raise_cap: Option<u32>,
+    pub button_rule: ButtonRule,
+    pub run_it: u32,
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub ante: Ante,
+    pub straddle: Straddle,
+    pub rake: Rake,
+    pub hands: usize,
+    pub seed: Option<u64>,
+    pub seats: Vec<SeatConfig>,
+    pub outputs: Vec<OutputConfig>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SeatConfig {
+    pub name: String,
+    pub stack: u32,
+    pub strategy: String,
+    pub pot_fraction: Option<f64>,
+}
+
+// where hand histories go; no path means stdout
+#[derive(Debug, PartialEq, Clone)]
+pub struct OutputConfig {
+    pub format: String,
+    pub path: Option<String>,
+    // the seat a pokerstars history is written for, see PokerStarsWriter
+    pub hero: Option<usize>,
+}
+
+impl Default for TableConfig {
+    fn default() -> TableConfig {
+        TableConfig {
+            name: "Table 1".to_string(),
+            max_seats: None,
+            variant: "holdem".to_string(),
+            betting: "no-limit".to_string(),
+            raise_cap: None,
+            button_rule: ButtonRule::Moving,
+            run_it: 1,
+            small_blind: 1,
+            big_blind: 2,
+            ante: Ante::None,
+            straddle: Straddle::None,
+            rake: Rake::none(),
+            hands: 10000,
+            seed: None,
+            seats: Vec::new(),
+            outputs: vec![OutputConfig { format: "text".to_string(), path: None, hero: None }],
+        }
+    }
+}
+
+fn check_keys(table: &Table, section: &str, allowed: &[&str]) -> Result<(), String> {
+    match table.keys().find(|key| !allowed.contains(&key.as_str())) {
+        Some(key) => Err(format!("unknown key '{}' in {}", key, section)),
+        None => Ok(()),
+    }
+}
+
+fn string(table: &Table, key: &str, section: &str) -> Result<Option<String>, String> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(value) => value
+            .as_str()
+            .map(|s| Some(s.to_string()))
+            .ok_or_else(|| format!("{}.{} must be a string", section, key)),
+    }
+}
+
+fn number<T: TryFrom<i64>>(table: &Table, key: &str, section: &str) -> Result<Option<T>, String> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(value) => value
+            .as_integer()
+            .and_then(|i| T::try_from(i).ok())
+            .map(Some)
+            .ok_or_else(|| format!("{}.{} must be a whole number", section, key)),
+    }
+}
+
+fn tables<'a>(doc: &'a Table, key: &str) -> Result<Vec<&'a Table>, String> {
+    match doc.get(key) {
+        None => Ok(Vec::new()),
+        Some(value) => value
+            .as_array()
+            .and_then(|items| items.iter().map(|item| item.as_table()).collect())
+            .ok_or_else(|| format!("'{}' must be written as [[{}]] tables", key, key)),
+    }
+}
+
+impl TableConfig {
+    pub fn parse(text: &str) -> Result<TableConfig, String> {
+        let doc = toml::parse(text)?;
+        check_keys(&doc, "the file", &["hands", "seed", "table", "blinds", "rake", "seats", "outputs"])?;
+        let mut config = TableConfig::default();
+        config.hands = number(&doc, "hands", "the file")?.unwrap_or(config.hands);
+        config.seed = number(&doc, "seed", "the file")?;
+
+        let empty = Table::new();
+        let section = |key: &str| match doc.get(key) {
+            None => Ok(&empty),
+            Some(value) => value.as_table().ok_or_else(|| format!("'{}' must be a table", key)),
+        };
+
+        let table = section("table")?;
+        check_keys(table, "[table]", &["name", "max_seats", "variant", "betting", "raise_cap", "button", "run_it"])?;
+        config.name = string(table, "name", "table")?.unwrap_or(config.name);
+        config.max_seats = number(table, "max_seats", "table")?;
+        config.variant = string(table, "variant", "table")?.unwrap_or(config.variant);
+        config.betting = string(table, "betting", "table")?.unwrap_or(config.betting);
+        config.raise_cap = number(table, "raise_cap", "table")?;
+        config.button_rule = match string(table, "button", "table")?.as_deref() {
+            None | Some("moving") => ButtonRule::Moving,
+            Some("dead") => ButtonRule::Dead,
+            Some(other) => return Err(format!("unknown button rule '{}', expected moving or dead", other)),
+        };
+        config.run_it = match number(table, "run_it", "table")? {
+            None => 1,
+            Some(0) => return Err("run_it must be at least 1".to_string()),
+            Some(times) => times,
+        };
+
+        let blinds = section("blinds")?;
+        check_keys(blinds, "[blinds]", &["small", "big", "ante", "big_blind_ante", "straddle"])?;
+        config.small_blind = number(blinds, "small", "blinds")?.unwrap_or(config.small_blind);
+        config.big_blind = number(blinds, "big", "blinds")?.unwrap_or(config.big_blind);
+        config.ante = match (number(blinds, "ante", "blinds")?, number(blinds, "big_blind_ante", "blinds")?) {
+            (Some(_), Some(_)) => return Err("use either an ante or a big blind ante, not both".to_string()),
+            (Some(ante), None) => Ante::Everyone(ante),
+            (None, Some(ante)) => Ante::BigBlind(ante),
+            (None, None) => Ante::None,
+        };
+        config.straddle = match string(blinds, "straddle", "blinds")?.as_deref() {
+            None | Some("none") => Straddle::None,
+            Some("utg") => Straddle::UnderTheGun,
+            Some("button") => Straddle::Button,
+            Some(other) => return Err(format!("unknown straddle '{}', expected none, utg or button", other)),
+        };
+
+        let rake = section("rake")?;
+        check_keys(rake, "[rake]", &["percent", "cap", "caps_by_players", "no_flop_no_drop"])?;
+        if let Some(value) = rake.get("percent") {
+            let percent = value.as_float().filter(|p| (0.0..=100.0).contains(p)).ok_or("rake.percent must be a number from 0 to 100")?;
+            config.rake = Rake::percent(percent);
+        }
+        config.rake.cap = number(rake, "cap", "rake")?;
+        if let Some(value) = rake.get("caps_by_players") {
+            config.rake.caps_by_players = value
+                .as_array()
+                .and_then(|caps| {
+                    caps.iter()
+                        .map(|cap| match cap.as_array().map(|pair| pair.as_slice()) {
+                            Some([players, cap]) => Some((usize::try_from(players.as_integer()?).ok()?, u32::try_from(cap.as_integer()?).ok()?)),
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or("rake.caps_by_players must be a list of [players, cap] pairs")?;
+            config.rake.caps_by_players.sort_unstable();
+        }
+        if let Some(value) = rake.get("no_flop_no_drop") {
+            config.rake.no_flop_no_drop = value.as_bool().ok_or("rake.no_flop_no_drop must be true or false")?;
+        }
+
+        for (idx, seat) in tables(&doc, "seats")?.into_iter().enumerate() {
+            let name = format!("seat {}", idx + 1);
+            check_keys(seat, &name, &["name", "stack", "strategy"])?;
+            let (strategy, pot_fraction) = match seat.get("strategy") {
+                None => ("random".to_string(), None),
+                Some(Value::String(strategy)) => (strategy.clone(), None),
+                Some(Value::Table(params)) => {
+                    let section = format!("{} strategy", name);
+                    check_keys(params, &section, &["name", "pot_fraction"])?;
+                    let fraction = match params.get("pot_fraction") {
+                        None => None,
+                        Some(value) => Some(value.as_float().ok_or_else(|| format!("{}.pot_fraction must be a number", section))?),
+                    };
+                    let strategy = string(params, "name", &section)?.ok_or_else(|| format!("{} needs a name", section))?;
+                    (strategy, fraction)
+                },
+                Some(_) => return Err(format!("{}.strategy must be a name or a table", name)),
+            };
+            config.seats.push(SeatConfig {
+                name: string(seat, "name", &name)?.unwrap_or_else(|| format!("Player {}", idx + 1)),
+                stack: number(seat, "stack", &name)?.ok_or_else(|| format!("{} needs a stack", name))?,
+                strategy,
+                pot_fraction,
+            });
+        }
+
+        let outputs = tables(&doc, "outputs")?;
+        if !outputs.is_empty() {
+            config.outputs.clear();
+        }
+        for (idx, output) in outputs.into_iter().enumerate() {
+            let name = format!("output {}", idx + 1);
+            check_keys(output, &name, &["format", "path", "hero"])?;
+            config.outputs.push(OutputConfig {
+                format: string(output, "format", &name)?.ok_or_else(|| format!("{} needs a format", name))?,
+                path: string(output, "path", &name)?,
+                hero: number(output, "hero", &name)?,
+            });
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn load(path: &str) -> Result<TableConfig, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        TableConfig::parse(&text).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    pub fn variant(&self) -> Result<Variant, String> {
+        Variant::from_name(&self.variant).ok_or_else(|| format!("unknown variant '{}'", self.variant))
+    }
+
+    pub fn betting_structure(&self) -> Result<BettingStructure, String> {
+        let structure = BettingStructure::from_name(&self.betting).ok_or_else(|| {
+            format!("unknown betting structure '{}', expected no-limit, pot-limit or fixed-limit", self.betting)
+        })?;
+        match (structure, self.raise_cap) {
+            (structure, None) => Ok(structure),
+            (BettingStructure::FixedLimit { .. }, Some(cap)) if cap > 0 => Ok(BettingStructure::FixedLimit { cap }),
+            (BettingStructure::FixedLimit { .. }, Some(_)) => Err("a raise cap must allow at least one bet".to_string()),
+            (_, Some(_)) => Err("raise_cap only applies to fixed-limit betting".to_string()),
+        }
+    }
+
+    // catches what the engine cannot play before any hand is dealt
+    pub fn validate(&self) -> Result<(), String> {
+        let variant = self.variant()?;
+        self.betting_structure()?;
+        if self.seats.len() < 2 {
+            return Err("a table needs at least two seats".to_string());
+        }
+        if let Some(max_seats) = self.max_seats && max_seats < self.seats.len() {
+            return Err(format!("{} seats listed for a {}-max table", self.seats.len(), max_seats));
+        }
+        if self.seats.len() > variant.max_players() {
+            return Err(format!("{} seats listed but {} deals to at most {}", self.seats.len(), variant.name(), variant.max_players()));
+        }
+        for seat in &self.seats {
+            strategy::build(&seat.strategy, seat.pot_fraction)?;
+        }
+        for output in &self.outputs {
+            if !["text", "pokerstars", "phh", "ohh", "json"].contains(&output.format.as_str()) {
+                return Err(format!("unknown format '{}', expected text, pokerstars, phh, ohh or json", output.format));
+            }
+            if output.format == "text" && output.path.is_some() {
+                return Err("text output only goes to the terminal".to_string());
+            }
+            match output.hero {
+                Some(_) if output.format != "pokerstars" => return Err("only pokerstars output is written for a hero".to_string()),
+                Some(seat) if seat < 1 || seat > self.seats.len() => return Err(format!("the hero's seat {} is not one of seats 1 to {}", seat, self.seats.len())),
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    // A game with these seats, blinds and strategies, writing to the outputs.
+    // Files are created here.
+    pub fn build_game(&self) -> Result<Game, String> {
+        self.validate()?;
+        let players = self
+            .seats
+            .iter()
+            .enumerate()
+            .map(|(idx, seat)| Player::new(idx, seat.name.clone(), seat.stack))
+            .collect();
+        let mut game = Game::with_players(players, self.small_blind, self.big_blind).map_err(|err| err.to_string())?;
+        game.set_button_rule(self.button_rule);
+        // a short deck table without an ante gets the variant's own
+        game.set_ante(self.ante);
+        game.set_variant(self.variant()?).map_err(|err| err.to_string())?;
+        game.set_betting_structure(self.betting_structure()?).map_err(|err| err.to_string())?;
+        game.set_straddle(self.straddle);
+        game.set_rake(self.rake.clone());
+        game.set_run_it(self.run_it);
+        game.set_table_name(&self.name);
+        if let Some(max_seats) = self.max_seats {
+            game.set_max_seats(max_seats);
+        }
+        for (idx, seat) in self.seats.iter().enumerate() {
+            if let Some(strategy) = strategy::build(&seat.strategy, seat.pot_fraction)? {
+                game.set_strategy(idx, strategy);
+            }
+        }
+        game.set_verbose(self.outputs.iter().any(|o| o.format == "text"));
+        for output in self.outputs.iter().filter(|o| o.format != "text") {
+            let sink: Box<dyn Write> = match &output.path {
+                Some(path) => Box::new(BufWriter::new(File::create(path).map_err(|err| format!("{}: {}", path, err))?)),
+                None => Box::new(io::stdout()),
+            };
+            let writer: Box<dyn HandHistoryWriter> = match output.format.as_str() {
+                "pokerstars" => match output.hero {
+                    Some(seat) => Box::new(PokerStarsWriter::with_hero(sink, seat)),
+                    None => Box::new(PokerStarsWriter::new(sink)),
+                },
+                "phh" => Box::new(PhhWriter::new(sink)),
+                "ohh" => Box::new(OhhWriter::new(sink)),
+                _ => Box::new(JsonWriter::new(sink)),
+            };
+            game.add_history_writer(writer).map_err(|err| err.to_string())?;
+        }
+        Ok(game)
+    }
+
+    // Plays the configured number of hands, or until one player has every
+    // chip, and returns the game with how many hands were played.
+    pub fn run(&self) -> Result<(Game, usize), String> {
+        let mut game = self.build_game()?;
+        if let Some(seed) = self.seed {
+            rng::seed(seed);
+        }
+        let mut played = 0;
+        let mut result = Ok(());
+        for _ in 0..self.hands {
+            if game.players().len() <= 1 {
+                break;
+            }
+            result = game.play_round().map_err(|err| err.to_string());
+            if result.is_err() {
+                break;
+            }
+            played += 1;
+            // the hand was played, but the output is incomplete
+            if let Some(err) = game.history_errors().first() {
+                result = Err(err.to_string());
+                break;
+            }
+        }
+        rng::unseed();
+        result.map(|_| (game, played))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"
+hands = 25
+seed = 3
+
+[table]
+name = "Experiment"
+max_seats = 6
+
+[blinds]
+small = 5
+big = 10
+
+[[seats]]
+name = "Alice"
+stack = 1000
+strategy = { name = "raise", pot_fraction = 0.75 }
+
+[[seats]]
+name = "Bob"
+stack = 500
+strategy = "call"
+
+[[seats]]
+stack = 800
+
+[[outputs]]
+format = "json"
+path = "PATH"
+"#;
+
+    #[test]
+    fn test_parse() {
+        let config = TableConfig::parse(EXAMPLE).unwrap();
+        assert_eq!(config.hands, 25);
+        assert_eq!(config.seed, Some(3));
+        assert_eq!(config.name, "Experiment");
+        assert_eq!(config.max_seats, Some(6));
+        assert_eq!(config.button_rule, ButtonRule::Moving);
+        assert_eq!(config.run_it, 1);
+        assert_eq!(TableConfig::parse(&EXAMPLE.replace("max_seats = 6", "max_seats = 6\nrun_it = 2")).unwrap().run_it, 2);
+        assert_eq!((config.small_blind, config.big_blind), (5, 10));
+        assert_eq!((config.ante, config.straddle), (Ante::None, Straddle::None));
+        assert_eq!(config.seats[0], SeatConfig { name: "Alice".to_string(), stack: 1000, strategy: "raise".to_string(), pot_fraction: Some(0.75) });
+        assert_eq!(config.seats[1].strategy, "call");
+        assert_eq!(config.seats[2], SeatConfig { name: "Player 3".to_string(), stack: 800, strategy: "random".to_string(), pot_fraction: None });
+        assert_eq!(config.outputs, vec![OutputConfig { format: "json".to_string(), path: Some("PATH".to_string()), hero: None }]);
+    }
+
+    #[test]
+    fn test_parse_antes_and_straddles() {
+        let config = TableConfig::parse(&EXAMPLE.replace("big = 10", "big = 10\nbig_blind_ante = 10\nstraddle = \"button\"")).unwrap();
+        assert_eq!((config.ante, config.straddle), (Ante::BigBlind(10), Straddle::Button));
+        let config = TableConfig::parse(&EXAMPLE.replace("big = 10", "big = 10\nante = 1\nstraddle = \"utg\"")).unwrap();
+        assert_eq!((config.ante, config.straddle), (Ante::Everyone(1), Straddle::UnderTheGun));
+    }
+
+    #[test]
+    fn test_parse_betting() {
+        assert_eq!(TableConfig::parse(EXAMPLE).unwrap().betting_structure(), Ok(BettingStructure::NoLimit));
+        let config = TableConfig::parse(&EXAMPLE.replace("max_seats = 6", "max_seats = 6\nbetting = \"pot-limit\"")).unwrap();
+        assert_eq!(config.betting_structure(), Ok(BettingStructure::PotLimit));
+        let config = TableConfig::parse(&EXAMPLE.replace("max_seats = 6", "max_seats = 6\nbetting = \"fixed-limit\"\nraise_cap = 5")).unwrap();
+        assert_eq!(config.betting_structure(), Ok(BettingStructure::FixedLimit { cap: 5 }));
+        assert_eq!(TableConfig::parse(EXAMPLE).unwrap().variant(), Ok(Variant::Holdem));
+        let config = TableConfig::parse(&EXAMPLE.replace("max_seats = 6", "max_seats = 6\nvariant = \"omaha5\"\nbetting = \"pot-limit\"")).unwrap();
+        assert_eq!(config.variant(), Ok(Variant::Omaha { hole_cards: 5 }));
+        let config = TableConfig::parse(&EXAMPLE.replace("max_seats = 6", "max_seats = 6\nvariant = \"short-deck-trips\"")).unwrap();
+        assert_eq!(config.variant(), Ok(Variant::ShortDeck { trips_beat_straight: true }));
+    }
+
+    #[test]
+    fn test_parse_rake() {
+        assert_eq!(TableConfig::parse(EXAMPLE).unwrap().rake, Rake::none());
+        let rake = "[rake]\npercent = 4.5\ncap = 3\ncaps_by_players = [[5, 2], [2, 1]]\nno_flop_no_drop = true\n\n[[seats]]";
+        let config = TableConfig::parse(&EXAMPLE.replacen("[[seats]]", rake, 1)).unwrap();
+        assert_eq!(config.rake, Rake { basis_points: 450, cap: Some(3), caps_by_players: vec![(2, 1), (5, 2)], no_flop_no_drop: true });
+        let error = |rake: &str| TableConfig::parse(&EXAMPLE.replacen("[[seats]]", &format!("[rake]\n{}\n\n[[seats]]", rake), 1)).unwrap_err();
+        assert_eq!(error("percent = 150"), "rake.percent must be a number from 0 to 100");
+        assert_eq!(error("caps_by_players = [2, 1]"), "rake.caps_by_players must be a list of [players, cap] pairs");
+        assert_eq!(error("drop = true"), "unknown key 'drop' in [rake]");
+    }
+
+    #[test]
+    fn test_run_repeats_exactly() {
+        let path = std::env::temp_dir().join(format!("poker-config-{}.json", std::process::id()));
+        let config = TableConfig::parse(&EXAMPLE.replace("PATH", &path.display().to_string())).unwrap();
+
+        let (game, played) = config.run().unwrap();
+        assert!(played > 0 && played <= 25);
+        assert_eq!(game.players().iter().map(|p| p.chips).sum::<u32>(), 2300);
+        drop(game);
+        let first = crate::history::parse_json_hands(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(first.len(), played);
+        assert_eq!(first[0].table_name, "Experiment");
+        assert_eq!(first[0].max_seats, 6);
+
+        let (game, _) = config.run().unwrap();
+        drop(game);
+        let second = crate::history::parse_json_hands(&fs::read_to_string(&path).unwrap()).unwrap();
+        let strip = |hands: &[crate::history::HandRecord]| {
+            hands.iter().map(|h| (h.seats.clone(), h.actions.clone(), h.pots.clone())).collect::<Vec<_>>()
+        };
+        assert_eq!(strip(&first), strip(&second));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |from: &str, to: &str| TableConfig::parse(&EXAMPLE.replace(from, to)).unwrap_err();
+        assert_eq!(error("stack = 500", "stak = 500"), "unknown key 'stak' in seat 2");
+        assert_eq!(error("stack = 500", "stack = -5"), "seat 2.stack must be a whole number");
+        assert_eq!(error("max_seats = 6", "max_seats = 2"), "3 seats listed for a 2-max table");
+        assert_eq!(error("big = 10", "big = 10\nante = 1\nbig_blind_ante = 10"), "use either an ante or a big blind ante, not both");
+        assert_eq!(error("big = 10", "big = 10\nstraddle = \"double\""), "unknown straddle 'double', expected none, utg or button");
+        assert_eq!(error("max_seats = 6", "variant = \"courchevel\""), "unknown variant 'courchevel'");
+        assert_eq!(error("max_seats = 6", "betting = \"spread-limit\""), "unknown betting structure 'spread-limit', expected no-limit, pot-limit or fixed-limit");
+        assert_eq!(error("max_seats = 6", "raise_cap = 4"), "raise_cap only applies to fixed-limit betting");
+        assert_eq!(error("max_seats = 6", "betting = \"fixed-limit\"\nraise_cap = 0"), "a raise cap must allow at least one bet");
+        assert_eq!(error("max_seats = 6", "button = \"fixed\""), "unknown button rule 'fixed', expected moving or dead");
+        assert_eq!(error("max_seats = 6", "run_it = 0"), "run_it must be at least 1");
+        assert_eq!(error("\"call\"", "\"bluff\""), "unknown strategy 'bluff', expected one of random, call, raise, tight");
+        assert_eq!(error("format = \"json\"", "format = \"text\""), "text output only goes to the terminal");
+        assert_eq!(error("format = \"json\"", "format = \"json\"\nhero = 1"), "only pokerstars output is written for a hero");
+        assert_eq!(error("format = \"json\"", "format = \"pokerstars\"\nhero = 4"), "the hero's seat 4 is not one of seats 1 to 3");
+        let hero = TableConfig::parse(&EXAMPLE.replace("format = \"json\"", "format = \"pokerstars\"\nhero = 2")).unwrap();
+        assert_eq!(hero.outputs[0].hero, Some(2));
+        assert_eq!(error("stack = 800", ""), "seat 3 needs a stack");
+    }
+}
@@ -126,6 +126,42 @@ impl Add<u8> for Rank {
 #[derive(Debug, Hash, PartialEq, Eq, Copy, Clone)]
 pub struct Card(pub Rank, pub Suit);
 impl Card {
+    // two character form used by hand histories, e.g. "Td" or "As"
+    pub fn code(&self) -> String {
+        let rank = match self.0 {
+            Rank::Ten => 'T',
+            rank => String::from(rank).chars().next().unwrap(),
+        };
+        let suit = match self.1 {
+            Suit::Hearts => 'h',
+            Suit::Diamonds => 'd',
+            Suit::Clubs => 'c',
+            Suit::Spades => 's',
+        };
+        format!("{}{}", rank, suit)
+    }
+
+    pub fn from_code(code: &str) -> Option<Card> {
+        let mut chars = code.trim().chars();
+        let rank = match chars.next()?.to_ascii_uppercase() {
+            'T' => Rank::Ten,
+            c @ ('2'..='9' | 'J' | 'Q' | 'K' | 'A') => Rank::from(c.to_string()),
+            _ => return None,
+        };
+        let suit = match chars.next()?.to_ascii_lowercase() {
+            'h' => Suit::Hearts,
+            'd' => Suit::Diamonds,
+            'c' => Suit::Clubs,
+            's' => Suit::Spades,
+            _ => return None,
+        };
+        if chars.next().is_some() {
+            return None;
+        }
+        Some(Card(rank, suit))
+    }
+
+    #[allow(clippy::useless_format)]
     pub fn get_display_lines(&self) -> [String;8] {
         let first_line = format!("_________");
         let (second_line, third_line,fourth_line, fifth_line, sixth_line, seventh_line, eighth_line) = match self.0 {
@@ -264,8 +300,16 @@ impl Card {
 #[derive(Debug)]
 pub struct Deck {
     pub deck: Vec<Card>,
+    // thrown away in a draw, and shuffled back in once the deck runs out
+    pub discards: Vec<Card>,
+}
+impl Default for Deck {
+    fn default() -> Deck {
+        Deck::new()
+    }
 }
 impl Deck {
+    #[allow(clippy::useless_vec)]
     pub fn new() -> Deck {
         let mut deck = Vec::new();
         for suit in vec![Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades] {
@@ -273,23 +317,51 @@ impl Deck {
                 deck.push(Card(*rank, suit));
             }
         }
-        Deck { deck }
+        Deck { deck, discards: Vec::new() }
+    }
+
+    // the 36 card short deck, sixes through aces
+    pub fn short() -> Deck {
+        let mut deck = Deck::new();
+        deck.deck.retain(|card| card.0 >= Rank::Six);
+        deck
     }
 
+    #[allow(clippy::len_zero)]
     pub fn deal(&mut self) -> Option<Card> {
         if self.deck.len() == 0 {
             return None;
         }
-        let index = rand::thread_rng().gen_range(0..self.deck.len());
+        let index = crate::rng::rng().gen_range(0..self.deck.len());
         let card = self.deck.swap_remove(index);
         Some(card)
     }
 
+    // A replacement for a card thrown away in a draw. Should the deck run out
+    // the discards so far are shuffled in to deal from, which never include
+    // the cards the player is drawing for: they are discarded after.
+    pub fn deal_replacement(&mut self) -> Option<Card> {
+        self.reshuffle();
+        self.deal()
+    }
+
+    // shuffles the discards back in, if the deck has run out
+    pub fn reshuffle(&mut self) {
+        if self.deck.is_empty() {
+            self.deck.append(&mut self.discards);
+        }
+    }
+
+    pub fn discard(&mut self, cards: &[Card]) {
+        self.discards.extend_from_slice(cards);
+    }
+
+    #[allow(clippy::len_zero)]
     pub fn burn_card(&mut self) {
         if self.deck.len() == 0 {
             return;
         }
-        let index = rand::thread_rng().gen_range(0..self.deck.len());
+        let index = crate::rng::rng().gen_range(0..self.deck.len());
         self.deck.swap_remove(index);
     }
 
@@ -302,6 +374,7 @@ impl Deck {
 
     }
 
+    #[allow(clippy::needless_range_loop)]
     pub fn print_cards<T: AsRef<[Card]>>(cards: T) {
         let mut lines = vec![String::new(); 8];
         let mut count_cards = 0; 
@@ -351,6 +424,9 @@ mod tests {
     fn test_deck_creation() {
         let deck = Deck::new();
         assert_eq!(deck.deck.len(), 52);
+        let short = Deck::short();
+        assert_eq!(short.deck.len(), 36);
+        assert!(short.deck.iter().all(|card| card.0 >= Rank::Six));
     }
 
     #[test]
@@ -364,11 +440,38 @@ mod tests {
 
     #[test]
     fn test_deal_empty_deck() {
-        let mut deck = Deck { deck: vec![] };
+        let mut deck = Deck { deck: vec![], discards: vec![] };
         let card = deck.deal();
         assert!(card.is_none());
     }
 
+    #[test]
+    fn test_deal_replacement() {
+        let mut deck = Deck::new();
+        let mut dealt: Vec<Card> = (0..50).map(|_| deck.deal().unwrap()).collect();
+        let discards = dealt.split_off(45);
+        // the stub comes first, then the discards
+        assert!(deck.deal_replacement().is_some_and(|card| !discards.contains(&card)));
+        assert!(deck.deal_replacement().is_some());
+        deck.discard(&discards);
+        let replacements: Vec<Card> = (0..5).map(|_| deck.deal_replacement().unwrap()).collect();
+        assert!(replacements.iter().all(|card| discards.contains(card)));
+        assert_eq!(deck.deal_replacement(), None);
+    }
+
+    #[test]
+    fn test_card_codes() {
+        assert_eq!(Card(Rank::Ten, Suit::Diamonds).code(), "Td");
+        assert_eq!(Card(Rank::Ace, Suit::Spades).code(), "As");
+        assert_eq!(Card::from_code("Td"), Some(Card(Rank::Ten, Suit::Diamonds)));
+        assert_eq!(Card::from_code("2h"), Some(Card(Rank::Two, Suit::Hearts)));
+        assert_eq!(Card::from_code("1h"), None);
+        assert_eq!(Card::from_code("Ahh"), None);
+        for card in Deck::new().deck {
+            assert_eq!(Card::from_code(&card.code()), Some(card));
+        }
+    }
+
     #[test]
     fn test_card_display_lines() {
         let card = Card(Rank::Ace, Suit::Hearts);
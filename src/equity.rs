@@ -0,0 +1,128 @@
+use std::cmp::Ordering;
+
+use rand::seq::SliceRandom;
+
+use crate::deck::{Card, Deck};
+use crate::error::PokerError;
+use crate::utils::HandComparator;
+
+// boards left to deal at or below this many cards are enumerated exactly
+const EXACT_CARDS_LEFT: usize = 2;
+
+// Each hand's share of the pot, ties split, averaged over the boards that can
+// still come. With two cards or fewer to come every board is dealt out;
+// otherwise `samples` random boards are used.
+pub fn equity(hands: &[[Card; 2]], board: &[Card], samples: u32) -> Result<Vec<f64>, PokerError> {
+    if board.len() > 5 {
+        return Err(PokerError::InvalidBoard { cards: board.len() });
+    }
+    let mut deck = Deck::new();
+    for card in hands.iter().flatten().chain(board.iter()) {
+        deck.deal_specific(card.0, card.1).ok_or(PokerError::CardUnavailable(*card))?;
+    }
+    let remaining = deck.deck;
+    let to_come = 5 - board.len();
+
+    let mut shares = vec![0.0; hands.len()];
+    let mut boards = 0u64;
+    let mut score = |runout: &[Card]| {
+        let mut full = board.to_vec();
+        full.extend_from_slice(runout);
+        award(hands, &full, &mut shares);
+        boards += 1;
+    };
+    if to_come <= EXACT_CARDS_LEFT {
+        for_each_combination(&remaining, to_come, &mut Vec::new(), 0, &mut score);
+    } else {
+        let mut rng = crate::rng::rng();
+        let mut deck = remaining.clone();
+        for _ in 0..samples {
+            let (runout, _) = deck.partial_shuffle(&mut rng, to_come);
+            score(runout);
+        }
+    }
+
+    if boards == 0 {
+        return Ok(shares);
+    }
+    Ok(shares.into_iter().map(|s| s / boards as f64).collect())
+}
+
+// splits one pot between the best hands on a complete board
+fn award(hands: &[[Card; 2]], board: &[Card], shares: &mut [f64]) {
+    let best: Vec<_> = hands
+        .iter()
+        .map(|hand| {
+            let mut cards = board.to_vec();
+            cards.extend_from_slice(hand);
+            HandComparator::best_hand(cards)
+        })
+        .collect();
+    let mut winners: Vec<usize> = Vec::new();
+    for idx in 0..best.len() {
+        match winners.first().map(|&w| HandComparator::compare_best(&best[idx], &best[w])) {
+            None | Some(Ordering::Equal) => winners.push(idx),
+            Some(Ordering::Greater) => winners = vec![idx],
+            Some(Ordering::Less) => {},
+        }
+    }
+    for &winner in &winners {
+        shares[winner] += 1.0 / winners.len() as f64;
+    }
+}
+
+fn for_each_combination(cards: &[Card], k: usize, chosen: &mut Vec<Card>, start: usize, f: &mut impl FnMut(&[Card])) {
+    if chosen.len() == k {
+        f(chosen);
+        return;
+    }
+    for idx in start..cards.len() {
+        chosen.push(cards[idx]);
+        for_each_combination(cards, k, chosen, idx + 1, f);
+        chosen.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hand(codes: &str) -> [Card; 2] {
+        let cards: Vec<Card> = codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect();
+        cards.try_into().unwrap()
+    }
+
+    fn board(codes: &str) -> Vec<Card> {
+        codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_exact_on_the_river() {
+        let result = equity(&[hand("Ah Ad"), hand("Kh Kd")], &board("2c 7d 9h Js 3s"), 0).unwrap();
+        assert_eq!(result, vec![1.0, 0.0]);
+        let split = equity(&[hand("Ah Kd"), hand("Ad Kh")], &board("2c 7d 9h Js 3s"), 0).unwrap();
+        assert_eq!(split, vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_exact_on_the_turn() {
+        // 44 rivers, only the two remaining kings save the kings
+        let result = equity(&[hand("Ah Ad"), hand("Kh Kd")], &board("2c 7d 9h Js"), 0).unwrap();
+        assert!((result[1] - 2.0 / 44.0).abs() < 1e-9);
+        assert!((result[0] + result[1] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sampled_preflop() {
+        let result = equity(&[hand("Ah Ad"), hand("7c 2d")], &[], 2000).unwrap();
+        assert!(result[0] > 0.8 && result[0] < 0.95, "{:?}", result);
+    }
+
+    #[test]
+    fn test_duplicate_cards() {
+        assert_eq!(
+            equity(&[hand("Ah Ad"), hand("Ah Kd")], &[], 10),
+            Err(PokerError::CardUnavailable(Card::from_code("Ah").unwrap()))
+        );
+    }
+}
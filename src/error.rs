@@ -0,0 +1,66 @@
+use std::fmt;
+
+use crate::deck::Card;
+use crate::player::Action;
+
+// Everything that can go wrong with bad input to the engine. Nothing that
+// returns one of these has changed any state it was called on, though a hand
+// undone partway has still been shown to strategies.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PokerError {
+    TooManyPlayers { requested: usize, max: usize },
+    HandFull { player_id: usize },
+    DeckEmpty,
+    CardUnavailable(Card),
+    InvalidAction { player_id: usize, action: Action, to_call: u32 },
+    NoWinner,
+    NoSuchSeat(usize),
+    SeatTaken(usize),
+    SeatEmpty(usize),
+    AlreadySeated(usize),
+    BuyInOutOfRange { chips: u32, min: u32, max: u32 },
+    // a board with a number of cards no street deals
+    InvalidBoard { cards: usize },
+    // Omaha dealt a number of hole cards it is not played with
+    HoleCardsOutOfRange { hole_cards: usize, min: usize, max: usize },
+    // a player at showdown without the cards to make a hand
+    IncompleteHand { player_id: usize },
+    // a hand history format that cannot record the game
+    UnsupportedHistory(String),
+    // a hand history could not be written, see Game::history_errors; the
+    // hand stands
+    HistoryWrite(String),
+}
+
+impl fmt::Display for PokerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PokerError::TooManyPlayers { requested, max } => {
+                write!(f, "{} players asked for but a table seats at most {}", requested, max)
+            },
+            PokerError::HandFull { player_id } => write!(f, "player {} already holds a full hand", player_id),
+            PokerError::DeckEmpty => write!(f, "the deck ran out of cards"),
+            PokerError::CardUnavailable(card) => write!(f, "{} is not in the deck", card.code()),
+            PokerError::InvalidAction { player_id, action, to_call } => {
+                write!(f, "player {} cannot {:?} facing {} to call", player_id, action, to_call)
+            },
+            PokerError::NoWinner => write!(f, "a pot has no live hand to award it to"),
+            PokerError::NoSuchSeat(seat) => write!(f, "the table has no seat {}", seat),
+            PokerError::SeatTaken(seat) => write!(f, "seat {} is taken", seat),
+            PokerError::SeatEmpty(seat) => write!(f, "nobody sits in seat {}", seat),
+            PokerError::AlreadySeated(player_id) => write!(f, "player {} already has a seat", player_id),
+            PokerError::BuyInOutOfRange { chips, min, max } => {
+                write!(f, "a stack of {} is outside the buy-in limits of {} to {}", chips, min, max)
+            },
+            PokerError::InvalidBoard { cards } => write!(f, "a board cannot have {} cards", cards),
+            PokerError::HoleCardsOutOfRange { hole_cards, min, max } => {
+                write!(f, "{} hole cards is outside the {} to {} Omaha is dealt", hole_cards, min, max)
+            },
+            PokerError::IncompleteHand { player_id } => write!(f, "player {} does not hold enough cards to make a hand", player_id),
+            PokerError::UnsupportedHistory(message) => write!(f, "{}", message),
+            PokerError::HistoryWrite(message) => write!(f, "could not write hand history: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PokerError {}
@@ -1,327 +1,2144 @@
-use std::vec;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::deck::{Deck, Card};
-use crate::player::{Action, Player, PlayerState};
-use crate::utils::{Hand,HandComparator};
+use crate::betting::{BettingState, BettingStructure};
+use crate::deck::{Deck, Card, Suit};
+use crate::error::PokerError;
+use crate::history::{combine_runs, run_share, ActionKind, ActionRecord, DrawRecord, HandHistoryWriter, HandRecord, PotRecord, RunRecord, SeatRecord, ShowdownRecord};
+use crate::player::{Action, Player, PlayerState, Strategy};
+use crate::rake::Rake;
+use crate::table::Table;
+use crate::utils::{compare_badugi, Hand, HandComparator};
+use crate::variant::{BestHand, Variant};
 
 pub struct Game {
-    players: Vec<Player>, 
+    table: Table,
+    variant: Variant,
     small_blind: u32,
     big_blind: u32,
-    buyin: u32
+    ante: Ante,
+    straddle: Straddle,
+    betting: BettingStructure,
+    rake: Rake,
+    // the most times the rest of the board is run once everyone is all in
+    run_it: u32,
+    button_rule: ButtonRule,
+    // where the button and blinds were last hand
+    positions: Option<Positions>,
+    // puts the button on this seat next hand
+    next_button: Option<usize>,
+    buyin: u32,
+    table_name: String,
+    hand_number: u64,
+    history: Vec<Box<dyn HandHistoryWriter>>,
+    // why any of them could not write the last hand
+    history_errors: Vec<PokerError>,
+    last_hand: Option<HandRecord>,
+    // everyone who lost their last chip in the last hand, in seat order
+    busted: Vec<Player>,
+    // busted players stay seated, sitting out, instead of leaving
+    keep_busted: bool,
+    // what each player sitting out owes by player id
+    missed_blinds: HashMap<usize, MissedBlinds>,
+    // on each player's head in a bounty tournament, shown to strategies
+    bounties: HashMap<usize, u32>,
+    // players without a strategy act randomly
+    strategies: HashMap<usize, Box<dyn Strategy>>,
+    // cards the next hand deals instead of random ones, hole cards by player id
+    forced_hole_cards: HashMap<usize, Vec<Card>>,
+    forced_board: Vec<Card>,
+    // the boards of the runs after the first
+    forced_runs: Vec<Vec<Card>>,
+    // prints every hand as it is played
+    verbose: bool,
 }
-enum BettingRoundName{
+// Who antes before each hand. A big blind ante is paid for the whole table
+// by the big blind, after their blind if they are short.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Ante {
+    None,
+    Everyone(u32),
+    BigBlind(u32),
+}
+
+// An optional blind raise to twice the big blind, posted under the gun or on
+// the button (a Mississippi straddle). The straddler acts last before the
+// flop and the player to their left acts first.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Straddle {
+    None,
+    UnderTheGun,
+    Button,
+}
+
+// How the button moves on when players bust. A moving button always goes to
+// the next player. Under the dead button rule the big blind moves on one seat
+// every hand and the small blind and button follow it, even onto empty seats,
+// so nobody misses or pays the big blind twice. Heads up the button posts the
+// small blind and acts first before the flop under either rule.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ButtonRule {
+    Moving,
+    Dead,
+}
+
+// Blinds that passed a player while they sat out. Coming back they post the
+// big blind live and, if they missed both, the small blind as dead money,
+// unless they come back in the blinds anyway. A missed small blind alone is
+// not owed.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct MissedBlinds {
+    pub small_blind: bool,
+    pub big_blind: bool,
+}
+
+// The seats of the button and the blinds in one hand.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Positions {
+    pub button: usize,
+    pub small_blind: usize,
+    pub big_blind: usize,
+}
+
+// the streets of the board games, then those of stud named for the cards
+// each player has after the deal, then the betting after each draw of the
+// draw games, which bet before the first draw as before the flop
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum BettingRoundName{
     Preflop, 
     Flop, 
     Turn, 
-    River
+    River,
+    ThirdStreet,
+    FourthStreet,
+    FifthStreet,
+    SixthStreet,
+    SeventhStreet,
+    FirstDraw,
+    SecondDraw,
+    ThirdDraw,
 }
-struct BettingRoundParams{
-    round: BettingRoundName,
-    pot: i32,
-    action: Vec<Action>,
-    n_active: usize, 
+pub(crate) static ROUND_ORDER: &[BettingRoundName] = &[
+    BettingRoundName::Preflop,
+    BettingRoundName::Flop,
+    BettingRoundName::Turn,
+    BettingRoundName::River,
+];
+pub(crate) static STUD_ROUND_ORDER: &[BettingRoundName] = &[
+    BettingRoundName::ThirdStreet,
+    BettingRoundName::FourthStreet,
+    BettingRoundName::FifthStreet,
+    BettingRoundName::SixthStreet,
+    BettingRoundName::SeventhStreet,
+];
+pub(crate) static DRAW_ROUND_ORDER: &[BettingRoundName] = &[
+    BettingRoundName::Preflop,
+    BettingRoundName::FirstDraw,
+    BettingRoundName::SecondDraw,
+    BettingRoundName::ThirdDraw,
+];
+
+// Where the betting stands, carried from one street to the next. Bets are
+// counted over the whole hand, so a street's totals are relative to its base.
+struct Betting {
+    street: BettingRoundName,
+    pot: u32,
+    current_bet: u32,
+    street_base: u32,
+    // the last full bet or raise, and how many bets the street has seen
+    last_raise: u32,
+    raises: u32,
+    // everyone's actions street by street, as strategies see them
+    action: Vec<Vec<Action>>,
+}
+
+// each player's best five cards on one board, None for anyone whose hand
+// is not shown
+pub(crate) type BestHands = Vec<Option<([Card;5],Hand)>>;
+// each player's qualifying low on one board in a hi-lo game
+pub(crate) type BestLows = Vec<Option<[Card;5]>>;
 
+// the most players one deck can deal a hand of hold'em to
+pub const MAX_PLAYERS: usize = 22;
 
+// bridge order, which breaks ties between cards of the same rank
+fn bridge_order(suit: Suit) -> u8 {
+    match suit {
+        Suit::Clubs => 0,
+        Suit::Diamonds => 1,
+        Suit::Hearts => 2,
+        Suit::Spades => 3,
+    }
 }
 
 impl Game {
 
-    pub fn new(n_players: u32, buyin: u32) -> Game {
+    pub fn new(n_players: u32, buyin: u32) -> Result<Game, PokerError> {
         
-        assert!( n_players < 23 );
+        if n_players as usize > MAX_PLAYERS {
+            return Err(PokerError::TooManyPlayers { requested: n_players as usize, max: MAX_PLAYERS });
+        }
 
-        let mut players = Vec::new();
+        let mut table = Table::new(n_players as usize);
         for i in 0..n_players { 
             let player = Player::new(i as usize,format!("Player {}", i + 1), buyin);
-            players.push(player);
+            table.sit(i as usize + 1, player)?;
+        }
+
+        Ok(Game { 
+            table, 
+            variant: Variant::Holdem,
+            small_blind: 1, 
+            big_blind: 2, 
+            ante: Ante::None,
+            straddle: Straddle::None,
+            betting: BettingStructure::NoLimit,
+            rake: Rake::none(),
+            run_it: 1,
+            button_rule: ButtonRule::Moving,
+            positions: None,
+            next_button: None,
+            buyin, 
+            table_name: "Table 1".to_string(),
+            hand_number: 0,
+            history: Vec::new(),
+            last_hand: None,
+            busted: Vec::new(),
+            history_errors: Vec::new(),
+            keep_busted: false,
+            missed_blinds: HashMap::new(),
+            bounties: HashMap::new(),
+            strategies: HashMap::new(),
+            forced_hole_cards: HashMap::new(),
+            forced_board: Vec::new(),
+            forced_runs: Vec::new(),
+            verbose: true,
+        })
+    }
+
+    // a table of the given players, each in the seat after their id
+    pub fn with_players(players: Vec<Player>, small_blind: u32, big_blind: u32) -> Result<Game, PokerError> {
+        if players.len() > MAX_PLAYERS {
+            return Err(PokerError::TooManyPlayers { requested: players.len(), max: MAX_PLAYERS });
+        }
+        let max_seats = players.iter().map(|p| p.id + 1).max().unwrap_or(0);
+        let mut table = Table::new(max_seats);
+        for player in players {
+            table.sit(player.id + 1, player)?;
+        }
+        Game::with_table(table, small_blind, big_blind)
+    }
+
+    // a game at a table with its players already seated, e.g. to replay a recorded hand
+    pub fn with_table(table: Table, small_blind: u32, big_blind: u32) -> Result<Game, PokerError> {
+        let seated = table.occupied().count();
+        if seated > MAX_PLAYERS {
+            return Err(PokerError::TooManyPlayers { requested: seated, max: MAX_PLAYERS });
+        }
+        let buyin = table.occupied().map(|(_, p)| p.chips).max().unwrap_or(0);
+        let mut game = Game::new(0, buyin)?;
+        game.table = table;
+        game.small_blind = small_blind;
+        game.big_blind = big_blind;
+        Ok(game)
+    }
+
+    // seats are added or removed at the end, though never taken ones
+    pub fn set_max_seats(&mut self, max_seats: usize) {
+        self.table.resize(max_seats);
+    }
+
+    // The player joins in this seat from the next hand on.
+    pub fn sit(&mut self, seat: usize, player: Player) -> Result<(), PokerError> {
+        let seated = self.table.occupied().count();
+        let max = self.variant.max_players();
+        if seated >= max {
+            return Err(PokerError::TooManyPlayers { requested: seated + 1, max });
+        }
+        self.table.sit(seat, player)
+    }
+
+    pub fn leave(&mut self, seat: usize) -> Option<Player> {
+        let player = self.table.leave(seat)?;
+        self.missed_blinds.remove(&player.id);
+        Some(player)
+    }
+
+    // A player sitting out keeps their seat but is dealt no cards and posts
+    // no blinds until they sit in again.
+    pub fn sit_out(&mut self, seat: usize) -> Result<(), PokerError> {
+        self.table.sit_out(seat)
+    }
+
+    pub fn sit_in(&mut self, seat: usize) -> Result<(), PokerError> {
+        self.table.sit_in(seat)
+    }
+
+    // chips bought between hands, e.g. a cash game rebuy
+    pub fn add_chips(&mut self, seat: usize, chips: u32) -> Result<(), PokerError> {
+        if seat == 0 || seat > self.table.max_seats() {
+            return Err(PokerError::NoSuchSeat(seat));
+        }
+        let player = self.table.player_mut(seat).ok_or(PokerError::SeatEmpty(seat))?;
+        player.chips += chips;
+        Ok(())
+    }
+
+    pub fn table(&self) -> &Table {
+        &self.table
+    }
+
+    // the next hand played gets this number
+    pub fn set_hand_number(&mut self, hand_number: u64) {
+        self.hand_number = hand_number.saturating_sub(1);
+    }
+
+    pub fn set_strategy(&mut self, player_id: usize, strategy: Box<dyn Strategy>) {
+        self.strategies.insert(player_id, strategy);
+    }
+
+    // e.g. to move the player's strategy with them to another table
+    pub fn take_strategy(&mut self, player_id: usize) -> Option<Box<dyn Strategy>> {
+        self.strategies.remove(&player_id)
+    }
+
+    // Deals these cards in the next hand. Hole cards are by player id and the
+    // board is dealt from the flop on; anything not given is dealt at random.
+    // In a draw game a player's cards go on to those they draw, which may be
+    // dealt again from the discards.
+    pub fn force_cards(&mut self, hole_cards: HashMap<usize, Vec<Card>>, board: Vec<Card>) -> Result<(), PokerError> {
+        let (hand, draws) = (self.variant.hole_cards(), self.variant.draws());
+        if let Some((&player_id, _)) = hole_cards.iter().find(|(_, cards)| cards.len() > hand * (1 + draws)) {
+            return Err(PokerError::HandFull { player_id });
         }
+        if board.len() > 5 {
+            return Err(PokerError::InvalidBoard { cards: board.len() });
+        }
+        let mut deck = self.variant.deck();
+        for card in hole_cards.values().flat_map(|cards| cards.iter().take(hand)).chain(board.iter()) {
+            deck.deal_specific(card.0, card.1).ok_or(PokerError::CardUnavailable(*card))?;
+        }
+        self.forced_hole_cards = hole_cards;
+        self.forced_board = board;
+        Ok(())
+    }
+
+    // The whole boards of the runs after the first should the next hand be
+    // run more than once. Cards they share with the forced board are the
+    // ones already out when the players went all in.
+    pub fn force_runs(&mut self, boards: Vec<Vec<Card>>) -> Result<(), PokerError> {
+        let mut deck = self.variant.deck();
+        for card in self.forced_hole_cards.values().flatten().chain(self.forced_board.iter()) {
+            deck.deal_specific(card.0, card.1);
+        }
+        for board in &boards {
+            if board.len() != 5 {
+                return Err(PokerError::InvalidBoard { cards: board.len() });
+            }
+            for card in board.iter().filter(|card| !self.forced_board.contains(card)) {
+                deck.deal_specific(card.0, card.1).ok_or(PokerError::CardUnavailable(*card))?;
+            }
+        }
+        self.forced_runs = boards;
+        Ok(())
+    }
+
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    pub fn set_blinds(&mut self, small_blind: u32, big_blind: u32) {
+        self.small_blind = small_blind;
+        self.big_blind = big_blind;
+    }
+
+    pub fn set_ante(&mut self, ante: Ante) {
+        self.ante = ante;
+    }
+
+    // a straddle is skipped in hands with too few players for one
+    pub fn set_straddle(&mut self, straddle: Straddle) {
+        self.straddle = straddle;
+    }
+
+    // Fails if more players are seated than the variant can deal a hand to.
+    // Short deck is an ante game, so a table without an ante switched to it
+    // has everyone ante a big blind.
+    pub fn set_variant(&mut self, variant: Variant) -> Result<(), PokerError> {
+        variant.check()?;
+        let seated = self.table.occupied().count();
+        if seated > variant.max_players() {
+            return Err(PokerError::TooManyPlayers { requested: seated, max: variant.max_players() });
+        }
+        Game::check_histories(&self.history, variant, self.betting)?;
+        if matches!(variant, Variant::ShortDeck { .. }) && self.ante == Ante::None {
+            self.ante = Ante::Everyone(self.big_blind);
+        }
+        self.variant = variant;
+        Ok(())
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn set_betting_structure(&mut self, betting: BettingStructure) -> Result<(), PokerError> {
+        Game::check_histories(&self.history, self.variant, betting)?;
+        self.betting = betting;
+        Ok(())
+    }
+
+    pub fn set_rake(&mut self, rake: Rake) {
+        self.rake = rake;
+    }
+
+    // Offers players who are all in before the river to run the rest of the
+    // board up to this many times, each pot split evenly between the runs.
+    // 1, the default, always runs it once.
+    pub fn set_run_it(&mut self, times: u32) {
+        self.run_it = times.max(1);
+    }
+
+    pub fn set_button_rule(&mut self, rule: ButtonRule) {
+        self.button_rule = rule;
+    }
+
+    // Puts the button on this seat next hand, with the blinds after it as in
+    // a first hand. Later hands move it on from there.
+    pub fn set_button(&mut self, seat: usize) {
+        self.next_button = Some(seat);
+    }
+
+    // where the button and blinds were in the last hand played
+    pub fn positions(&self) -> Option<Positions> {
+        self.positions
+    }
+
+    pub fn set_table_name(&mut self, name: &str) {
+        self.table_name = name.to_string();
+    }
+
+    // every completed hand is written to each of these, e.g. a PokerStarsWriter over a File,
+    // unless the format cannot record the game
+    pub fn add_history_writer(&mut self, writer: Box<dyn HandHistoryWriter>) -> Result<(), PokerError> {
+        Game::check_histories(std::slice::from_ref(&writer), self.variant, self.betting)?;
+        self.history.push(writer);
+        Ok(())
+    }
+
+    // the game would be written to histories that cannot record it
+    fn check_histories(writers: &[Box<dyn HandHistoryWriter>], variant: Variant, betting: BettingStructure) -> Result<(), PokerError> {
+        match writers.iter().find_map(|writer| writer.unsupported(variant, betting)) {
+            Some(message) => Err(PokerError::UnsupportedHistory(message)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn buyin(&self) -> u32 {
+        self.buyin
+    }
+
+    // everyone still at the table, in seat order
+    pub fn players(&self) -> Vec<&Player> {
+        self.table.occupied().map(|(_, player)| player).collect()
+    }
+
+    pub fn last_hand(&self) -> Option<&HandRecord> {
+        self.last_hand.as_ref()
+    }
 
-        Game { players, small_blind: 1, big_blind: 2, buyin}
+    // who lost their seat by busting in the last hand
+    pub fn busted(&self) -> &[Player] {
+        &self.busted
+    }
+
+    // a PokerError::HistoryWrite for each writer that could not record the
+    // last hand; the hand stands all the same
+    pub fn history_errors(&self) -> &[PokerError] {
+        &self.history_errors
+    }
+
+    // In a cash game a player who loses their last chip keeps their seat,
+    // sitting out, until they buy more chips or leave.
+    pub fn set_keep_busted(&mut self, keep_busted: bool) {
+        self.keep_busted = keep_busted;
+    }
+
+    pub fn missed_blinds(&self, player_id: usize) -> MissedBlinds {
+        self.missed_blinds.get(&player_id).copied().unwrap_or_default()
+    }
+
+    // the bounty on each player's head by player id, which strategies see
+    // before every hand
+    pub fn set_bounties(&mut self, bounties: HashMap<usize, u32>) {
+        self.bounties = bounties;
+    }
+
+    // e.g. to replay a hand where a player came back owing blinds
+    pub fn set_missed_blinds(&mut self, player_id: usize, missed: MissedBlinds) {
+        self.missed_blinds.insert(player_id, missed);
+    }
+
+    fn start_record(&self, button: usize, seats: &[usize], players: &[Player]) -> HandRecord {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        HandRecord {
+            hand_id: self.hand_number,
+            table_name: self.table_name.clone(),
+            timestamp,
+            max_seats: self.table.max_seats(),
+            button_seat: button,
+            small_blind: self.small_blind,
+            big_blind: self.big_blind,
+            ante: 0,
+            variant: self.variant,
+            betting: self.betting,
+            rake: 0,
+            seats: seats
+                .iter()
+                .zip(players)
+                .map(|(&seat, p)| SeatRecord { seat, player_id: p.id, name: p.name.clone(), stack: p.chips, cards: Vec::new(), up_cards: Vec::new() })
+                .collect(),
+            actions: Vec::new(),
+            draws: Vec::new(),
+            board: Vec::new(),
+            uncalled: None,
+            pots: Vec::new(),
+            showdown: Vec::new(),
+            runs: Vec::new(),
+        }
     }
     
-    fn find_winner(community_cards: [Card;5], players: &Vec<Player>) -> usize {
+    // indices of everyone tied for the best hand among the eligible indices
+    fn find_winners<H>(hands: &[Option<H>], eligible: &[usize], compare: &impl Fn(&H, &H) -> std::cmp::Ordering) -> Vec<usize> {
+        let mut winners: Vec<usize> = Vec::new();
+        for &idx in eligible {
+            let Some(hand) = &hands[idx] else { continue };
+            match winners.first() {
+                None => winners.push(idx),
+                Some(&best) => {
+                    match compare(hand, hands[best].as_ref().unwrap()) {
+                        std::cmp::Ordering::Greater => {
+                            winners.clear();
+                            winners.push(idx);
+                        },
+                        std::cmp::Ordering::Equal => winners.push(idx),
+                        std::cmp::Ordering::Less => {},
+                    }
+                }
+            }
+        }
+        winners
+    }
 
-        // havent implemented split pots
+    // indices of everyone tied for the best low among the eligible indices,
+    // empty when nobody has one
+    fn find_low_winners(lows: &[Option<[Card;5]>], eligible: &[usize]) -> Vec<usize> {
+        let Some(best) = eligible.iter().filter_map(|&idx| lows[idx]).max_by(HandComparator::compare_low) else { return Vec::new() };
+        eligible.iter().copied().filter(|&idx| lows[idx].is_some_and(|low| HandComparator::compare_low(&low, &best).is_eq())).collect()
+    }
 
-        if players.len() - players.iter().filter(|p| (p.state == PlayerState::Folded) ).count() == 1 {
-            println!("Only one remaining player");    
+    // Takes the part of the biggest bet that nobody matched off that bet,
+    // returning who made it and how much goes back to them.
+    pub(crate) fn return_uncalled(bets: &mut [u32]) -> Option<(usize, u32)> {
+        let top = (0..bets.len()).max_by_key(|&idx| bets[idx])?;
+        let matched = bets
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| *idx != top)
+            .map(|(_, bet)| *bet)
+            .max()
+            .unwrap_or(0);
+        if bets[top] <= matched {
+            return None;
         }
-        
-        let mut winners: Vec::<(usize,([Card;5],Hand))> = Vec::new();
-        for player in players {
+        let uncalled = bets[top] - matched;
+        bets[top] = matched;
+        Some((top, uncalled))
+    }
+
+    // Splits everyone's total bets into a main pot and side pots, one per
+    // distinct all-in level, each with who can win it and its part of the
+    // rake. The rake comes off each pot in proportion to its size, with what
+    // rounding leaves over from the main pot.
+    pub(crate) fn contested_pots(bets: &[u32], live: &[bool], rake: u32) -> Vec<(u32, Vec<usize>, u32)> {
+        let mut levels: Vec<u32> = (0..bets.len()).filter(|&idx| live[idx]).map(|idx| bets[idx]).collect();
+        levels.sort_unstable();
+        levels.dedup();
 
-            if player.state== PlayerState::Folded { 
+        let mut contested = Vec::new();
+        let mut previous = 0;
+        for (level_idx, &level) in levels.iter().enumerate() {
+            let mut amount: u32 = bets
+                .iter()
+                .map(|bet| bet.min(&level) - bet.min(&previous))
+                .sum();
+            if level_idx == levels.len() - 1 {
+                amount += bets.iter().map(|bet| bet.saturating_sub(level)).sum::<u32>();
+            }
+            previous = level;
+            if amount == 0 {
                 continue;
             }
-            
-            let mut hand: Vec<Card> = Vec::new();
-            hand.extend_from_slice(&community_cards);
-            hand.extend_from_slice(&player.hand);
-
-            let best_hand = HandComparator::best_hand(hand);
-
-            // now that we have best hand of this person, compare with earlier best hand and replace; 
-            if !winners.is_empty() {
-                let (_, winner_hand) = winners[0];
-                if best_hand.1 > winner_hand.1 {
-                    winners.clear(); 
-                    winners.push((player.id,best_hand));
+            let eligible: Vec<usize> = (0..bets.len()).filter(|&idx| live[idx] && bets[idx] >= level).collect();
+            contested.push((amount, eligible));
+        }
+
+        let total: u64 = contested.iter().map(|(amount, _)| *amount as u64).sum();
+        let mut rakes: Vec<u32> = contested.iter().map(|(amount, _)| (rake as u64 * *amount as u64 / total.max(1)) as u32).collect();
+        let raked: u32 = rakes.iter().sum();
+        if let Some(main) = rakes.first_mut() {
+            *main += rake.saturating_sub(raked);
+        }
+        contested.into_iter().zip(rakes).map(|((amount, eligible), rake)| (amount, eligible, rake)).collect()
+    }
+
+    // Awards each of the pots found by contested_pots to the best live hand
+    // that reached it. Players are listed starting left of the button, the
+    // order odd chips are handed out in; hands only need to be known when a
+    // pot is contested. A board run more than once has the hands on each
+    // run, and each pot and its rake are split evenly between the runs with
+    // odd chips going to the earliest; the pots of every run are returned. In a hi-lo game the lows on each run are given too and
+    // each pot is split between the high and the low, the odd chip to the
+    // high, unless no low qualifies. Hands are ordered by `compare`, better
+    // greater. Winners in them are indices into the given slices.
+    pub(crate) fn split_pots<H>(bets: &[u32], live: &[bool], runs: &[Vec<Option<H>>], lows: &[BestLows], compare: impl Fn(&H, &H) -> std::cmp::Ordering, rake: u32) -> Result<Vec<Vec<PotRecord>>, PokerError> {
+        let n_runs = runs.len() as u32;
+        let mut pots = vec![Vec::new(); runs.len()];
+        for (amount, eligible, pot_rake) in Game::contested_pots(bets, live, rake) {
+            let amount = amount - pot_rake.min(amount);
+            for (run, hands) in runs.iter().enumerate() {
+                let amount = run_share(amount, n_runs, run);
+                let rake = run_share(pot_rake, n_runs, run);
+                let winners = if eligible.len() == 1 {
+                    eligible.clone()
+                } else {
+                    Game::find_winners(hands, &eligible, &compare)
+                };
+                if winners.is_empty() {
+                    return Err(PokerError::NoWinner);
                 }
-                else if best_hand.1 == winner_hand.1 {
-                    // they have same 
-                    // look through sorted cards and find first diff
-                    let old = winners[0].1.0;
-                    let new = best_hand.0;
-                    for idx in 0..5 {
-                        if new[idx].0 > old[idx].0 {
-                            winners.clear(); 
-                            winners.push((player.id,best_hand));
-                            break; 
-                        }
-                        else if old[idx].0 > new[idx].0 {
-                            break; 
-                        }
-                        else if idx == 4 {
-                            winners.push((player.id,best_hand));
+                let low_winners = lows.get(run).map_or(Vec::new(), |lows| Game::find_low_winners(lows, &eligible));
+                let low = if low_winners.is_empty() { 0 } else { amount / 2 };
+
+                let mut shares: Vec<(usize, u32)> = Vec::new();
+                for (half, winners) in [(amount - low, winners), (low, low_winners)] {
+                    let Some(split) = half.checked_div(winners.len() as u32) else { continue };
+                    let odd_chips = (half % winners.len() as u32) as usize;
+                    for (n, &idx) in winners.iter().enumerate() {
+                        let won = split + if n < odd_chips { 1 } else { 0 };
+                        match shares.iter_mut().find(|(winner, _)| *winner == idx) {
+                            Some((_, share)) => *share += won,
+                            None => shares.push((idx, won)),
                         }
                     }
                 }
-            } else {winners.push((player.id,best_hand));}
+                pots[run].push(PotRecord { amount, rake, winners: shares });
+            }
         }
-        println!("Winning Hand: ");
-        Deck::print_cards(&winners[0].1.0);
-        
-        return winners[0].0; 
+        Ok(pots)
     }
-      
-    fn showdown(&mut self, community_cards: [Card;5], mut pots: Vec<u32>, all_in_player_ids: Vec<Vec<usize>>) -> usize {
-        // for now just find one winner, fix later to do side pots
-        let max_pot = pots.iter().sum();
 
-        println!("Showdown");
-        let mut pot_distributed = 0; 
-        let mut players = self.players.clone(); 
-        
-        while pot_distributed < max_pot {
-            let winner_id = Game::find_winner(community_cards, &players);
-            let mut pot = 0; 
-            for idx in 0..=3 {
-                pot+=pots[idx];
-                pots[idx] = 0;
-                if all_in_player_ids[idx].contains(&winner_id) {
-                    break; 
+    // Pays out the pots on the board, or on each board when it was run more
+    // than once.
+    fn showdown(&self, players: &mut [Player], boards: &[Vec<Card>], dealer: usize, antes: &[u32], record: &mut HandRecord) -> Result<(), PokerError> {
+        let n_players = players.len();
+        // everyone in the order they sit, starting left of the button
+        let order: Vec<usize> = (1..=n_players).map(|i| (dealer + i) % n_players).collect();
+
+        // an unmatched bet goes straight back to whoever made it
+        let mut bets: Vec<u32> = order.iter().map(|&idx| players[idx].bet).collect();
+        if let Some((pos, uncalled)) = Game::return_uncalled(&mut bets) {
+            let player = &mut players[order[pos]];
+            player.bet -= uncalled;
+            player.chips += uncalled;
+            if self.verbose { println!("Uncalled bet of {} returned to {}", uncalled, player.name); }
+            record.uncalled = Some((player.id, uncalled));
+        }
+        for (pos, &idx) in order.iter().enumerate() {
+            bets[pos] += antes[idx];
+        }
+
+        let live: Vec<bool> = order.iter().map(|&idx| players[idx].state != PlayerState::Folded).collect();
+        let mut runs: Vec<BestHands> = vec![vec![None; n_players]; boards.len()];
+        // a badugi is not five cards and is ordered on its own
+        let badugi = self.variant == Variant::Badugi;
+        let mut badugis: Vec<Vec<Option<Vec<Card>>>> = vec![vec![None; n_players]; boards.len()];
+        let mut lows: Vec<BestLows> = if self.variant.is_hi_lo() { vec![vec![None; n_players]; boards.len()] } else { Vec::new() };
+        let mut showdowns: Vec<Vec<ShowdownRecord>> = vec![Vec::new(); boards.len()];
+        if live.iter().filter(|&&l| l).count() > 1 {
+            if self.verbose { println!("Showdown"); }
+            for (run, board) in boards.iter().enumerate() {
+                if boards.len() > 1 && self.verbose {
+                    println!("Run {}", run + 1);
+                    Deck::print_cards(board);
+                }
+                for (idx, player) in players.iter().enumerate() {
+                    if player.state == PlayerState::Folded {
+                        continue;
+                    }
+                    let pos = order.iter().position(|&o| o == idx).unwrap();
+                    let best_hand = self.variant.best_hand(&player.hand, board).ok_or(PokerError::IncompleteHand { player_id: player.id })?;
+                    let low = self.variant.best_low(&player.hand, board);
+                    showdowns[run].push(ShowdownRecord { player_id: player.id, cards: best_hand.cards().to_vec(), hand: best_hand.hand(), low });
+                    match best_hand {
+                        BestHand::Five(cards, hand) => runs[run][pos] = Some((cards, hand)),
+                        BestHand::Badugi(cards) => badugis[run][pos] = Some(cards),
+                    }
+                    if let Some(lows) = lows.get_mut(run) {
+                        lows[pos] = low;
+                    }
                 }
             }
-            let (idx, winner) = self.players
-                .iter_mut()
-                .enumerate()
-                .find(|(_, player)| player.id == winner_id)
-                .unwrap();
-            winner.deal_chips(pot);
-            players.remove(idx);
-            
-            pot_distributed+=pot;
-            // println!("Side Pot: Player {} got {} chips", winner_id,pot);
+        } else {
+            if self.verbose { println!("Only one remaining player"); }
         }
 
-        self.players.retain(|player| player.chips > 0);
-        for player in self.players.iter_mut() {
-            player.reset();
+        // in stud the first street stands in for the deal before the flop,
+        // and in the draw games the betting before the first draw
+        let saw_flop = if self.variant.is_stud() {
+            record.seats.iter().any(|s| s.cards.len() > 3)
+        } else if self.variant.draws() > 0 {
+            !record.draws.is_empty()
+        } else {
+            !record.board.is_empty()
+        };
+        record.rake = self.rake.take(bets.iter().sum(), n_players, saw_flop);
+        let ranking = self.variant.ranking();
+        let mut pots = if badugi {
+            Game::split_pots(&bets, &live, &badugis, &lows, |pro, opp| compare_badugi(pro, opp), record.rake)?
+        } else {
+            Game::split_pots(&bets, &live, &runs, &lows, |pro, opp| HandComparator::compare_ranked(pro, opp, ranking), record.rake)?
+        };
+        for pot in pots.iter_mut().flatten() {
+            for (pos, won) in pot.winners.iter_mut() {
+                let player = &mut players[order[*pos]];
+                player.deal_chips(*won);
+                if self.verbose { println!("{} got {} chips", player.name, won); }
+                *pos = player.id;
+            }
+        }
+        record.pots = combine_runs(&pots);
+        record.showdown = showdowns[0].clone();
+        if boards.len() > 1 {
+            record.runs = boards
+                .iter()
+                .zip(showdowns)
+                .zip(pots)
+                .map(|((board, showdown), pots)| RunRecord { board: board.to_vec(), showdown, pots })
+                .collect();
         }
-        return 0;
+
+        if let Some(&(id, _)) = record.pots.first().map(|pot| &pot.winners[0])
+            && let Some(shown) = record.showdown.iter().find(|s| s.player_id == id)
+            && self.verbose {
+            println!("Winning Hand: {}", record.name(id));
+            Deck::print_cards(&shown.cards);
+        }
+
+        Ok(())
     }
 
-    pub fn play_round(&mut self, dealer: usize){
+    // the seat of the next player dealt in after this seat, going round the table
+    fn next_player(&self, seat: usize) -> usize {
+        self.table.next_dealt_in(seat).unwrap_or(seat)
+    }
 
-        // Important Test: Can't allow raise if everyone else all in
-        
-        // print!("Stacks: ");
-        // for player in &self.players {
-        //     print!("{}: {}, ", player.name, player.chips);
-        // }
-        
+    // everyone dealt in, taken from their seats for the hand, and their seat numbers
+    fn deal_in(&mut self) -> (Vec<usize>, Vec<Player>) {
+        let seats = self.table.dealt_in();
+        let players = seats.iter().map(|&seat| self.table.leave(seat).unwrap()).collect();
+        (seats, players)
+    }
+
+    // Everyone sitting out in a seat the blinds moved past since last hand
+    // owes them.
+    fn mark_missed_blinds(&mut self, last: Positions, next: Positions) {
+        let max_seats = self.table.max_seats();
+        let passed = |from: usize, to: usize| (1..max_seats).map(move |n| (from + n - 1) % max_seats + 1).take_while(move |&seat| seat != to);
+        for (from, to, big_blind) in [(last.big_blind, next.big_blind, true), (last.small_blind, next.small_blind, false)] {
+            if from == to {
+                continue;
+            }
+            for seat in passed(from, to) {
+                let Some(player) = self.table.player(seat).filter(|_| self.table.is_sitting_out(seat)) else { continue };
+                let missed = self.missed_blinds.entry(player.id).or_default();
+                if big_blind {
+                    missed.big_blind = true;
+                } else {
+                    missed.small_blind = true;
+                }
+            }
+        }
+    }
+
+    // where the button and blinds go this hand
+    fn next_positions(&self) -> Positions {
+        let dealt_in = self.table.dealt_in();
+        let heads_up = dealt_in.len() == 2;
+        let first_hand = |button: usize| {
+            let small_blind = if heads_up { button } else { self.next_player(button) };
+            Positions { button, small_blind, big_blind: self.next_player(small_blind) }
+        };
+        if let Some(button) = self.next_button.filter(|seat| dealt_in.contains(seat)) {
+            return first_hand(button);
+        }
+        match self.positions {
+            None => first_hand(dealt_in[0]),
+            Some(last) if heads_up => {
+                let big_blind = self.next_player(last.big_blind);
+                let button = self.next_player(big_blind);
+                Positions { button, small_blind: button, big_blind }
+            },
+            Some(last) => match self.button_rule {
+                ButtonRule::Moving => first_hand(self.next_player(last.button)),
+                ButtonRule::Dead => Positions {
+                    button: last.small_blind,
+                    small_blind: last.big_blind,
+                    big_blind: self.next_player(last.big_blind),
+                },
+            },
+        }
+    }
+
+    // Plays one hand and moves the button on. Does nothing once only one
+    // player is left. A hand that goes wrong, e.g. because a strategy checked
+    // facing a bet, is undone and leaves every stack and the button as they were.
+    pub fn play_round(&mut self) -> Result<(), PokerError> {
+        let table = self.table.clone();
+        let hand_number = self.hand_number;
+        let (positions, next_button) = (self.positions, self.next_button);
+        let missed_blinds = self.missed_blinds.clone();
+        let forced = (self.forced_hole_cards.clone(), self.forced_board.clone(), self.forced_runs.clone());
+        let busted = self.busted.clone();
+        self.history_errors.clear();
+        let played = self.play_hand();
+        if played.is_ok() && self.hand_number != hand_number {
+            self.write_histories();
+        }
+        if played.is_err() {
+            self.table = table;
+            self.hand_number = hand_number;
+            self.positions = positions;
+            self.next_button = next_button;
+            self.missed_blinds = missed_blinds;
+            (self.forced_hole_cards, self.forced_board, self.forced_runs) = forced;
+            self.busted = busted;
+        }
+        played
+    }
+
+    fn play_hand(&mut self) -> Result<(), PokerError> {
         let revealed_card_numbers = [0,3,4,5];
         
-        let mut deck = Deck::new();
+        let mut deck = self.variant.deck();
         let mut action: Vec<Vec<Action>> = Vec::new(); 
         
-        let n_players = self.players.len();
-        if n_players<=1  {return;} 
+        let n_players = self.table.dealt_in().len();
+        if n_players<=1  {return Ok(());} 
+
+        self.hand_number += 1;
+        let positions = self.next_positions();
+        let stud = self.variant.is_stud();
+        // nobody misses a blind in a game without them
+        if let Some(last) = self.positions && !stud {
+            self.mark_missed_blinds(last, positions);
+        }
+        self.positions = Some(positions);
+        self.next_button = None;
+        let first_seat = self.next_player(positions.button);
+        let (seats, mut players) = self.deal_in();
+        let mut record = self.start_record(positions.button, &seats, &players);
+        if !self.bounties.is_empty() {
+            let bounties: Vec<(usize, u32)> = players.iter().map(|p| (p.id, self.bounties.get(&p.id).copied().unwrap_or(0))).collect();
+            for player in &players {
+                if let Some(strategy) = self.strategies.get_mut(&player.id) {
+                    strategy.see_bounties(&bounties);
+                }
+            }
+        }
+
+        let index = |seat: usize| seats.iter().position(|&s| s == seat);
+        let bb_idx = index(positions.big_blind).unwrap();
+        // a small blind or button on an empty seat is dead
+        let sb_idx = index(positions.small_blind);
+        let button_idx = index(positions.button);
+        // the last to act after the flop, whether or not the button is dead
+        let dealer = (index(first_seat).unwrap() + n_players - 1) % n_players;
 
-        for i in 0..2*n_players {
-            let idx = (dealer + 1 + i) % n_players;
-            self.players[idx].deal_card(deck.deal().unwrap());
+        // forced cards come out of the deck first so nothing else can deal them
+        let forced_hole_cards = std::mem::take(&mut self.forced_hole_cards);
+        let forced_board = std::mem::take(&mut self.forced_board);
+        // past the first hand come the cards drawn, taken out as they are dealt
+        let first_hands = forced_hole_cards.values().flat_map(|cards| cards.iter().take(self.variant.hole_cards()));
+        for card in first_hands.chain(forced_board.iter()) {
+            deck.deal_specific(card.0, card.1).ok_or(PokerError::CardUnavailable(*card))?;
+        }
+        let forced_runs = std::mem::take(&mut self.forced_runs);
+        for card in forced_runs.iter().flatten() {
+            deck.deal_specific(card.0, card.1);
         }
 
-        for player in &self.players{
-            player.display();
-            // println!("{}'s hand:",player.name);
-            // Deck::print_cards(&player.hand);
+        // stud deals its cards street by street
+        if !stud {
+            for i in 0..self.variant.hole_cards()*n_players {
+                let idx = (dealer + 1 + i) % n_players;
+                let player = &mut players[idx];
+                let forced = forced_hole_cards.get(&player.id).and_then(|cards| cards.get(player.hand.len()));
+                let card = match forced {
+                    Some(card) => *card,
+                    None => deck.deal().ok_or(PokerError::DeckEmpty)?,
+                };
+                player.deal_card(card)?;
+            }
+
+            for (seat, player) in record.seats.iter_mut().zip(players.iter()) {
+                seat.cards = player.hand.clone();
+                if self.verbose { player.display(); }
+            }
         }
 
-        let mut pots :Vec<u32> = vec![0,0,0,0]; 
-        let mut all_in_player_ids : Vec<Vec<usize>> = vec![vec![],vec![],vec![],vec![]];
         let mut pot = 0; 
+        let straddler = match self.straddle {
+            _ if stud => None,
+            Straddle::UnderTheGun if n_players >= 4 => Some((bb_idx + 1) % n_players),
+            Straddle::Button if n_players >= 3 => button_idx,
+            _ => None,
+        };
 
-        self.players[ (dealer+1) % n_players ].bet_blind(self.small_blind);
-        pot += self.small_blind; 
-        println!("{} bet blind {}, current_bet: {}, pot: {}",self.players[ (dealer+1) % n_players ].name, self.small_blind, self.small_blind, pot);
-        
-        self.players[ (dealer+2) % n_players ].bet_blind(self.big_blind);
-        pot += self.big_blind; 
-        println!("{} bet blind {}, current_bet: {}, pot: {}",self.players[ (dealer+2) % n_players ].name, self.big_blind, self.big_blind, pot);
+        // antes first, though a short stack covers their blind before their ante
+        let mut antes = vec![0; n_players];
+        for i in 1..=n_players {
+            let idx = (dealer + i) % n_players;
+            let owed = match self.ante {
+                Ante::Everyone(ante) => ante,
+                Ante::BigBlind(ante) if idx == bb_idx => ante,
+                _ => 0,
+            };
+            let blind = if stud {
+                0
+            } else if Some(idx) == sb_idx {
+                self.small_blind
+            } else if idx == bb_idx {
+                self.big_blind
+            } else if Some(idx) == straddler {
+                2 * self.big_blind
+            } else {
+                0
+            };
+            let player = &mut players[idx];
+            let ante = owed.min(player.chips.saturating_sub(blind));
+            if ante == 0 {
+                continue;
+            }
+            player.post_ante(ante);
+            antes[idx] = ante;
+            pot += ante;
+            if self.verbose { println!("{} posted ante {}, pot: {}", player.name, ante, pot); }
+            record.actions.push(ActionRecord {
+                street: BettingRoundName::Preflop,
+                player_id: player.id,
+                kind: ActionKind::Ante,
+                amount: ante,
+                to: 0,
+                all_in: player.state == PlayerState::AllIn,
+            });
+        }
+        record.ante = antes.iter().copied().max().unwrap_or(0);
+
+        if stud {
+            let mut betting = Betting { street: BettingRoundName::ThirdStreet, pot, current_bet: 0, street_base: 0, last_raise: self.big_blind, raises: 0, action };
+            self.play_stud_streets(&mut players, &mut deck, &forced_hole_cards, dealer, &mut betting, &mut record)?;
+            return self.end_hand(seats, players, &[Vec::new()], dealer, &antes, record);
+        }
+
+        let mut blinds = Vec::new();
+        if let Some(idx) = sb_idx {
+            blinds.push((idx, self.small_blind, ActionKind::SmallBlind));
+        }
+        blinds.push((bb_idx, self.big_blind, ActionKind::BigBlind));
+        if let Some(idx) = straddler {
+            blinds.push((idx, 2 * self.big_blind, ActionKind::Straddle));
+        }
+
+        let mut current_bet = self.big_blind;
+        for (idx, blind, kind) in blinds {
+            let player = &mut players[idx];
+            let chips_before = player.chips;
+            player.bet_blind(blind);
+            let posted = chips_before - player.chips;
+            pot += posted;
+            current_bet = current_bet.max(player.bet);
+            if self.verbose { println!("{} bet blind {}, current_bet: {}, pot: {}",player.name, posted, blind, pot); }
+            record.actions.push(ActionRecord {
+                street: BettingRoundName::Preflop,
+                player_id: player.id,
+                kind,
+                amount: posted,
+                to: player.bet,
+                all_in: player.state == PlayerState::AllIn,
+            });
+        }
+
+        // back from sitting out, anyone not in the blinds posts what they missed
+        for (idx, player) in players.iter_mut().enumerate() {
+            let Some(missed) = self.missed_blinds.remove(&player.id) else { continue };
+            if idx == bb_idx || Some(idx) == sb_idx || Some(idx) == straddler || !missed.big_blind {
+                continue;
+            }
+            let chips_before = player.chips;
+            player.bet_blind(self.big_blind);
+            let mut posted = vec![(ActionKind::BigBlind, chips_before - player.chips)];
+            if missed.small_blind && player.chips > 0 {
+                let dead = player.post_ante(self.small_blind);
+                antes[idx] += dead;
+                posted.push((ActionKind::DeadBlind, dead));
+            }
+            for (kind, amount) in posted {
+                pot += amount;
+                if self.verbose { println!("{} posted missed blind {}, pot: {}", player.name, amount, pot); }
+                record.actions.push(ActionRecord {
+                    street: BettingRoundName::Preflop,
+                    player_id: player.id,
+                    kind,
+                    amount,
+                    to: player.bet,
+                    all_in: player.state == PlayerState::AllIn,
+                });
+            }
+        }
 
         action.push(vec![Action::Raise(self.small_blind),Action::Raise(self.small_blind)]);
+        if straddler.is_some() {
+            action[0].push(Action::Raise(current_bet - self.big_blind));
+        }
+        // left of the straddle, or else of the big blind, acts first
+        let first_to_act = (straddler.unwrap_or(bb_idx) + n_players - dealer) % n_players + 1;
 
-        let community_cards = std::array::from_fn(|_| deck.deal().unwrap());
+        // the draw games have no board
+        let draws = self.variant.draws();
+        let board_size = if draws > 0 { 0 } else { 5 };
+        let streets = if draws > 0 { &DRAW_ROUND_ORDER[..=draws] } else { ROUND_ORDER };
+        let mut community_cards = [Card(crate::deck::Rank::Two, crate::deck::Suit::Clubs); 5];
+        for (i, card) in community_cards.iter_mut().enumerate().take(board_size) {
+            *card = match forced_board.get(i) {
+                Some(card) => *card,
+                None => deck.deal().ok_or(PokerError::DeckEmpty)?,
+            };
+        }
         let mut street = 0; 
-        let mut current_bet = self.big_blind;
+        let mut revealed_upto = 0;
+        let mut betting = Betting { street: BettingRoundName::Preflop, pot, current_bet, street_base: 0, last_raise: current_bet, raises: if straddler.is_some() { 2 } else { 1 }, action };
 
-        'street: loop {
-            let mut n_active = self.players.iter().filter(|p| p.state == PlayerState::Active).count(); 
-            if n_active<=1 {break}; 
-            // deck.burn_card(); // what
-            
-            let revealed_upto = revealed_card_numbers[street];
+        loop {
+            let n_active = players.iter().filter(|p| p.state == PlayerState::Active).count(); 
+            let live = players.iter().filter(|p| p.state != PlayerState::Folded).count();
+            // players all in still draw, with no more betting
+            if n_active<=1 && (draws == 0 || live <= 1) {break}; 
+            revealed_upto = revealed_card_numbers[street].min(board_size);
 
-            if revealed_upto!=0 {
+            if revealed_upto!=0 && self.verbose {
                 Deck::print_cards(&community_cards[0..revealed_upto]);
             }
 
-            let  mut idx = if street != 0 {
-                action.push(Vec::new());
-                1
-            } else {3};
+            betting.street = streets[street];
+            let first = if street == 0 {
+                (first_to_act + dealer) % n_players
+            } else {
+                betting.street_base = betting.current_bet;
+                betting.last_raise = self.big_blind;
+                betting.raises = 0;
+                betting.action.push(Vec::new());
+                if draws > 0 {
+                    self.draw_cards(&mut players, &mut deck, &forced_hole_cards, dealer, &mut betting, &mut record)?;
+                }
+                (dealer + 1) % n_players
+            };
+            if n_active > 1 {
+                self.bet_street(&mut players, &community_cards[..revealed_upto], first, 0, &mut betting, &mut record)?;
+            }
+            street +=1 ;
+            if self.verbose { println!("Pot: {}",betting.pot); }
+            if street == streets.len() { break } 
+        }
 
-            let mut callers = 0;  
-            let mut n_all_in_this_street = 0; 
-            
-            while callers + n_all_in_this_street < n_active {
-    
-                let player = &mut self.players[(idx+dealer)%n_players];
-                if player.state != PlayerState::Active {
-                    idx = (idx + 1) % n_players;
-                    continue; 
+        let live = players.iter().filter(|p| p.state != PlayerState::Folded).count();
+        let mut boards = vec![community_cards[..board_size].to_vec()];
+        if live > 1 && revealed_upto < board_size && self.run_it > 1 {
+            // as many runs as everyone still in agrees to and the deck allows
+            let board = &community_cards[..revealed_upto];
+            let mut times = self.run_it.min(1 + deck.deck.len() as u32 / (5 - revealed_upto) as u32);
+            for player in players.iter().filter(|p| p.state != PlayerState::Folded) {
+                if let Some(strategy) = self.strategies.get_mut(&player.id) {
+                    times = times.min(strategy.run_it(player, board, times).max(1));
                 }
-                
-                let player_bet = player.bet; 
-                let player_action = player.act(pot, &community_cards[..revealed_upto], current_bet-player_bet, &action); 
-                
-                match player_action {
-                    Action::Check => {
-                        callers+=1; 
-                        println!("{} checked, current_bet: {}, pot: {}",player.name,current_bet, pot);
-                        action[street].push(Action::Check);
-                    },
-                    Action::Fold => {
-                        n_active -=1;  
-                        println!("{} folded",player.name);
-                        action[street].push(Action::Fold);
-                    },
-                    Action::Call => {
-                        callers+=1; 
-                        // players old bet was player_bet, now its current_bet
-                        pot += current_bet-player_bet; 
-                        println!("{} called {}, current_bet: {}, pot: {}",player.name, current_bet-player_bet, current_bet, pot);
-                        action[street].push(Action::Call);
-                    },
-                    Action::Raise(raise) => {
-                        // players old bet was player_bet, now its current_bet + raise 
-                        // current bet should be incremented by raise 
-                        callers = 1;
-                        pot += raise + current_bet - player_bet;
-                        current_bet += raise;
-                        println!("{} raised {}, current_bet: {}, pot: {}",player.name, raise, current_bet, pot);
-                        action[street].push(Action::Raise(raise));
-                    },
-                    Action::AllIn(chips) => {
+            }
+            if times > 1 && self.verbose { println!("Running it {} times", times); }
+            for run in 1..times as usize {
+                let mut runout = community_cards;
+                for (i, card) in runout.iter_mut().enumerate().skip(revealed_upto) {
+                    *card = match forced_runs.get(run - 1) {
+                        Some(forced) => forced[i],
+                        None => deck.deal().ok_or(PokerError::DeckEmpty)?,
+                    };
+                }
+                boards.push(runout.to_vec());
+            }
+        }
+        if live > 1 {
+            revealed_upto = board_size;
+        }
+        record.board = community_cards[..revealed_upto].to_vec();
+
+        self.end_hand(seats, players, &boards, dealer, &antes, record)
+    }
+
+    // One street of betting, starting with the player at index `first` and
+    // going round until everyone who can still bet has matched the last bet
+    // or raise. `acted` is one when a forced bet already acted for its player
+    // and closes the betting unless someone raises, as a bring-in does.
+    fn bet_street(&mut self, players: &mut [Player], board: &[Card], first: usize, acted: usize, betting: &mut Betting, record: &mut HandRecord) -> Result<(), PokerError> {
+        let n_players = players.len();
+        let street = betting.street;
+        let mut n_active = players.iter().filter(|p| p.state == PlayerState::Active).count();
+        let (mut pot, mut current_bet, street_base) = (betting.pot, betting.current_bet, betting.street_base);
+        let (mut last_raise, mut raises) = (betting.last_raise, betting.raises);
+        let last = betting.action.len() - 1;
+        let mut idx = first;
+        let mut callers = acted;
+        let mut n_all_in_this_street = 0;
+
+        while callers + n_all_in_this_street < n_active {
+
+            let heads_up = players.iter().filter(|p| p.state != PlayerState::Folded).count() == 2;
+            let player = &mut players[idx];
+            if player.state != PlayerState::Active {
+                idx = (idx + 1) % n_players;
+                continue; 
+            }
+            
+            let player_bet = player.bet; 
+            let to_call = current_bet - player_bet;
+            let decided = match self.strategies.get_mut(&player.id) {
+                Some(strategy) => strategy.decide(player, pot, board, to_call, &betting.action),
+                None => player.random_action(pot, board, to_call, &betting.action),
+            };
+            let state = BettingState {
+                street,
+                draws: self.variant.draws(),
+                big_blind: self.big_blind,
+                pot,
+                to_call,
+                chips: player.chips,
+                last_raise,
+                raises,
+                heads_up,
+            };
+            let player_action = player.apply(self.betting.fit(decided, &state), to_call)?;
+            // completing a bring-in is the street's first full bet
+            let bet_or_raise = if current_bet == street_base || raises == 0 { ActionKind::Bet } else { ActionKind::Raise };
+            let kind = match player_action {
+                Action::Check => {
+                    callers+=1; 
+                    if self.verbose { println!("{} checked, current_bet: {}, pot: {}",player.name,current_bet, pot); }
+                    betting.action[last].push(Action::Check);
+                    ActionKind::Check
+                },
+                Action::Fold => {
+                    n_active -=1;  
+                    if self.verbose { println!("{} folded",player.name); }
+                    betting.action[last].push(Action::Fold);
+                    ActionKind::Fold
+                },
+                Action::Call => {
+                    callers+=1; 
+                    // players old bet was player_bet, now its current_bet
+                    pot += current_bet-player_bet; 
+                    if self.verbose { println!("{} called {}, current_bet: {}, pot: {}",player.name, current_bet-player_bet, current_bet, pot); }
+                    betting.action[last].push(Action::Call);
+                    ActionKind::Call
+                },
+                Action::Raise(raise) => {
+                    // players old bet was player_bet, now its current_bet + raise 
+                    // current bet should be incremented by raise 
+                    // the raiser has matched their own bet, unless that was all they had
+                    if player.state == PlayerState::AllIn {
+                        callers = 0;
                         n_all_in_this_street += 1;
-                        all_in_player_ids[street].push(player.id);
-                        if chips > current_bet - player_bet {
-                            callers = 0;
-                            current_bet = chips + player_bet; 
-                        }
-                        pot += chips; 
-                        println!("{} went all in for {}, current_bet: {}, pot: {}",player.name, chips, current_bet, pot);
-                        action[street].push(Action::AllIn(chips));
+                    } else {
+                        callers = 1;
+                    }
+                    last_raise = last_raise.max(raise);
+                    raises += 1;
+                    pot += raise + current_bet - player_bet;
+                    current_bet += raise;
+                    if self.verbose { println!("{} raised {}, current_bet: {}, pot: {}",player.name, raise, current_bet, pot); }
+                    betting.action[last].push(Action::Raise(raise));
+                    bet_or_raise
+                },
+                // Player::apply refuses a draw
+                Action::Draw(_) => unreachable!(),
+                Action::AllIn(chips) => {
+                    n_all_in_this_street += 1;
+                    let kind = if chips > to_call {
+                        callers = 0;
+                        // all in for less than a full raise leaves the size to match as it was
+                        last_raise = last_raise.max(chips - to_call);
+                        raises += 1;
+                        current_bet = chips + player_bet; 
+                        bet_or_raise
+                    } else {
+                        ActionKind::Call
+                    };
+                    pot += chips; 
+                    if self.verbose { println!("{} went all in for {}, current_bet: {}, pot: {}",player.name, chips, current_bet, pot); }
+                    betting.action[last].push(Action::AllIn(chips));
+                    kind
+                }
+            };
+            record.actions.push(ActionRecord {
+                street,
+                player_id: player.id,
+                kind,
+                amount: player.bet - player_bet,
+                to: player.bet - street_base,
+                all_in: player.state == PlayerState::AllIn,
+            });
+            idx = (idx+1) % n_players; 
+
+            if n_active <=1 {break}
+        }
+        betting.pot = pot;
+        betting.current_bet = current_bet;
+        betting.last_raise = last_raise;
+        betting.raises = raises;
+        Ok(())
+    }
+
+    // Stud's five streets: two cards down and one up to start, three more up
+    // and the last one down, with a round of betting after each. The lowest
+    // card showing, or the highest in a lowball game, brings in on third
+    // street with suits breaking ties in bridge order, and the best hand
+    // showing starts the betting on every street after. Once nobody is left
+    // to bet against, the cards are dealt out without it.
+    fn play_stud_streets(&mut self, players: &mut [Player], deck: &mut Deck, forced: &HashMap<usize, Vec<Card>>, dealer: usize, betting: &mut Betting, record: &mut HandRecord) -> Result<(), PokerError> {
+        let n_players = players.len();
+        let ranking = self.variant.ranking();
+        // everyone in the order they sit, starting left of the button
+        let order: Vec<usize> = (1..=n_players).map(|i| (dealer + i) % n_players).collect();
+        for (street, &name) in STUD_ROUND_ORDER.iter().enumerate() {
+            if players.iter().filter(|p| p.state != PlayerState::Folded).count() <= 1 {
+                break;
+            }
+            let face_up: &[bool] = match street {
+                0 => &[false, false, true],
+                4 => &[false],
+                _ => &[true],
+            };
+            for &up in face_up {
+                for &idx in &order {
+                    let player = &mut players[idx];
+                    if player.state == PlayerState::Folded {
+                        continue;
                     }
+                    let card = match forced.get(&player.id).and_then(|cards| cards.get(player.hand.len())) {
+                        Some(card) => *card,
+                        None => deck.deal().ok_or(PokerError::DeckEmpty)?,
+                    };
+                    if up { player.deal_card_up(card)? } else { player.deal_card(card)? }
+                }
+            }
+            let up_cards: Vec<(usize, Vec<Card>)> = players.iter().filter(|p| p.state != PlayerState::Folded).map(|p| (p.id, p.up_cards.clone())).collect();
+            for player in players.iter() {
+                if let Some(strategy) = self.strategies.get_mut(&player.id) {
+                    strategy.see_up_cards(&up_cards);
                 }
-                idx = (idx+1) % n_players; 
+                if self.verbose && player.state != PlayerState::Folded { player.display(); }
+            }
 
-                if n_active <=1 {break 'street}
-                // println!("agreed: {} ,all in: {}, folded: {}", agreed_players, n_all_in, n_folded);
-                // println!(" {} + {} + {} < {} : {}",agreed_players, n_all_in, n_folded, n_players, (agreed_players + n_all_in + n_folded < n_players));
+            if players.iter().filter(|p| p.state == PlayerState::Active).count() <= 1 {
+                continue;
+            }
+            let (first, acted) = if street == 0 {
+                let card_order = |card: &Card| (ranking.value(card.0), bridge_order(card.1));
+                let showing = order.iter().copied().filter(|&idx| players[idx].state == PlayerState::Active);
+                let bring_in = if ranking.is_low() {
+                    showing.max_by_key(|&idx| card_order(&players[idx].up_cards[0]))
+                } else {
+                    showing.min_by_key(|&idx| card_order(&players[idx].up_cards[0]))
+                }.unwrap();
+                let player = &mut players[bring_in];
+                let chips_before = player.chips;
+                player.bet_blind(self.small_blind);
+                let posted = chips_before - player.chips;
+                betting.pot += posted;
+                betting.current_bet = self.small_blind.max(player.bet);
+                betting.action.push(vec![Action::Raise(self.small_blind)]);
+                if self.verbose { println!("{} brought in for {}, pot: {}", player.name, posted, betting.pot); }
+                record.actions.push(ActionRecord {
+                    street: name,
+                    player_id: player.id,
+                    kind: ActionKind::BringIn,
+                    amount: posted,
+                    to: player.bet,
+                    all_in: player.state == PlayerState::AllIn,
+                });
+                ((bring_in + 1) % n_players, usize::from(player.state == PlayerState::Active))
+            } else {
+                betting.street_base = betting.current_bet;
+                betting.last_raise = self.big_blind;
+                betting.raises = 0;
+                betting.action.push(Vec::new());
+                // ties go to whoever sits first from the button
+                let best = order.iter().copied()
+                    .filter(|&idx| players[idx].state != PlayerState::Folded)
+                    .reduce(|best, idx| if HandComparator::compare_showing(&players[idx].up_cards, &players[best].up_cards, ranking).is_gt() { idx } else { best })
+                    .unwrap();
+                (best, 0)
+            };
+            betting.street = name;
+            self.bet_street(players, &[], first, acted, betting, record)?;
+            if self.verbose { println!("Pot: {}", betting.pot); }
+        }
+        for (seat, player) in record.seats.iter_mut().zip(players.iter()) {
+            seat.cards = player.hand.clone();
+            seat.up_cards = player.up_cards.clone();
+        }
+        Ok(())
+    }
+
+    // Everyone still in, starting left of the button, throws away the cards
+    // they choose and is dealt as many new ones before the street's betting.
+    // Forced cards for a player go on from their first hand to the cards
+    // they draw, in the order they are dealt.
+    fn draw_cards(&mut self, players: &mut [Player], deck: &mut Deck, forced: &HashMap<usize, Vec<Card>>, dealer: usize, betting: &mut Betting, record: &mut HandRecord) -> Result<(), PokerError> {
+        let n_players = players.len();
+        for idx in (1..=n_players).map(|i| (dealer + i) % n_players) {
+            let player = &mut players[idx];
+            if player.state == PlayerState::Folded {
+                continue;
+            }
+            let decided = match self.strategies.get_mut(&player.id) {
+                Some(strategy) => strategy.draw(player, self.variant, &betting.action),
+                None => player.random_draw(),
+            };
+            let discarded = player.discard(decided)?;
+            let dealt = self.variant.hole_cards() + record.draws.iter().filter(|d| d.player_id == player.id).map(|d| d.drawn.len()).sum::<usize>();
+            let mut drawn = Vec::new();
+            for dealt in dealt..dealt + discarded.len() {
+                let card = match forced.get(&player.id).and_then(|cards| cards.get(dealt)) {
+                    Some(card) => {
+                        deck.reshuffle();
+                        deck.deal_specific(card.0, card.1).ok_or(PokerError::CardUnavailable(*card))?
+                    },
+                    None => deck.deal_replacement().ok_or(PokerError::DeckEmpty)?,
+                };
+                player.deal_card(card)?;
+                drawn.push(card);
+            }
+            deck.discard(&discarded);
+            if self.verbose { println!("{} drew {}", player.name, drawn.len()); }
+            betting.action.last_mut().unwrap().push(decided);
+            record.draws.push(DrawRecord { street: betting.street, player_id: player.id, discarded, drawn });
+        }
+        Ok(())
+    }
+
+    // Settles the pots and sends everyone back to their seats, apart from
+    // anyone who busted, then writes the hand to every history.
+    fn end_hand(&mut self, seats: Vec<usize>, mut players: Vec<Player>, boards: &[Vec<Card>], dealer: usize, antes: &[u32], mut record: HandRecord) -> Result<(), PokerError> {
+        self.showdown(&mut players, boards, dealer, antes, &mut record)?;
+
+        // back to their seats, apart from anyone who busted
+        self.busted.clear();
+        for (seat, mut player) in seats.into_iter().zip(players) {
+            player.reset();
+            if player.chips > 0 {
+                self.table.sit(seat, player)?;
+            } else if self.keep_busted {
+                self.busted.push(player.clone());
+                player.state = PlayerState::SittingOut;
+                self.table.sit(seat, player)?;
+            } else {
+                self.busted.push(player);
             }
-            pots[street]=pot;
-            street +=1 ;
-            pot = 0; 
-            println!("Pots: {:?}",pots);
-            if street > 3{ break 'street } 
         }
 
-        self.showdown(community_cards,pots, all_in_player_ids); 
+        self.last_hand = Some(record);
+        Ok(())
+    }
 
+    // once the hand is over, so every writer records the same hands
+    fn write_histories(&mut self) {
+        let Some(record) = &self.last_hand else { return };
+        for writer in self.history.iter_mut() {
+            if let Err(err) = writer.write_hand(record) {
+                self.history_errors.push(PokerError::HistoryWrite(err.to_string()));
+            }
+        }
     }
 
 }
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::betting::BettingStructure;
+    use crate::history::ActionKind;
+    use crate::rake::RakeAttribution;
+    use crate::table::Position;
 
     #[test]
     fn test_game_initialization() {
-        let game = Game::new(4,500);
-        assert_eq!(game.players.len(), 4);
+        let game = Game::new(4,500).unwrap();
+        assert_eq!(game.players().len(), 4);
         assert_eq!(game.small_blind, 1);
         assert_eq!(game.big_blind, 2);
     }
 
     #[test]
-    #[should_panic]
     fn test_game_initialization_too_many_players() {
-        Game::new(23,500); // Should panic because the maximum number of players is 22
+        // the maximum number of players is 22
+        assert_eq!(Game::new(23,500).err(), Some(PokerError::TooManyPlayers { requested: 23, max: 22 }));
+    }
+
+    #[test]
+    fn test_invalid_strategy_action_undoes_hand() {
+        struct AlwaysCheck;
+        impl Strategy for AlwaysCheck {
+            fn decide(&mut self, _player: &Player, _pot: u32, _board: &[Card], _to_call: u32, _action: &[Vec<Action>]) -> Action {
+                Action::Check
+            }
+        }
+        let mut game = Game::new(3,500).unwrap();
+        game.set_strategy(0, Box::new(AlwaysCheck));
+        let ace_king = vec![Card::from_code("Ah").unwrap(), Card::from_code("Kh").unwrap()];
+        game.force_cards(HashMap::from([(1, ace_king.clone())]), Vec::new()).unwrap();
+        game.missed_blinds.insert(2, MissedBlinds { small_blind: false, big_blind: true });
+        // player 0 is first to act, facing the big blind
+        assert_eq!(game.play_round(), Err(PokerError::InvalidAction { player_id: 0, action: Action::Check, to_call: 2 }));
+        assert!(game.players().iter().all(|p| p.chips == 500 && p.hand.is_empty() && p.bet == 0));
+        assert!(game.last_hand().is_none());
+        // the forced cards and missed blinds wait for the hand that is played
+        assert_eq!(game.forced_hole_cards, HashMap::from([(1, ace_king)]));
+        assert_eq!(game.missed_blinds.get(&2), Some(&MissedBlinds { small_blind: false, big_blind: true }));
+    }
+
+    #[test]
+    fn test_force_cards() {
+        let mut game = Game::new(3,500).unwrap();
+        let cards = |codes: &str| codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect::<Vec<_>>();
+        let clash = HashMap::from([(0, cards("Ah Kh")), (1, cards("Ah Qd"))]);
+        assert!(matches!(game.force_cards(clash, Vec::new()), Err(PokerError::CardUnavailable(_))));
+        let too_many = HashMap::from([(0, cards("Ah Kh Qh"))]);
+        assert_eq!(game.force_cards(too_many, Vec::new()), Err(PokerError::HandFull { player_id: 0 }));
+        assert_eq!(game.force_cards(HashMap::new(), cards("2c 3d 4h 5s 6c 7d")), Err(PokerError::InvalidBoard { cards: 6 }));
+        assert_eq!(game.force_runs(vec![cards("2c 3d 4h 5s")]), Err(PokerError::InvalidBoard { cards: 4 }));
+
+        game.force_cards(HashMap::from([(2, cards("7c 7d"))]), cards("2c 3d 4h")).unwrap();
+        game.play_round().unwrap();
+        let hand = game.last_hand().unwrap();
+        assert_eq!(hand.seats[2].cards, cards("7c 7d"));
+        assert!(hand.board.len() < 3 || hand.board[..3] == cards("2c 3d 4h")[..]);
     }
 
     #[test]
     fn test_play_round() {
-        let mut game = Game::new(4,500);
-        game.play_round(0);
+        let mut game = Game::new(4,500).unwrap();
+        game.play_round().unwrap();
+    }
+
+    fn deal(game: &mut Game, hands: &[&str]) -> (Vec<usize>, Vec<Player>, [Card;5]) {
+        let (seats, mut players) = game.deal_in();
+        for (player, hand) in players.iter_mut().zip(hands) {
+            for code in hand.split_whitespace() {
+                player.deal_card(Card::from_code(code).unwrap()).unwrap();
+            }
+        }
+        let board: Vec<Card> = "2c 7d 9h Js 3s".split_whitespace().map(|c| Card::from_code(c).unwrap()).collect();
+        (seats, players, board.try_into().unwrap())
     }
 
     #[test]
     fn test_showdown() {
-        let mut game = Game::new(4,500);
-        let mut deck = Deck::new(); 
-        let community_cards = [
-            deck.deal().unwrap(),
-            deck.deal().unwrap(),
-            deck.deal().unwrap(),
-            deck.deal().unwrap(),
-            deck.deal().unwrap(),
-        ];
-        game.showdown(community_cards,vec![500,0,0,0],vec![vec![],vec![],vec![],vec![]]);
+        let mut game = Game::new(4,500).unwrap();
+        let (seats, mut players, community_cards) = deal(&mut game, &["Ah Ad", "Kh Kd", "Qh Qd", "5h 4d"]);
+        for player in players.iter_mut() {
+            player.bet = 125;
+            player.chips -= 125;
+        }
+        let mut record = game.start_record(1, &seats, &players);
+        game.showdown(&mut players, &[community_cards.to_vec()], 0, &[0; 4], &mut record).unwrap();
+        assert_eq!(record.pots, vec![PotRecord { amount: 500, rake: 0, winners: vec![(0, 500)] }]);
+        assert_eq!(record.showdown.len(), 4);
+        assert_eq!(players[0].chips, 875);
+        assert_eq!(players.iter().map(|p| p.chips).sum::<u32>(), 2000);
+    }
+
+    #[test]
+    fn test_showdown_side_pots() {
+        let mut game = Game::new(3,500).unwrap();
+        let (seats, mut players, community_cards) = deal(&mut game, &["Ah Ad", "Kh Kd", "Qh Qd"]);
+        // the best hand is all in for the least, the second best for a bit more
+        players[0].bet = 100;
+        players[0].chips = 0;
+        players[0].state = PlayerState::AllIn;
+        players[1].bet = 300;
+        players[1].chips = 0;
+        players[1].state = PlayerState::AllIn;
+        players[2].bet = 500;
+        players[2].chips = 0;
+        let mut record = game.start_record(1, &seats, &players);
+        game.showdown(&mut players, &[community_cards.to_vec()], 0, &[0; 3], &mut record).unwrap();
+        assert_eq!(record.uncalled, Some((2, 200)));
+        assert_eq!(record.pots, vec![
+            PotRecord { amount: 300, rake: 0, winners: vec![(0, 300)] },
+            PotRecord { amount: 400, rake: 0, winners: vec![(1, 400)] },
+        ]);
+        assert_eq!(players.iter().map(|p| p.chips).sum::<u32>(), 900);
+        assert_eq!(players.iter().map(|p| p.chips).collect::<Vec<_>>(), [300, 400, 200]);
+    }
+
+    #[test]
+    fn test_split_pots_rake() {
+        // a main pot of 300 and a side pot of 400 share a rake of 36
+        let hands = vec![vec![Some(3), Some(2), Some(1)]];
+        let pots = Game::split_pots(&[100, 300, 300], &[true; 3], &hands, &[], |pro: &u32, opp: &u32| pro.cmp(opp), 36).unwrap();
+        assert_eq!(pots, vec![vec![
+            PotRecord { amount: 284, rake: 16, winners: vec![(0, 284)] },
+            PotRecord { amount: 380, rake: 20, winners: vec![(1, 380)] },
+        ]]);
+
+        // run twice, each run takes half of each pot and of its rake
+        let pots = Game::split_pots(&[100, 300, 300], &[true; 3], &[hands[0].clone(), hands[0].clone()], &[], |pro: &u32, opp: &u32| pro.cmp(opp), 36).unwrap();
+        assert_eq!(pots[0][0], PotRecord { amount: 142, rake: 8, winners: vec![(0, 142)] });
+        assert_eq!(combine_runs(&pots), vec![
+            PotRecord { amount: 284, rake: 16, winners: vec![(0, 284)] },
+            PotRecord { amount: 380, rake: 20, winners: vec![(1, 380)] },
+        ]);
+    }
+
+    #[test]
+    fn test_showdown_split_pot_odd_chip() {
+        let mut game = Game::new(3,500).unwrap();
+        let (seats, mut players, community_cards) = deal(&mut game, &["Ah Kd", "Ad Kh", "5h 4d"]);
+        players[0].bet = 101;
+        players[1].bet = 101;
+        players[2].bet = 101;
+        players[2].state = PlayerState::Folded;
+        let mut record = game.start_record(1, &seats, &players);
+        game.showdown(&mut players, &[community_cards.to_vec()], 0, &[0; 3], &mut record).unwrap();
+        // the player left of the button gets the odd chip
+        assert_eq!(record.pots, vec![PotRecord { amount: 303, rake: 0, winners: vec![(1, 152), (0, 151)] }]);
+        assert_eq!(record.showdown.len(), 2);
+    }
+
+    #[test]
+    fn test_hand_record() {
+        let mut game = Game::new(4,500).unwrap();
+        game.set_table_name("Test Table");
+        game.play_round().unwrap();
+        let record = game.last_hand().unwrap();
+        assert_eq!(record.table_name, "Test Table");
+        assert_eq!(record.hand_id, 1);
+        assert_eq!(record.button_seat, 1);
+        assert_eq!(record.seats.len(), 4);
+        assert!(record.seats.iter().all(|s| s.cards.len() == 2));
+        assert_eq!(record.actions[0].kind, ActionKind::SmallBlind);
+        assert_eq!(record.actions[0].player_id, 1);
+        assert_eq!(record.actions[1].kind, ActionKind::BigBlind);
+        assert_eq!(record.actions[1].player_id, 2);
+
+        let put_in: u32 = record.actions.iter().map(|a| a.amount).sum();
+        let returned = record.uncalled.map_or(0, |(_, amount)| amount);
+        assert_eq!(record.total_pot(), put_in - returned);
+        let stacks: u32 = game.players().iter().map(|p| p.chips).sum();
+        assert_eq!(stacks, 2000);
+    }
+
+    #[test]
+    fn test_antes_and_straddle() {
+        for (straddle, straddler, first) in [(Straddle::UnderTheGun, 3, 0), (Straddle::Button, 0, 1)] {
+            let mut game = Game::new(4,500).unwrap();
+            game.set_verbose(false);
+            game.set_ante(Ante::Everyone(5));
+            game.set_straddle(straddle);
+            for id in 0..4 {
+                game.set_strategy(id, Box::new(crate::strategy::CallingStation));
+            }
+            game.play_round().unwrap();
+            let record = game.last_hand().unwrap();
+            assert_eq!(record.ante, 5);
+            let kinds: Vec<ActionKind> = record.actions.iter().take(7).map(|a| a.kind).collect();
+            assert_eq!(kinds[..4], [ActionKind::Ante; 4]);
+            assert_eq!(kinds[4..], [ActionKind::SmallBlind, ActionKind::BigBlind, ActionKind::Straddle]);
+            assert_eq!((record.actions[6].player_id, record.actions[6].to), (straddler, 4));
+            // left of the straddler acts first and the straddler last
+            let preflop: Vec<usize> = record.actions_on(BettingRoundName::Preflop).skip(7).map(|a| a.player_id).collect();
+            assert_eq!(preflop.first(), Some(&first));
+            assert_eq!(preflop.last(), Some(&straddler));
+            assert_eq!(record.pots.iter().map(|p| p.amount).sum::<u32>(), 4 * 5 + 4 * 4);
+            assert_eq!(game.players().iter().map(|p| p.chips).sum::<u32>(), 2000);
+        }
+    }
+
+    #[test]
+    fn test_big_blind_ante() {
+        let players = [500, 500, 3].iter().enumerate().map(|(id, &chips)| Player::new(id, format!("P{}", id), chips)).collect();
+        let mut game = Game::with_players(players, 1, 2).unwrap();
+        game.set_verbose(false);
+        game.set_ante(Ante::BigBlind(10));
+        game.play_round().unwrap();
+        let record = game.last_hand().unwrap();
+        // the short big blind covers the blind first
+        assert_eq!(record.actions[0], ActionRecord {
+            street: BettingRoundName::Preflop,
+            player_id: 2,
+            kind: ActionKind::Ante,
+            amount: 1,
+            to: 0,
+            all_in: false,
+        });
+        assert_eq!((record.actions[2].kind, record.actions[2].amount, record.actions[2].all_in), (ActionKind::BigBlind, 2, true));
+        assert_eq!(record.actions.iter().filter(|a| a.kind == ActionKind::Ante).count(), 1);
+    }
+
+    fn calling_game(n_players: u32) -> Game {
+        let mut game = Game::new(n_players, 1000).unwrap();
+        game.set_verbose(false);
+        for id in 0..n_players as usize {
+            game.set_strategy(id, Box::new(crate::strategy::CallingStation));
+        }
+        game
+    }
+
+    #[test]
+    fn test_button_moves_every_hand() {
+        let mut game = calling_game(3);
+        let mut buttons = Vec::new();
+        for _ in 0..4 {
+            game.play_round().unwrap();
+            buttons.push(game.positions().unwrap());
+        }
+        assert_eq!(buttons[0], Positions { button: 1, small_blind: 2, big_blind: 3 });
+        assert_eq!(buttons.iter().map(|p| p.button).collect::<Vec<_>>(), [1, 2, 3, 1]);
+        game.set_button(3);
+        game.play_round().unwrap();
+        assert_eq!(game.last_hand().unwrap().button_seat, 3);
+    }
+
+    #[test]
+    fn test_heads_up() {
+        let mut game = calling_game(2);
+        game.play_round().unwrap();
+        let record = game.last_hand().unwrap();
+        // the button posts the small blind and acts first, then last after the flop
+        assert_eq!(game.positions(), Some(Positions { button: 1, small_blind: 1, big_blind: 2 }));
+        let first = |street| record.actions_on(street).find(|a| !a.kind.is_forced()).unwrap().player_id;
+        assert_eq!(record.actions[0].kind, ActionKind::SmallBlind);
+        assert_eq!(record.actions[0].player_id, 0);
+        assert_eq!(first(BettingRoundName::Preflop), 0);
+        assert_eq!(first(BettingRoundName::Flop), 1);
+        game.play_round().unwrap();
+        assert_eq!(game.positions(), Some(Positions { button: 2, small_blind: 2, big_blind: 1 }));
+    }
+
+    #[test]
+    fn test_busted_players() {
+        let last = Positions { button: 1, small_blind: 2, big_blind: 3 };
+        let mut game = calling_game(4);
+        game.positions = Some(last);
+        game.leave(3);
+        assert_eq!(game.next_positions(), Positions { button: 2, small_blind: 4, big_blind: 1 });
+
+        // the big blind still moves on one seat, so the small blind is dead
+        game.set_button_rule(ButtonRule::Dead);
+        assert_eq!(game.next_positions(), Positions { button: 2, small_blind: 3, big_blind: 4 });
+        game.play_round().unwrap();
+        let record = game.last_hand().unwrap();
+        assert!(game.busted().is_empty());
+        assert_eq!(record.button_seat, 2);
+        assert!(record.actions.iter().all(|a| a.kind != ActionKind::SmallBlind));
+        assert_eq!((record.actions[0].kind, record.actions[0].player_id), (ActionKind::BigBlind, 3));
+        assert_eq!(record.actions[1].player_id, 0);
+        game.play_round().unwrap();
+        // the button is now on the empty seat
+        assert_eq!(game.positions(), Some(Positions { button: 3, small_blind: 4, big_blind: 1 }));
+
+        // down to two players the big blind keeps moving and the button posts the small blind
+        game.leave(1);
+        assert_eq!(game.next_positions(), Positions { button: 4, small_blind: 4, big_blind: 2 });
+    }
+
+    #[test]
+    fn test_betting_structures() {
+        let aggressive_game = |n_players: u32, betting| {
+            let mut game = Game::new(n_players, 1000).unwrap();
+            game.set_verbose(false);
+            game.set_betting_structure(betting).unwrap();
+            for id in 0..n_players as usize {
+                game.set_strategy(id, Box::new(crate::strategy::Aggressive { pot_fraction: 10.0 }));
+            }
+            game.play_round().unwrap();
+            game.last_hand().unwrap().clone()
+        };
+        let raised_to = |record: &HandRecord, street| {
+            record.actions_on(street).filter(|a| !a.kind.is_forced()).map(|a| a.to).collect::<Vec<_>>()
+        };
+
+        // calling 2 into 3 makes the pot 5, so the button can raise to 7, and
+        // the small blind calling 6 into 10 can raise 16 more
+        let record = aggressive_game(3, BettingStructure::PotLimit);
+        assert_eq!(raised_to(&record, BettingRoundName::Preflop)[..2], [7, 23]);
+
+        // the big blind and three raises, then only calls, with double bets from the turn
+        let record = aggressive_game(4, BettingStructure::FixedLimit { cap: 4 });
+        assert_eq!(raised_to(&record, BettingRoundName::Preflop), [4, 6, 8, 8, 8, 8]);
+        assert_eq!(raised_to(&record, BettingRoundName::Turn), [4, 8, 12, 16, 16, 16, 16]);
+
+        // heads up there is no cap
+        let record = aggressive_game(2, BettingStructure::FixedLimit { cap: 4 });
+        assert!(raised_to(&record, BettingRoundName::Preflop).iter().any(|&to| to > 8));
+    }
+
+    #[test]
+    fn test_seats_are_kept() {
+        let mut game = calling_game(3);
+        game.set_max_seats(6);
+        game.sit(5, Player::new(7, "Dave".to_string(), 1000)).unwrap();
+        game.set_strategy(7, Box::new(crate::strategy::CallingStation));
+        assert_eq!(game.sit(5, Player::new(8, "Erin".to_string(), 1000)), Err(PokerError::SeatTaken(5)));
+        game.sit_out(2).unwrap();
+        game.play_round().unwrap();
+        let record = game.last_hand().unwrap();
+        assert_eq!(record.seats.iter().map(|s| s.seat).collect::<Vec<_>>(), [1, 3, 5]);
+        assert_eq!(record.positions(), [(0, Position::Button), (2, Position::SmallBlind), (7, Position::BigBlind)]);
+        assert_eq!(game.table().player(2).unwrap().chips, 1000);
+
+        // back in, and the button moves on to them
+        game.sit_in(2).unwrap();
+        game.play_round().unwrap();
+        let record = game.last_hand().unwrap();
+        assert_eq!(record.max_seats, 6);
+        assert_eq!(record.positions(), [
+            (0, Position::UnderTheGun(0)),
+            (1, Position::Button),
+            (2, Position::SmallBlind),
+            (7, Position::BigBlind),
+        ]);
+    }
+
+    #[test]
+    fn test_missed_blinds() {
+        let mut game = calling_game(4);
+        game.play_round().unwrap();
+        // seat 4 sits out as the big blind passes it, then the small blind
+        game.sit_out(4).unwrap();
+        assert_eq!(game.table().player(4).unwrap().state, PlayerState::SittingOut);
+        game.play_round().unwrap();
+        assert_eq!(game.missed_blinds(3), MissedBlinds { small_blind: false, big_blind: true });
+        game.play_round().unwrap();
+        assert_eq!(game.missed_blinds(3), MissedBlinds { small_blind: true, big_blind: true });
+
+        game.sit_in(4).unwrap();
+        game.play_round().unwrap();
+        let record = game.last_hand().unwrap();
+        let posted: Vec<(ActionKind, u32)> = record.actions.iter().filter(|a| a.player_id == 3 && a.kind.is_forced()).map(|a| (a.kind, a.amount)).collect();
+        assert_eq!(posted, [(ActionKind::BigBlind, 2), (ActionKind::DeadBlind, 1)]);
+        assert_eq!(record.positions()[0], (0, Position::SmallBlind));
+        assert_eq!(game.missed_blinds(3), MissedBlinds::default());
+        assert_eq!(crate::replay::verify(record), Ok(()));
+        assert_eq!(game.players().iter().map(|p| p.chips).sum::<u32>(), 4000);
+    }
+
+    #[test]
+    fn test_keep_busted() {
+        let mut game = Game::with_players(vec![Player::new(0, "A".to_string(), 100), Player::new(1, "B".to_string(), 100)], 1, 2).unwrap();
+        game.set_verbose(false);
+        game.set_keep_busted(true);
+        for id in 0..2 {
+            game.set_strategy(id, Box::new(crate::strategy::Aggressive { pot_fraction: 100.0 }));
+        }
+        // fixed cards so the hand can't end in a split pot
+        let cards = |codes: &str| codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect::<Vec<_>>();
+        game.force_cards(HashMap::from([(0, cards("As Ad")), (1, cards("7c 2d"))]), cards("Kh Qs 9c 5d 3h")).unwrap();
+        game.play_round().unwrap();
+        let loser = game.busted()[0].id;
+        let seat = game.table().seat_of(loser).unwrap();
+        assert_eq!(game.table().player(seat).unwrap().state, PlayerState::SittingOut);
+        assert_eq!(game.table().dealt_in().len(), 1);
+        game.add_chips(seat, 100).unwrap();
+        game.sit_in(seat).unwrap();
+        assert_eq!(game.table().dealt_in().len(), 2);
+        assert_eq!(game.add_chips(3, 100), Err(PokerError::NoSuchSeat(3)));
+    }
+
+    #[test]
+    fn test_rake() {
+        let players = (0..3).map(|id| Player::new(id, format!("P{}", id + 1), 100)).collect();
+        let mut game = Game::with_players(players, 1, 2).unwrap();
+        game.set_verbose(false);
+        for id in 0..3 {
+            game.set_strategy(id, Box::new(crate::strategy::CallingStation));
+        }
+        game.set_rake(Rake { cap: Some(2), ..Rake::percent(50.0) });
+        game.play_round().unwrap();
+        let hand = game.last_hand().unwrap();
+        assert_eq!((hand.rake, hand.total_pot()), (2, 6));
+        assert_eq!(hand.pots.iter().map(|p| p.amount).sum::<u32>(), 4);
+        assert_eq!(hand.rake_paid(0, RakeAttribution::Dealt), 2.0 / 3.0);
+        assert_eq!(game.players().iter().map(|p| p.chips).sum::<u32>(), 298);
+
+        // everyone folds to the big blind, whose uncalled chip is not raked
+        for id in 0..3 {
+            game.set_strategy(id, Box::new(crate::replay::ReplayStrategy::new(Vec::new())));
+        }
+        game.set_rake(Rake::percent(50.0));
+        game.play_round().unwrap();
+        let hand = game.last_hand().unwrap();
+        assert_eq!((hand.rake, hand.total_pot()), (1, 2));
+        let big_blind = hand.actions.iter().find(|a| a.kind == ActionKind::BigBlind).unwrap().player_id;
+        assert_eq!(hand.rake_paid(big_blind, RakeAttribution::Contributed), 0.5);
+        game.set_rake(Rake { no_flop_no_drop: true, ..Rake::percent(50.0) });
+        game.play_round().unwrap();
+        assert_eq!(game.last_hand().unwrap().rake, 0);
+
+        // a draw game has no flop, so the first draw stands in for it
+        for variant in [Variant::FiveCardDraw, Variant::Badugi] {
+            let mut game = calling_game(3);
+            game.set_variant(variant).unwrap();
+            game.set_rake(Rake { no_flop_no_drop: true, ..Rake::percent(50.0) });
+            game.play_round().unwrap();
+            let hand = game.last_hand().unwrap();
+            assert!(!hand.draws.is_empty());
+            assert_eq!((hand.rake, hand.total_pot()), (3, 6));
+        }
+    }
+
+    #[test]
+    fn test_run_it() {
+        let players = (0..2).map(|id| Player::new(id, format!("P{}", id + 1), 100)).collect();
+        let mut game = Game::with_players(players, 1, 2).unwrap();
+        game.set_verbose(false);
+        for id in 0..2 {
+            game.set_strategy(id, Box::new(crate::strategy::Aggressive { pot_fraction: 100.0 }));
+        }
+        let cards = |codes: &str| codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect::<Vec<_>>();
+        game.set_run_it(3);
+        game.force_cards(HashMap::from([(0, cards("Ah Ad")), (1, cards("Kh Kd"))]), cards("2c 7d 9s 3h 4c")).unwrap();
+        game.force_runs(vec![cards("2c 7d 9s Ks 5c"), cards("2c 7d 9s Jh Jc")]).unwrap();
+        game.play_round().unwrap();
+        let hand = game.last_hand().unwrap();
+        assert_eq!(hand.runs.len(), 3);
+        // all in before the flop, so only the board is shared
+        assert_eq!(hand.runs[2].board, cards("2c 7d 9s Jh Jc"));
+        assert_eq!(hand.runs[1].showdown[1].hand, Hand::Trips);
+        // the odd chip of the 200 goes to the first run
+        let split: Vec<u32> = hand.runs.iter().map(|run| run.pots[0].amount).collect();
+        assert_eq!(split, [67, 67, 66]);
+        assert_eq!(hand.pots, vec![PotRecord { amount: 200, rake: 0, winners: vec![(0, 133), (1, 67)] }]);
+        assert_eq!((hand.board.clone(), hand.showdown.clone()), (hand.runs[0].board.clone(), hand.runs[0].showdown.clone()));
+        assert_eq!(game.players().iter().map(|p| p.chips).collect::<Vec<_>>(), [133, 67]);
+
+        // anyone can hold it to a single run
+        struct RunOnce;
+        impl Strategy for RunOnce {
+            fn decide(&mut self, _player: &Player, _pot: u32, _board: &[Card], to_call: u32, _action: &[Vec<Action>]) -> Action {
+                if to_call > 0 { Action::Call } else { Action::Check }
+            }
+
+            fn run_it(&mut self, _player: &Player, _board: &[Card], _offered: u32) -> u32 {
+                1
+            }
+        }
+        game.set_strategy(1, Box::new(RunOnce));
+        game.play_round().unwrap();
+        assert!(game.last_hand().unwrap().runs.is_empty());
+    }
+
+    #[test]
+    fn test_omaha() {
+        let players = (0..3).map(|id| Player::new(id, format!("P{}", id + 1), 100)).collect();
+        let mut game = Game::with_players(players, 1, 2).unwrap();
+        game.set_verbose(false);
+        game.set_variant(Variant::omaha()).unwrap();
+        for id in 0..3 {
+            game.set_strategy(id, Box::new(crate::strategy::Aggressive { pot_fraction: 100.0 }));
+        }
+        let cards = |codes: &str| codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect::<Vec<_>>();
+        assert_eq!(game.force_cards(HashMap::from([(0, cards("Ah Kd Qs Qd Jc"))]), Vec::new()), Err(PokerError::HandFull { player_id: 0 }));
+        // the ace of hearts would make the nut flush in hold'em, but in Omaha
+        // only two hearts in the hand make a flush
+        let hole_cards = HashMap::from([(0, cards("Ah Kd Qs Qd")), (1, cards("3h 4h 8c 8d")), (2, cards("2c 2d 5s 6s"))]);
+        game.force_cards(hole_cards, cards("2h 7h 9h Jh Kc")).unwrap();
+        game.play_round().unwrap();
+        let hand = game.last_hand().unwrap();
+        assert_eq!(hand.variant, Variant::omaha());
+        assert!(hand.seats.iter().all(|s| s.cards.len() == 4));
+        let shown: Vec<(usize, Hand)> = hand.showdown.iter().map(|s| (s.player_id, s.hand)).collect();
+        assert_eq!(shown, [(0, Hand::Pair), (1, Hand::Flush), (2, Hand::Trips)]);
+        assert_eq!(hand.pots[0].winners, [(1, 300)]);
+
+        // six card Omaha deals to seven players at most
+        let mut full = Game::new(8, 100).unwrap();
+        assert_eq!(full.set_variant(Variant::Omaha { hole_cards: 6 }), Err(PokerError::TooManyPlayers { requested: 8, max: 7 }));
+        full.set_variant(Variant::Omaha { hole_cards: 5 }).unwrap();
+        assert_eq!(full.set_variant(Variant::Omaha { hole_cards: 0 }), Err(PokerError::HoleCardsOutOfRange { hole_cards: 0, min: 4, max: 6 }));
+        assert_eq!(full.variant(), Variant::Omaha { hole_cards: 5 });
+        assert_eq!(full.sit(9, Player::new(8, "P9".to_string(), 100)), Err(PokerError::TooManyPlayers { requested: 9, max: 8 }));
+    }
+
+    #[test]
+    fn test_omaha_hi_lo() {
+        let play = |hole_cards: [&str; 3], board: &str| {
+            let players = (0..3).map(|id| Player::new(id, format!("P{}", id + 1), 100)).collect();
+            let mut game = Game::with_players(players, 1, 2).unwrap();
+            game.set_verbose(false);
+            game.set_variant(Variant::OmahaHiLo { hole_cards: 4 }).unwrap();
+            for id in 0..3 {
+                game.set_strategy(id, Box::new(crate::strategy::Aggressive { pot_fraction: 100.0 }));
+            }
+            let cards = |codes: &str| codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect::<Vec<_>>();
+            let hole_cards = hole_cards.iter().enumerate().map(|(id, codes)| (id, cards(codes))).collect();
+            game.force_cards(hole_cards, cards(board)).unwrap();
+            game.play_round().unwrap();
+            game.last_hand().unwrap().clone()
+        };
+        // the full house takes the high half and the two seven lows quarter
+        // the pot between them
+        let hand = play(["As 3d Qc Jd", "Ad 3c Qs Jh", "7c 7s 9d 9h"], "2h 5s 7d Kc Kh");
+        assert_eq!(hand.pots[0].winners, [(2, 150), (1, 75), (0, 75)]);
+        assert_eq!(hand.showdown[2].low, None);
+        assert_eq!(hand.showdown[0].low.map(|low| low[0]), Some(Card::from_code("7d").unwrap()));
+
+        // with no low the high hand scoops
+        let hand = play(["As 3d Qc Jd", "Ad 3c Qs Jh", "7c 7s 9d 9h"], "2h 5s Td Kc Kh");
+        assert_eq!(hand.pots[0].winners, [(2, 300)]);
+    }
+
+    #[test]
+    fn test_history_writers() {
+        // a format that cannot record the game is refused either way round
+        let mut game = calling_game(3);
+        game.set_variant(Variant::Stud).unwrap();
+        let refused = game.add_history_writer(Box::new(crate::pokerstars::PokerStarsWriter::new(std::io::sink())));
+        assert!(matches!(refused, Err(PokerError::UnsupportedHistory(_))));
+        game.set_variant(Variant::Holdem).unwrap();
+        game.add_history_writer(Box::new(crate::phh::PhhWriter::new(std::io::sink()))).unwrap();
+        assert!(matches!(game.set_variant(Variant::FiveCardDraw), Err(PokerError::UnsupportedHistory(_))));
+        assert!(matches!(game.set_betting_structure(BettingStructure::PotLimit), Err(PokerError::UnsupportedHistory(_))));
+        assert_eq!(game.variant(), Variant::Holdem);
+        // PHH has fixed-limit razz, though not no-limit
+        assert!(matches!(game.set_variant(Variant::Razz), Err(PokerError::UnsupportedHistory(_))));
+        game.set_betting_structure(BettingStructure::FixedLimit { cap: crate::betting::DEFAULT_CAP }).unwrap();
+        game.set_variant(Variant::Razz).unwrap();
+        game.set_variant(Variant::Holdem).unwrap();
+        game.set_betting_structure(BettingStructure::NoLimit).unwrap();
+
+        // a writer that fails once, after one that keeps every hand, is
+        // reported without undoing the hand the other has written
+        #[derive(Clone, Default)]
+        struct Kept(std::rc::Rc<std::cell::RefCell<Vec<u64>>>);
+        impl HandHistoryWriter for Kept {
+            fn write_hand(&mut self, hand: &HandRecord) -> std::io::Result<()> {
+                self.0.borrow_mut().push(hand.hand_id);
+                Ok(())
+            }
+        }
+        struct FailsOnce(bool);
+        impl HandHistoryWriter for FailsOnce {
+            fn write_hand(&mut self, _hand: &HandRecord) -> std::io::Result<()> {
+                if std::mem::replace(&mut self.0, true) { Ok(()) } else { Err(std::io::Error::other("disk full")) }
+            }
+        }
+        let mut game = calling_game(3);
+        let kept = Kept::default();
+        game.add_history_writer(Box::new(kept.clone())).unwrap();
+        game.add_history_writer(Box::new(FailsOnce(false))).unwrap();
+        game.play_round().unwrap();
+        assert_eq!(game.history_errors(), [PokerError::HistoryWrite("disk full".to_string())]);
+        assert_eq!(game.last_hand().map(|hand| hand.hand_id), Some(1));
+        game.play_round().unwrap();
+        assert!(game.history_errors().is_empty());
+        assert_eq!(*kept.0.borrow(), [1, 2]);
+    }
+
+    #[test]
+    fn test_stud() {
+        // down, down, up, up, up, up, down
+        let hole_cards = ["Ah Kh 9c 9d 2s 3s Qc", "2c 3c 4d Kd Ks 5h 6h", "Tc Td 4s 7h 8h Jh Jd", "Qh Qd 5c 6c 7c 8c 9h"];
+        let play = |variant: Variant| {
+            let mut game = calling_game(4);
+            game.set_variant(variant).unwrap();
+            game.set_ante(Ante::Everyone(1));
+            game.set_betting_structure(BettingStructure::FixedLimit { cap: crate::betting::DEFAULT_CAP }).unwrap();
+            let cards = |codes: &str| codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect::<Vec<_>>();
+            game.force_cards(hole_cards.iter().enumerate().map(|(id, codes)| (id, cards(codes))).collect(), Vec::new()).unwrap();
+            game.play_round().unwrap();
+            let hand = game.last_hand().unwrap().clone();
+            assert!(hand.board.is_empty());
+            assert!(hand.seats.iter().all(|s| s.cards.len() == 7 && s.up_cards == s.cards[2..6]));
+            crate::replay::verify(&hand).unwrap();
+            assert_eq!(HandRecord::from_json(&hand.to_json()).unwrap(), hand);
+            hand
+        };
+        let first_to_act = |hand: &HandRecord, street: BettingRoundName| hand.actions.iter().find(|a| a.street == street && !a.kind.is_forced()).unwrap().player_id;
+
+        // the four of diamonds is lower than the four of spades and brings in
+        let hand = play(Variant::Stud);
+        let bring_in = hand.actions.iter().find(|a| a.kind == ActionKind::BringIn).unwrap();
+        assert_eq!((bring_in.player_id, bring_in.amount, bring_in.street), (1, 1, BettingRoundName::ThirdStreet));
+        assert_eq!(first_to_act(&hand, BettingRoundName::ThirdStreet), 2);
+        // then the pair of nines showing, until the kings pair
+        assert_eq!(first_to_act(&hand, BettingRoundName::FourthStreet), 0);
+        assert_eq!(first_to_act(&hand, BettingRoundName::FifthStreet), 1);
+        assert_eq!(first_to_act(&hand, BettingRoundName::SeventhStreet), 1);
+        // nine high straight
+        assert_eq!(hand.pots[0].winners, [(3, 8)]);
+
+        // in razz the highest card brings in and the lowest hand showing acts first
+        let hand = play(Variant::Razz);
+        assert_eq!(hand.actions.iter().find(|a| a.kind == ActionKind::BringIn).unwrap().player_id, 0);
+        assert_eq!(first_to_act(&hand, BettingRoundName::ThirdStreet), 1);
+        assert_eq!(first_to_act(&hand, BettingRoundName::FourthStreet), 3);
+        // a six low, with no straight in ace-to-five
+        assert_eq!(hand.pots[0].winners, [(1, 8)]);
+        assert_eq!(hand.showdown.iter().find(|s| s.player_id == 1).unwrap().hand, Hand::HighCard);
+
+        // random play, with players folding and going all in along the way
+        for variant in [Variant::Stud, Variant::Razz] {
+            let mut game = Game::new(7, 60).unwrap();
+            game.set_verbose(false);
+            game.set_variant(variant).unwrap();
+            game.set_ante(Ante::Everyone(1));
+            for _ in 0..30 {
+                game.play_round().unwrap();
+                let Some(hand) = game.last_hand() else { continue };
+                crate::replay::verify(hand).unwrap();
+                assert_eq!(&HandRecord::from_json(&hand.to_json()).unwrap(), hand);
+            }
+        }
+    }
+
+    #[test]
+    fn test_draw() {
+        let cards = |codes: &str| codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect::<Vec<_>>();
+        let play = |variant: Variant, hands: &[&str]| {
+            let mut game = calling_game(hands.len() as u32);
+            game.set_variant(variant).unwrap();
+            game.force_cards(hands.iter().enumerate().map(|(id, codes)| (id, cards(codes))).collect(), Vec::new()).unwrap();
+            game.play_round().unwrap();
+            let hand = game.last_hand().unwrap().clone();
+            crate::replay::verify(&hand).unwrap();
+            assert_eq!(HandRecord::from_json(&hand.to_json()).unwrap(), hand);
+            hand
+        };
+
+        // the nines draw three, the straight stands pat and the jack high
+        // draws four, left of the button first
+        let hand = play(Variant::FiveCardDraw, &["9c 9d 2h Ks 4c 9h Qd Jc", "Ac Kd Qh Js Tc", "2c 3d 7h 8s Jd Jh Qc 4d 5d"]);
+        let drawn: Vec<(usize, usize)> = hand.draws.iter().map(|d| (d.player_id, d.drawn.len())).collect();
+        assert_eq!(drawn, [(1, 0), (2, 4), (0, 3)]);
+        assert!(hand.draws.iter().all(|d| d.street == BettingRoundName::FirstDraw));
+        assert_eq!(hand.draws[2].discarded, cards("2h Ks 4c"));
+        assert_eq!(hand.seats[0].cards, cards("9c 9d 2h Ks 4c"));
+        assert!(hand.board.is_empty());
+        assert_eq!(hand.showdown.iter().find(|s| s.player_id == 0).unwrap().hand, Hand::Trips);
+        assert_eq!(hand.pots[0].winners, [(1, 6)]);
+
+        // in fixed limit the bet doubles after five card draw's only draw
+        let mut game = Game::new(2, 1000).unwrap();
+        game.set_verbose(false);
+        game.set_variant(Variant::FiveCardDraw).unwrap();
+        game.set_betting_structure(BettingStructure::FixedLimit { cap: 4 }).unwrap();
+        game.set_strategy(0, Box::new(crate::strategy::Aggressive { pot_fraction: 1.0 }));
+        game.set_strategy(1, Box::new(crate::strategy::CallingStation));
+        game.play_round().unwrap();
+        let opening = |street| game.last_hand().unwrap().actions.iter().find(|a| a.street == street && a.kind == ActionKind::Bet).map(|a| a.to);
+        assert_eq!(opening(BettingRoundName::FirstDraw), Some(4));
+
+        // in deuce-to-seven a straight counts against the hand, and three
+        // draws are betting on three more streets
+        let hand = play(Variant::DeuceToSevenTripleDraw, &["8s 7c 5c 3h 2h", "6s 5d 4c 3c 2c"]);
+        assert_eq!(hand.draws.len(), 6);
+        assert!(hand.actions.iter().any(|a| a.street == BettingRoundName::ThirdDraw));
+        assert_eq!(hand.pots[0].winners, [(0, 4)]);
+
+        // a king high badugi stands pat and beats three cards however low,
+        // each diamond drawn to them sharing the three's suit
+        let hand = play(Variant::Badugi, &["Kc Qh Js Td", "As 2c 3d Kd 9d 8d 7d"]);
+        assert_eq!(hand.draws.iter().map(|d| d.drawn.len()).collect::<Vec<_>>(), [1, 0, 1, 0, 1, 0]);
+        assert_eq!(hand.showdown.iter().find(|s| s.player_id == 1).unwrap().cards, cards("3d 2c As"));
+        assert_eq!(hand.pots[0].winners, [(0, 4)]);
+
+        // random play, drawing from the discards once the deck runs out
+        for variant in [Variant::DeuceToSevenTripleDraw, Variant::Badugi] {
+            let mut game = Game::new(6, 100).unwrap();
+            game.set_verbose(false);
+            game.set_variant(variant).unwrap();
+            game.set_betting_structure(BettingStructure::FixedLimit { cap: crate::betting::DEFAULT_CAP }).unwrap();
+            for _ in 0..30 {
+                game.play_round().unwrap();
+                let Some(hand) = game.last_hand() else { continue };
+                crate::replay::verify(hand).unwrap();
+                assert_eq!(&HandRecord::from_json(&hand.to_json()).unwrap(), hand);
+            }
+        }
+    }
+
+    #[test]
+    fn test_short_deck() {
+        let mut game = Game::new(6, 200).unwrap();
+        game.set_verbose(false);
+        game.set_variant(Variant::ShortDeck { trips_beat_straight: false }).unwrap();
+        // everyone antes a big blind unless the table already has an ante
+        assert_eq!(game.ante, Ante::Everyone(2));
+        for _ in 0..20 {
+            game.play_round().unwrap();
+            let Some(hand) = game.last_hand() else { continue };
+            assert!(hand.board.iter().chain(hand.seats.iter().flat_map(|s| &s.cards)).all(|card| card.0 >= crate::deck::Rank::Six));
+            crate::replay::verify(hand).unwrap();
+        }
+        let mut game = Game::new(15, 200).unwrap();
+        game.set_ante(Ante::BigBlind(2));
+        assert_eq!(game.set_variant(Variant::ShortDeck { trips_beat_straight: true }), Err(PokerError::TooManyPlayers { requested: 15, max: 14 }));
+        game.leave(15).unwrap();
+        game.set_variant(Variant::ShortDeck { trips_beat_straight: true }).unwrap();
+        assert_eq!(game.ante, Ante::BigBlind(2));
+
+        // a flush takes the pot from a full house
+        let players = (0..2).map(|id| Player::new(id, format!("P{}", id + 1), 100)).collect();
+        let mut game = Game::with_players(players, 1, 2).unwrap();
+        game.set_verbose(false);
+        game.set_variant(Variant::ShortDeck { trips_beat_straight: false }).unwrap();
+        for id in 0..2 {
+            game.set_strategy(id, Box::new(crate::strategy::Aggressive { pot_fraction: 100.0 }));
+        }
+        let cards = |codes: &str| codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect::<Vec<_>>();
+        game.force_cards(HashMap::from([(0, cards("As Ad")), (1, cards("8h 9h"))]), cards("Ah Kh Kc 7h 6s")).unwrap();
+        game.play_round().unwrap();
+        assert_eq!(game.last_hand().unwrap().pots[0].winners, [(1, 200)]);
     }
 
     #[test]
     fn test_player_bets() {
-        let mut game = Game::new(3,500);
-        let dealer = 0;
+        let mut game = Game::new(3,500).unwrap();
 
-        game.players[1].bet_blind(game.small_blind);
-        assert_eq!(game.players[1].chips, 499);
+        let (small_blind, big_blind) = (game.small_blind, game.big_blind);
+        game.table.player_mut(2).unwrap().bet_blind(small_blind);
+        assert_eq!(game.table.player(2).unwrap().chips, 499);
 
-        game.players[2].bet_blind(game.big_blind);
-        assert_eq!(game.players[2].chips, 498);
+        game.table.player_mut(3).unwrap().bet_blind(big_blind);
+        assert_eq!(game.table.player(3).unwrap().chips, 498);
 
-        game.play_round(dealer);
+        game.play_round().unwrap();
     }
     
     #[test]
     fn test_play_multiple_rounds(){
-        let mut game = Game::new(3,500);
-        for round in 0..=100{
-            game.play_round(round);
+        let mut game = Game::new(3,500).unwrap();
+        for _ in 0..=100{
+            game.play_round().unwrap();
         }
     }
 
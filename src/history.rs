@@ -0,0 +1,595 @@
+use std::fmt::Debug;
+use std::io::{self, Write};
+
+use crate::betting::BettingStructure;
+use crate::deck::Card;
+use crate::game::{BettingRoundName, DRAW_ROUND_ORDER, ROUND_ORDER, STUD_ROUND_ORDER};
+use crate::json::{self, Json};
+use crate::rake::RakeAttribution;
+use crate::table::Position;
+use crate::utils::{Hand, HAND_ORDER};
+use crate::variant::Variant;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ActionKind {
+    Ante,
+    SmallBlind,
+    BigBlind,
+    Straddle,
+    // a missed small blind, posted as dead money on coming back
+    DeadBlind,
+    // the forced opening bet on third street in stud
+    BringIn,
+    Fold,
+    Check,
+    Call,
+    Bet,
+    Raise,
+}
+
+impl ActionKind {
+    // posted before the cards are dealt rather than chosen
+    pub fn is_forced(self) -> bool {
+        matches!(self, ActionKind::Ante | ActionKind::SmallBlind | ActionKind::BigBlind | ActionKind::Straddle | ActionKind::DeadBlind | ActionKind::BringIn)
+    }
+}
+
+static ACTION_KINDS: &[ActionKind] = &[
+    ActionKind::Ante,
+    ActionKind::SmallBlind,
+    ActionKind::BigBlind,
+    ActionKind::Straddle,
+    ActionKind::DeadBlind,
+    ActionKind::BringIn,
+    ActionKind::Fold,
+    ActionKind::Check,
+    ActionKind::Call,
+    ActionKind::Bet,
+    ActionKind::Raise,
+];
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ActionRecord {
+    pub street: BettingRoundName,
+    pub player_id: usize,
+    pub kind: ActionKind,
+    // chips the player put in with this action
+    pub amount: u32,
+    // the player's total bet on this street after the action; antes are
+    // dead money and leave it as it was
+    pub to: u32,
+    pub all_in: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SeatRecord {
+    pub seat: usize,
+    pub player_id: usize,
+    pub name: String,
+    pub stack: u32,
+    pub cards: Vec<Card>,
+    // the cards dealt face up in stud, also among the cards
+    pub up_cards: Vec<Card>,
+}
+
+// One player's draw in a draw game, made before the betting on the street.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DrawRecord {
+    pub street: BettingRoundName,
+    pub player_id: usize,
+    pub discarded: Vec<Card>,
+    // the new cards, as many as were thrown away
+    pub drawn: Vec<Card>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PotRecord {
+    // what the winners took, after this pot's part of the rake
+    pub amount: u32,
+    pub rake: u32,
+    pub winners: Vec<(usize, u32)>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ShowdownRecord {
+    pub player_id: usize,
+    // the five cards played, or a badugi's four or fewer, named high card
+    pub cards: Vec<Card>,
+    pub hand: Hand,
+    // the best low in a hi-lo game, if it qualified
+    pub low: Option<[Card; 5]>,
+}
+
+// One running of the board when it was run more than once: the board, the
+// hands shown on it and its share of every pot.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RunRecord {
+    pub board: Vec<Card>,
+    pub showdown: Vec<ShowdownRecord>,
+    pub pots: Vec<PotRecord>,
+}
+
+// A run's share of a pot split evenly between the runs, odd chips going to
+// the earliest.
+pub(crate) fn run_share(amount: u32, n_runs: u32, run: usize) -> u32 {
+    amount / n_runs + if (run as u32) < amount % n_runs { 1 } else { 0 }
+}
+
+// Adds up each pot over the runs, listing each winner once in the order
+// they first won a share of it.
+pub(crate) fn combine_runs(runs: &[Vec<PotRecord>]) -> Vec<PotRecord> {
+    let mut pots: Vec<PotRecord> = Vec::new();
+    for run in runs {
+        for (idx, pot) in run.iter().enumerate() {
+            if pots.len() <= idx {
+                pots.push(PotRecord { amount: 0, rake: 0, winners: Vec::new() });
+            }
+            let combined = &mut pots[idx];
+            combined.amount += pot.amount;
+            combined.rake += pot.rake;
+            for &(player_id, amount) in &pot.winners {
+                match combined.winners.iter_mut().find(|(id, _)| *id == player_id) {
+                    Some((_, won)) => *won += amount,
+                    None => combined.winners.push((player_id, amount)),
+                }
+            }
+        }
+    }
+    pots
+}
+
+// Everything that happened in one hand, in the order it happened.
+// Exporters only ever read this, so every format describes the same hand.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HandRecord {
+    pub hand_id: u64,
+    pub table_name: String,
+    // seconds since the unix epoch
+    pub timestamp: u64,
+    pub max_seats: usize,
+    pub button_seat: usize,
+    pub small_blind: u32,
+    pub big_blind: u32,
+    // the ante as posted by everyone who could cover it, 0 without antes
+    pub ante: u32,
+    pub variant: Variant,
+    pub betting: BettingStructure,
+    // everyone's cards as first dealt
+    pub seats: Vec<SeatRecord>,
+    pub actions: Vec<ActionRecord>,
+    pub draws: Vec<DrawRecord>,
+    pub board: Vec<Card>,
+    pub uncalled: Option<(usize, u32)>,
+    // taken off the pots before they were awarded
+    pub rake: u32,
+    // the pots over every run of the board
+    pub pots: Vec<PotRecord>,
+    pub showdown: Vec<ShowdownRecord>,
+    // Empty unless the board was run more than once, when it holds every run
+    // from the first; board and showdown are then the first run's.
+    pub runs: Vec<RunRecord>,
+}
+
+impl HandRecord {
+    pub fn seat(&self, player_id: usize) -> Option<&SeatRecord> {
+        self.seats.iter().find(|s| s.player_id == player_id)
+    }
+
+    pub fn name(&self, player_id: usize) -> &str {
+        self.seat(player_id).map_or("", |s| s.name.as_str())
+    }
+
+    // everything put in apart from the uncalled bet, the rake included
+    pub fn total_pot(&self) -> u32 {
+        self.pots.iter().map(|p| p.amount).sum::<u32>() + self.rake
+    }
+
+    // the part of the rake charged to a player
+    pub fn rake_paid(&self, player_id: usize, attribution: RakeAttribution) -> f64 {
+        if self.rake == 0 || self.seat(player_id).is_none() {
+            return 0.0;
+        }
+        match attribution {
+            RakeAttribution::Dealt => self.rake as f64 / self.seats.len() as f64,
+            RakeAttribution::Contributed => {
+                let returned = self.uncalled.filter(|u| u.0 == player_id).map_or(0, |u| u.1);
+                let contributed = self.put_in(player_id) - returned;
+                self.rake as f64 * contributed as f64 / self.total_pot() as f64
+            },
+        }
+    }
+
+    pub fn won_by(&self, player_id: usize) -> u32 {
+        self.pots
+            .iter()
+            .flat_map(|p| p.winners.iter())
+            .filter(|(id, _)| *id == player_id)
+            .map(|(_, amount)| amount)
+            .sum()
+    }
+
+    // chips the player put in over the hand, before any uncalled bet came back
+    pub fn put_in(&self, player_id: usize) -> u32 {
+        self.actions
+            .iter()
+            .filter(|a| a.player_id == player_id)
+            .map(|a| a.amount)
+            .sum()
+    }
+
+    pub fn finishing_stack(&self, player_id: usize) -> u32 {
+        let returned = match self.uncalled {
+            Some((id, amount)) if id == player_id => amount,
+            _ => 0,
+        };
+        let stack = self.seat(player_id).map_or(0, |s| s.stack);
+        stack - self.put_in(player_id) + returned + self.won_by(player_id)
+    }
+
+    pub fn actions_on(&self, street: BettingRoundName) -> impl Iterator<Item = &ActionRecord> {
+        self.actions.iter().filter(move |a| a.street == street)
+    }
+
+    // the street a player folded on, if they folded
+    pub fn folded_on(&self, player_id: usize) -> Option<BettingRoundName> {
+        self.actions
+            .iter()
+            .find(|a| a.player_id == player_id && a.kind == ActionKind::Fold)
+            .map(|a| a.street)
+    }
+
+    // Where everyone dealt in sat relative to the button, by player id,
+    // going by who posted the blinds. Empty if nobody posted a big blind.
+    pub fn positions(&self) -> Vec<(usize, Position)> {
+        let posted = |kind| {
+            let id = self.actions.iter().find(|a| a.kind == kind)?.player_id;
+            self.seat(id).map(|s| s.seat)
+        };
+        let Some(big_blind) = posted(ActionKind::BigBlind) else { return Vec::new() };
+        let mut seats: Vec<&SeatRecord> = self.seats.iter().collect();
+        seats.sort_by_key(|s| s.seat);
+        let numbers: Vec<usize> = seats.iter().map(|s| s.seat).collect();
+        let positions = Position::assign(&numbers, self.button_seat, posted(ActionKind::SmallBlind), big_blind);
+        seats.iter().map(|s| s.player_id).zip(positions).collect()
+    }
+}
+
+// (year, month, day, hour, minute, second) in UTC for a unix timestamp
+pub(crate) fn civil_from_timestamp(timestamp: u64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = (timestamp / 86400) as i64;
+    let seconds = (timestamp % 86400) as u32;
+
+    // civil-from-days, counting eras of 400 years from 0000-03-01
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day, seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+// the inverse of civil_from_timestamp
+pub(crate) fn timestamp_from_civil(year: i64, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    (days * 86400) as u64 + (hour * 3600 + minute * 60 + second) as u64
+}
+
+pub trait HandHistoryWriter {
+    fn write_hand(&mut self, hand: &HandRecord) -> io::Result<()>;
+
+    // Why the format cannot record hands of this game, if it cannot. A
+    // Game refuses the writer, or the game, up front.
+    fn unsupported(&self, _variant: Variant, _betting: BettingStructure) -> Option<String> {
+        None
+    }
+}
+
+// Our own format: each hand as one JSON object, field for field the HandRecord.
+// Enums are written by their variant names and cards as codes like "Td".
+impl HandRecord {
+    pub(crate) fn to_json(&self) -> Json {
+        let cards = |cards: &[Card]| Json::from(cards.iter().map(|c| c.code()).collect::<Vec<_>>());
+        let pots = |pots: &[PotRecord]| Json::Array(pots.iter().map(|pot| {
+            Json::object()
+                .with("amount", pot.amount)
+                .with("rake", pot.rake)
+                .with("winners", Json::Array(pot.winners.iter().map(|(player_id, amount)| {
+                    Json::object().with("player_id", *player_id).with("amount", *amount)
+                }).collect()))
+        }).collect());
+        let showdown = |showdown: &[ShowdownRecord]| Json::Array(showdown.iter().map(|shown| {
+            Json::object()
+                .with("player_id", shown.player_id)
+                .with("cards", cards(&shown.cards))
+                .with("hand", format!("{:?}", shown.hand))
+                .with("low", shown.low.map(|low| cards(&low)))
+        }).collect());
+        Json::object()
+            .with("hand_id", self.hand_id)
+            .with("table_name", self.table_name.as_str())
+            .with("timestamp", self.timestamp)
+            .with("max_seats", self.max_seats)
+            .with("button_seat", self.button_seat)
+            .with("small_blind", self.small_blind)
+            .with("big_blind", self.big_blind)
+            .with("ante", self.ante)
+            .with("variant", self.variant.name())
+            .with("betting", self.betting.name())
+            .with("raise_cap", match self.betting {
+                BettingStructure::FixedLimit { cap } => Some(cap),
+                _ => None,
+            })
+            .with("seats", Json::Array(self.seats.iter().map(|seat| {
+                Json::object()
+                    .with("seat", seat.seat)
+                    .with("player_id", seat.player_id)
+                    .with("name", seat.name.as_str())
+                    .with("stack", seat.stack)
+                    .with("cards", cards(&seat.cards))
+                    .with("up_cards", cards(&seat.up_cards))
+            }).collect()))
+            .with("actions", Json::Array(self.actions.iter().map(|action| {
+                Json::object()
+                    .with("street", format!("{:?}", action.street))
+                    .with("player_id", action.player_id)
+                    .with("kind", format!("{:?}", action.kind))
+                    .with("amount", action.amount)
+                    .with("to", action.to)
+                    .with("all_in", action.all_in)
+            }).collect()))
+            .with("draws", Json::Array(self.draws.iter().map(|draw| {
+                Json::object()
+                    .with("street", format!("{:?}", draw.street))
+                    .with("player_id", draw.player_id)
+                    .with("discarded", cards(&draw.discarded))
+                    .with("drawn", cards(&draw.drawn))
+            }).collect()))
+            .with("board", cards(&self.board))
+            .with("uncalled", self.uncalled.map(|(player_id, amount)| {
+                Json::object().with("player_id", player_id).with("amount", amount)
+            }))
+            .with("rake", self.rake)
+            .with("pots", pots(&self.pots))
+            .with("showdown", showdown(&self.showdown))
+            .with("runs", Json::Array(self.runs.iter().map(|run| {
+                Json::object()
+                    .with("board", cards(&run.board))
+                    .with("showdown", showdown(&run.showdown))
+                    .with("pots", pots(&run.pots))
+            }).collect()))
+    }
+
+    pub(crate) fn from_json(value: &Json) -> Result<HandRecord, String> {
+        let seats = array(value, "seats")?
+            .iter()
+            .map(|seat| Ok(SeatRecord {
+                seat: integer(seat, "seat")?,
+                player_id: integer(seat, "player_id")?,
+                name: string(seat, "name")?,
+                stack: integer(seat, "stack")?,
+                cards: cards(seat, "cards")?,
+                up_cards: cards(seat, "up_cards")?,
+            }))
+            .collect::<Result<_, String>>()?;
+        let streets = [ROUND_ORDER, STUD_ROUND_ORDER, DRAW_ROUND_ORDER].concat();
+        let actions = array(value, "actions")?
+            .iter()
+            .map(|action| Ok(ActionRecord {
+                street: variant(action, "street", &streets)?,
+                player_id: integer(action, "player_id")?,
+                kind: variant(action, "kind", ACTION_KINDS)?,
+                amount: integer(action, "amount")?,
+                to: integer(action, "to")?,
+                all_in: field(action, "all_in")?.as_bool().ok_or("all_in is not a boolean")?,
+            }))
+            .collect::<Result<_, String>>()?;
+        let draws = array(value, "draws")?
+            .iter()
+            .map(|draw| Ok(DrawRecord {
+                street: variant(draw, "street", &streets)?,
+                player_id: integer(draw, "player_id")?,
+                discarded: cards(draw, "discarded")?,
+                drawn: cards(draw, "drawn")?,
+            }))
+            .collect::<Result<_, String>>()?;
+        let uncalled = match field(value, "uncalled")? {
+            Json::Null => None,
+            uncalled => Some((integer(uncalled, "player_id")?, integer(uncalled, "amount")?)),
+        };
+        let pots = |value: &Json| array(value, "pots")?
+            .iter()
+            .map(|pot| Ok(PotRecord {
+                amount: integer(pot, "amount")?,
+                rake: integer(pot, "rake")?,
+                winners: array(pot, "winners")?
+                    .iter()
+                    .map(|winner| Ok((integer(winner, "player_id")?, integer(winner, "amount")?)))
+                    .collect::<Result<_, String>>()?,
+            }))
+            .collect::<Result<Vec<_>, String>>();
+        let showdown = |value: &Json| array(value, "showdown")?
+            .iter()
+            .map(|shown| Ok(ShowdownRecord {
+                player_id: integer(shown, "player_id")?,
+                cards: cards(shown, "cards")?,
+                hand: variant(shown, "hand", HAND_ORDER)?,
+                low: match field(shown, "low")? {
+                    Json::Null => None,
+                    _ => Some(cards(shown, "low")?.try_into().map_err(|_| "a low needs 5 cards".to_string())?),
+                },
+            }))
+            .collect::<Result<Vec<_>, String>>();
+        let runs = array(value, "runs")?
+            .iter()
+            .map(|run| Ok(RunRecord { board: cards(run, "board")?, showdown: showdown(run)?, pots: pots(run)? }))
+            .collect::<Result<_, String>>()?;
+        let variant = string(value, "variant")?;
+
+        Ok(HandRecord {
+            hand_id: integer(value, "hand_id")?,
+            table_name: string(value, "table_name")?,
+            timestamp: integer(value, "timestamp")?,
+            max_seats: integer(value, "max_seats")?,
+            button_seat: integer(value, "button_seat")?,
+            small_blind: integer(value, "small_blind")?,
+            big_blind: integer(value, "big_blind")?,
+            ante: integer(value, "ante")?,
+            variant: Variant::from_name(&variant).ok_or_else(|| format!("unknown variant '{}'", variant))?,
+            betting: match BettingStructure::from_name(&string(value, "betting")?) {
+                Some(BettingStructure::FixedLimit { .. }) => BettingStructure::FixedLimit { cap: integer(value, "raise_cap")? },
+                Some(betting) => betting,
+                None => return Err(format!("unknown betting structure '{}'", string(value, "betting")?)),
+            },
+            seats,
+            actions,
+            draws,
+            board: cards(value, "board")?,
+            uncalled,
+            rake: integer(value, "rake")?,
+            pots: pots(value)?,
+            showdown: showdown(value)?,
+            runs,
+        })
+    }
+}
+
+fn field<'a>(value: &'a Json, key: &str) -> Result<&'a Json, String> {
+    value.get(key).ok_or_else(|| format!("missing field '{}'", key))
+}
+
+fn integer<T: TryFrom<i64>>(value: &Json, key: &str) -> Result<T, String> {
+    field(value, key)?
+        .as_i64()
+        .and_then(|i| T::try_from(i).ok())
+        .ok_or_else(|| format!("'{}' is not a valid number", key))
+}
+
+fn string(value: &Json, key: &str) -> Result<String, String> {
+    field(value, key)?
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| format!("'{}' is not a string", key))
+}
+
+fn array<'a>(value: &'a Json, key: &str) -> Result<&'a Vec<Json>, String> {
+    field(value, key)?.as_array().ok_or_else(|| format!("'{}' is not an array", key))
+}
+
+fn cards(value: &Json, key: &str) -> Result<Vec<Card>, String> {
+    array(value, key)?
+        .iter()
+        .map(|card| card.as_str().and_then(Card::from_code).ok_or_else(|| format!("invalid card in '{}'", key)))
+        .collect()
+}
+
+// an enum written by its variant name
+fn variant<T: Debug + Copy>(value: &Json, key: &str, variants: &[T]) -> Result<T, String> {
+    let name = string(value, key)?;
+    variants
+        .iter()
+        .find(|v| format!("{:?}", v) == name)
+        .copied()
+        .ok_or_else(|| format!("unknown {} '{}'", key, name))
+}
+
+// one JSON hand per line, see HandRecord::to_json
+pub struct JsonWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> JsonWriter<W> {
+    pub fn new(out: W) -> JsonWriter<W> {
+        JsonWriter { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> HandHistoryWriter for JsonWriter<W> {
+    fn write_hand(&mut self, hand: &HandRecord) -> io::Result<()> {
+        writeln!(self.out, "{}", hand.to_json())?;
+        self.out.flush()
+    }
+}
+
+// reads hands written by JsonWriter, skipping blank lines
+pub fn parse_json_hands(text: &str) -> Result<Vec<HandRecord>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            json::parse(line)
+                .and_then(|value| HandRecord::from_json(&value))
+                .map_err(|err| format!("line {}: {}", n + 1, err))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn test_civil_time() {
+        assert_eq!(civil_from_timestamp(0), (1970, 1, 1, 0, 0, 0));
+        assert_eq!(civil_from_timestamp(1_700_000_000), (2023, 11, 14, 22, 13, 20));
+        assert_eq!(civil_from_timestamp(951_782_400), (2000, 2, 29, 0, 0, 0));
+        for timestamp in [0, 951_782_400, 1_700_000_000, 4_102_444_799] {
+            let (y, mo, d, h, mi, s) = civil_from_timestamp(timestamp);
+            assert_eq!(timestamp_from_civil(y, mo, d, h, mi, s), timestamp);
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut game = Game::new(6, 300).unwrap();
+        let mut hands = Vec::new();
+        for _ in 0..29 {
+            game.play_round().unwrap();
+            if let Some(hand) = game.last_hand() {
+                hands.push(hand.clone());
+            }
+        }
+        // the last hand is all in before the flop and run twice, at a table
+        // of its own so nobody has busted before it
+        let mut game = Game::new(6, 300).unwrap();
+        game.set_verbose(false);
+        game.set_run_it(2);
+        for id in 0..6 {
+            game.set_strategy(id, Box::new(crate::strategy::Aggressive { pot_fraction: 100.0 }));
+        }
+        game.play_round().unwrap();
+        hands.push(game.last_hand().unwrap().clone());
+        assert_eq!(hands.last().unwrap().runs.len(), 2);
+        let mut writer = JsonWriter::new(Vec::new());
+        for hand in &hands {
+            writer.write_hand(hand).unwrap();
+        }
+        let text = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(text.lines().count(), hands.len());
+        assert_eq!(parse_json_hands(&text).unwrap(), hands);
+    }
+
+    #[test]
+    fn test_json_errors() {
+        assert!(parse_json_hands("{}").unwrap_err().contains("missing field"));
+        let mut game = Game::new(3, 100).unwrap();
+        game.play_round().unwrap();
+        let text = game.last_hand().unwrap().to_json().to_string().replace("\"Preflop\"", "\"Preflip\"");
+        assert_eq!(parse_json_hands(&text).unwrap_err(), "line 1: unknown street 'Preflip'");
+    }
+}
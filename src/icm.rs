@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+
+// The Independent Chip Model: the chance of finishing first is a player's
+// share of the chips, and of finishing in each place after that their share
+// of the chips left once the places above are filled (Malmuth-Harville).
+// Players without chips finish last, in any order.
+
+// Above this many memoized states equity is sampled instead of worked out.
+pub const MAX_EXACT_STATES: u64 = 1 << 20;
+// finishing orders drawn when sampling
+pub const SAMPLES: usize = 200_000;
+
+// Each player's expected prize given their stacks and the prizes from first
+// place on down. Places past the last player are never paid.
+pub fn equity(stacks: &[u32], payouts: &[u32]) -> Vec<f64> {
+    let places = payouts.len().min(stacks.len());
+    if stacks.len() <= 128 && exact_states(stacks.len(), places) <= MAX_EXACT_STATES {
+        exact_equity(stacks, &payouts[..places])
+    } else {
+        sampled_equity(stacks, &payouts[..places], SAMPLES)
+    }
+}
+
+// how many sets of players can fill the places above the last one paid
+fn exact_states(n_players: usize, places: usize) -> u64 {
+    let mut states: u64 = 0;
+    let mut choose: u64 = 1;
+    for taken in 0..places as u64 {
+        states = states.saturating_add(choose);
+        choose = choose.saturating_mul(n_players as u64 - taken) / (taken + 1);
+    }
+    states
+}
+
+// Fills the places one at a time, keeping the chance of every set of players
+// having taken the places so far; the order they took them in no longer
+// matters for what comes after, which is what keeps this from growing with n!.
+fn exact_equity(stacks: &[u32], payouts: &[u32]) -> Vec<f64> {
+    let n_players = stacks.len();
+    let total: u64 = stacks.iter().map(|&s| s as u64).sum();
+    let mut equity = vec![0.0; n_players];
+    let mut placed: HashMap<u128, f64> = HashMap::from([(0, 1.0)]);
+    for &prize in payouts {
+        let mut next: HashMap<u128, f64> = HashMap::new();
+        for (&mask, &chance) in &placed {
+            let left: Vec<usize> = (0..n_players).filter(|&i| mask >> i & 1 == 0).collect();
+            let behind: u64 = total - (0..n_players).filter(|&i| mask >> i & 1 == 1).map(|i| stacks[i] as u64).sum::<u64>();
+            for &i in &left {
+                let share = if behind == 0 { 1.0 / left.len() as f64 } else { stacks[i] as f64 / behind as f64 };
+                if share == 0.0 {
+                    continue;
+                }
+                equity[i] += chance * share * prize as f64;
+                *next.entry(mask | 1 << i).or_insert(0.0) += chance * share;
+            }
+        }
+        placed = next;
+    }
+    equity
+}
+
+// Draws finishing orders by the same model, for fields too big to work out.
+// Repeats exactly when the rng module is seeded.
+pub fn sampled_equity(stacks: &[u32], payouts: &[u32], samples: usize) -> Vec<f64> {
+    let mut rng = crate::rng::rng();
+    let total: u64 = stacks.iter().map(|&s| s as u64).sum();
+    let mut equity = vec![0.0; stacks.len()];
+    for _ in 0..samples {
+        let mut left: Vec<usize> = (0..stacks.len()).collect();
+        let mut behind = total;
+        for &prize in payouts.iter().take(stacks.len()) {
+            let pos = if behind == 0 {
+                rng.gen_range(0..left.len())
+            } else {
+                let mut pick = rng.gen_range(0..behind);
+                left.iter().position(|&i| {
+                    let stack = stacks[i] as u64;
+                    if pick < stack {
+                        return true;
+                    }
+                    pick -= stack;
+                    false
+                }).unwrap()
+            };
+            let i = left.swap_remove(pos);
+            equity[i] += prize as f64;
+            behind -= stacks[i] as u64;
+        }
+    }
+    equity.iter().map(|e| e / samples.max(1) as f64).collect()
+}
+
+// A chip chop deal: everyone is paid the prize for the last place still in
+// play and what is left of the prizes in play is shared by chip count.
+pub fn chip_chop(stacks: &[u32], payouts: &[u32]) -> Vec<f64> {
+    let n_players = stacks.len();
+    let in_play: u64 = payouts.iter().take(n_players).map(|&p| p as u64).sum();
+    let floor = payouts.get(n_players.wrapping_sub(1)).copied().unwrap_or(0) as f64;
+    let shared = in_play as f64 - floor * n_players as f64;
+    let total: u64 = stacks.iter().map(|&s| s as u64).sum();
+    stacks
+        .iter()
+        .map(|&stack| {
+            let share = if total == 0 { 1.0 / n_players as f64 } else { stack as f64 / total as f64 };
+            floor + shared * share
+        })
+        .collect()
+}
+
+// An ICM deal that keeps some of first prize back to be played for: the
+// rest of the prizes in play are shared by ICM equity.
+pub fn icm_chop(stacks: &[u32], payouts: &[u32], left_to_play: u32) -> Vec<f64> {
+    let mut prizes: Vec<u32> = payouts.iter().take(stacks.len()).copied().collect();
+    if let Some(first) = prizes.first_mut() {
+        *first -= left_to_play.min(*first);
+    }
+    equity(stacks, &prizes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f64], expected: &[f64], tolerance: f64) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < tolerance, "{:?} is not {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_equity() {
+        assert_close(&equity(&[75, 25], &[70, 30]), &[60.0, 40.0], 1e-9);
+        assert_close(&equity(&[5000, 3000, 2000], &[50, 30, 20]), &[38.392857, 32.75, 28.857143], 1e-5);
+        // without chips a player can only finish last
+        assert_close(&equity(&[5000, 3000, 2000, 0], &[50, 30, 20, 10]), &[38.392857, 32.75, 28.857143, 10.0], 1e-5);
+        // only the places in play are paid
+        assert_close(&equity(&[1, 1], &[50, 30, 20]), &[40.0, 40.0], 1e-9);
+    }
+
+    #[test]
+    fn test_big_fields() {
+        // twelve players is 4096 states rather than 12! orders
+        let stacks: Vec<u32> = (1..=12).map(|i| i * 1000).collect();
+        let payouts = [300, 200, 150, 110, 90, 70, 50, 30];
+        let exact = equity(&stacks, &payouts);
+        assert_close(&[exact.iter().sum::<f64>()], &[1000.0], 1e-6);
+        assert!(exact.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(exact_states(12, 8), (0..8).map(|k| [1, 12, 66, 220, 495, 792, 924, 792][k]).sum::<u64>());
+
+        crate::rng::seed(3);
+        let sampled = sampled_equity(&stacks, &payouts, 50_000);
+        crate::rng::unseed();
+        assert_close(&sampled, &exact, 2.0);
+
+        // too many players to keep sets of as a mask, so sampled
+        assert!(exact_states(200, 30) > MAX_EXACT_STATES);
+        crate::rng::seed(5);
+        let field = equity(&[100; 140], &[1000, 500]);
+        crate::rng::unseed();
+        assert_close(&[field[0], field.iter().sum::<f64>()], &[1500.0 / 140.0, 1500.0], 0.5);
+    }
+
+    #[test]
+    fn test_deals() {
+        let stacks = [6000, 3000, 1000];
+        assert_close(&chip_chop(&stacks, &[50, 30, 20]), &[44.0, 32.0, 24.0], 1e-9);
+        let deal = icm_chop(&stacks, &[50, 30, 20], 10);
+        assert_close(&[deal.iter().sum::<f64>()], &[90.0], 1e-9);
+        assert!(deal[0] > deal[1] && deal[1] > deal[2]);
+        // a deal never pays less than the last place in play
+        assert!(icm_chop(&stacks, &[50, 30, 20], 0)[2] > 20.0);
+    }
+}
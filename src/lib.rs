@@ -0,0 +1,63 @@
+//! A poker engine for hold'em, Omaha, short deck, stud, razz and the draw
+//! games: cards and decks, hand evaluators, players and a game loop,
+//! tournaments and cash games, plus hand history import and export.
+//!
+//! Most programs only need the prelude:
+//!
+//! ```
+//! use poker::prelude::*;
+//!
+//! let mut game = Game::new(4, 1000).unwrap();
+//! game.play_round().unwrap();
+//! assert!(game.last_hand().is_some());
+//! ```
+
+pub mod betting;
+pub mod deck;
+pub mod error;
+pub mod utils;
+pub mod variant;
+pub mod game;
+pub mod table;
+pub mod tournament;
+pub mod bounty;
+pub mod mtt;
+pub mod cash;
+pub mod player;
+pub mod rake;
+pub mod history;
+pub mod equity;
+pub mod icm;
+pub mod pokerstars;
+pub mod phh;
+pub mod ohh;
+pub mod replay;
+pub mod rng;
+pub mod strategy;
+pub mod config;
+// the command line behind src/main.rs; not part of the library API
+#[doc(hidden)]
+pub mod cli;
+// the JSON and TOML readers only serve the history and config modules
+pub(crate) mod json;
+mod toml;
+
+// the version of this API, following semver
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub mod prelude {
+    pub use crate::betting::BettingStructure;
+    pub use crate::bounty::{Bounties, BountyFormat};
+    pub use crate::cash::{CashGame, CashResult, Rebuy};
+    pub use crate::deck::{Card, Deck, Rank, Suit};
+    pub use crate::error::PokerError;
+    pub use crate::game::{Ante, BettingRoundName, ButtonRule, Game, MissedBlinds, Positions, Straddle};
+    pub use crate::history::{HandHistoryWriter, HandRecord};
+    pub use crate::player::{Action, Player, PlayerState, Strategy};
+    pub use crate::rake::{Rake, RakeAttribution};
+    pub use crate::table::{Position, Table};
+    pub use crate::mtt::MultiTableTournament;
+    pub use crate::tournament::{BlindLevel, BlindSchedule, Finish, LevelLength, Tournament};
+    pub use crate::utils::{Hand, HandComparator, Ranking};
+    pub use crate::variant::{BestHand, Variant};
+}
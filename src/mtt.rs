@@ -0,0 +1,572 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::bounty::{Bounties, BountyFormat};
+use crate::error::PokerError;
+use crate::game::{Game, MAX_PLAYERS};
+use crate::player::{Player, Strategy};
+use crate::table::Table;
+use crate::tournament::{busted_in, pay, place_busts, with_bounties, BlindSchedule, Finish};
+
+// A player moved to another table, either to balance the tables or because
+// the one they sat at was broken.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Move {
+    pub player_id: usize,
+    pub from_table: usize,
+    pub to_table: usize,
+    pub seat: usize,
+    // rounds played before the move
+    pub round: u64,
+    pub broken: bool,
+}
+
+// A tournament played across many tables of one Game each. Every round deals
+// one hand at each table, hand for hand, so players who bust in the same
+// round finish in order of the stacks they started their hands with.
+//
+// After each round the smallest table is broken while everyone left fits at
+// one table fewer, and players are moved from the biggest table to the
+// smallest until no two tables differ by more than one player. The player
+// moved is whoever was due the big blind next, and they take the open seat
+// nearest after the big blind at their new table, so nobody escapes or pays
+// the blinds twice by moving. When the last tables come together the final
+// table is seated by a fresh draw.
+pub struct MultiTableTournament {
+    // by table number, in order, without the tables broken so far
+    tables: Vec<(usize, Game)>,
+    seats_per_table: usize,
+    schedule: BlindSchedule,
+    payouts: Vec<u32>,
+    rounds_played: u64,
+    elapsed: Duration,
+    hand_duration: Duration,
+    entrants: usize,
+    // hands are numbered across all the tables in the order they are played
+    next_hand_id: u64,
+    // the rounds played when the final table was drawn
+    final_table_round: Option<u64>,
+    moves: Vec<Move>,
+    // eliminations in the order they happened, the winner last
+    finishes: Vec<Finish>,
+    bounties: Option<Bounties>,
+}
+
+impl MultiTableTournament {
+    // Seats the players at random across as few tables as will hold them,
+    // evened out so the tables differ by at most one player.
+    pub fn new(mut players: Vec<Player>, seats_per_table: usize, schedule: BlindSchedule) -> Result<MultiTableTournament, PokerError> {
+        if seats_per_table > MAX_PLAYERS {
+            return Err(PokerError::TooManyPlayers { requested: seats_per_table, max: MAX_PLAYERS });
+        }
+        let seats_per_table = seats_per_table.max(2);
+        let mut ids = HashSet::new();
+        if let Some(player) = players.iter().find(|p| !ids.insert(p.id)) {
+            return Err(PokerError::AlreadySeated(player.id));
+        }
+
+        let entrants = players.len();
+        let n_tables = entrants.div_ceil(seats_per_table).max(1);
+        players.shuffle(&mut crate::rng::rng());
+        let mut tables: Vec<Table> = (0..n_tables).map(|_| Table::new(seats_per_table)).collect();
+        for (n, player) in players.into_iter().enumerate() {
+            tables[n % n_tables].sit(n / n_tables + 1, player)?;
+        }
+
+        let (small_blind, big_blind) = schedule.levels.first().map_or((1, 2), |l| (l.small_blind, l.big_blind));
+        let mut games = Vec::new();
+        for (idx, table) in tables.into_iter().enumerate() {
+            let mut game = Game::with_table(table, small_blind, big_blind)?;
+            game.set_table_name(&format!("Table {}", idx + 1));
+            game.set_verbose(false);
+            games.push((idx + 1, game));
+        }
+        Ok(MultiTableTournament {
+            tables: games,
+            seats_per_table,
+            schedule,
+            payouts: Vec::new(),
+            rounds_played: 0,
+            elapsed: Duration::ZERO,
+            hand_duration: Duration::from_secs(60),
+            entrants,
+            next_hand_id: 1,
+            final_table_round: None,
+            moves: Vec::new(),
+            finishes: Vec::new(),
+            bounties: None,
+        })
+    }
+
+    // prizes for first place on down; places past the end win nothing
+    pub fn set_payouts(&mut self, payouts: Vec<u32>) {
+        self.payouts = payouts;
+    }
+
+    // how much tournament time a round of hands takes
+    pub fn set_hand_duration(&mut self, duration: Duration) {
+        self.hand_duration = duration;
+    }
+
+    // Makes this a bounty tournament with the same bounty on everyone still
+    // in, paid on top of the prizes.
+    pub fn set_bounties(&mut self, format: BountyFormat, bounty: u32) {
+        let players = self.tables().flat_map(|(_, game)| game.players()).map(|p| p.id).collect::<Vec<_>>();
+        self.bounties = Some(Bounties::new(format, players, bounty));
+        self.show_bounties();
+    }
+
+    pub fn bounties(&self) -> Option<&Bounties> {
+        self.bounties.as_ref()
+    }
+
+    pub fn set_verbose(&mut self, verbose: bool) {
+        for (_, game) in &mut self.tables {
+            game.set_verbose(verbose);
+        }
+    }
+
+    // The strategy moves with the player from table to table. Does nothing
+    // for a player who is no longer in the tournament.
+    pub fn set_strategy(&mut self, player_id: usize, strategy: Box<dyn Strategy>) {
+        if let Some((number, _)) = self.table_of(player_id) {
+            self.table_mut(number).unwrap().set_strategy(player_id, strategy);
+        }
+    }
+
+    pub fn payouts(&self) -> &[u32] {
+        &self.payouts
+    }
+
+    pub fn entrants(&self) -> usize {
+        self.entrants
+    }
+
+    pub fn seats_per_table(&self) -> usize {
+        self.seats_per_table
+    }
+
+    pub fn rounds_played(&self) -> u64 {
+        self.rounds_played
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    // the index into the schedule of the level the next round is played at
+    pub fn level(&self) -> usize {
+        self.schedule.level_at(self.rounds_played, self.elapsed)
+    }
+
+    // the tables still running with their numbers, in order
+    pub fn tables(&self) -> impl Iterator<Item = (usize, &Game)> {
+        self.tables.iter().map(|(number, game)| (*number, game))
+    }
+
+    pub fn table(&self, number: usize) -> Option<&Game> {
+        self.tables.iter().find(|t| t.0 == number).map(|t| &t.1)
+    }
+
+    // e.g. to add a history writer to one table
+    pub fn table_mut(&mut self, number: usize) -> Option<&mut Game> {
+        self.tables.iter_mut().find(|t| t.0 == number).map(|t| &mut t.1)
+    }
+
+    // the table number and seat a player sits in
+    pub fn table_of(&self, player_id: usize) -> Option<(usize, usize)> {
+        self.tables().find_map(|(number, game)| game.table().seat_of(player_id).map(|seat| (number, seat)))
+    }
+
+    // the one table left, once the field is down to it
+    pub fn final_table(&self) -> Option<&Game> {
+        match self.tables.as_slice() {
+            [(_, game)] => Some(game),
+            _ => None,
+        }
+    }
+
+    // how many rounds had been played when the final table was drawn, if it
+    // has been and the tournament did not start at one table
+    pub fn final_table_round(&self) -> Option<u64> {
+        self.final_table_round
+    }
+
+    // every player moved so far, in the order they moved
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn players_left(&self) -> usize {
+        self.tables.iter().map(|(_, game)| game.players().len()).sum()
+    }
+
+    // The chips of every player left by player id, the biggest stack first.
+    pub fn stacks(&self) -> Vec<(usize, u32)> {
+        let mut stacks: Vec<(usize, u32)> = self.tables().flat_map(|(_, game)| game.players()).map(|p| (p.id, p.chips)).collect();
+        stacks.sort_by_key(|&(id, chips)| (Reverse(chips), id));
+        stacks
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.players_left() <= 1
+    }
+
+    // Plays a hand at every table that can deal one, then places whoever
+    // busted and breaks and balances the tables. Returns false without
+    // playing once the tournament is over or no table can deal a hand. An
+    // error stops the round, and the tables that played before it keep
+    // their hands.
+    pub fn play_round(&mut self) -> Result<bool, PokerError> {
+        if self.is_finished() {
+            return Ok(false);
+        }
+        let level = self.schedule.levels.get(self.level()).copied();
+        let mut busted = Vec::new();
+        let mut played = false;
+        // a table whose hand fails stops the round, but the players who
+        // busted at the tables before it are still placed
+        let mut failed = None;
+        for (_, game) in &mut self.tables {
+            if game.table().dealt_in().len() < 2 {
+                continue;
+            }
+            if let Some(level) = level {
+                game.set_blinds(level.small_blind, level.big_blind);
+                game.set_ante(level.ante);
+            }
+            game.set_hand_number(self.next_hand_id);
+            if let Err(err) = game.play_round() {
+                failed = Some(err);
+                break;
+            }
+            self.next_hand_id += 1;
+            let busted_here: Vec<(u32, Finish)> = busted_in(game).collect();
+            if let (Some(bounties), Some(record)) = (&mut self.bounties, game.last_hand()) {
+                bounties.knock_out(record, &busted_here.iter().map(|b| b.1.player_id).collect::<Vec<_>>());
+            }
+            busted.extend(busted_here);
+            played = true;
+        }
+        if !played {
+            return failed.map_or(Ok(false), Err);
+        }
+        self.rounds_played += 1;
+        self.elapsed += self.hand_duration;
+
+        let remaining = self.players_left();
+        self.finishes.extend(place_busts(busted, remaining));
+        if remaining == 1 {
+            let (winner, hand_id) = self
+                .tables()
+                .find_map(|(_, game)| game.players().first().map(|p| (*p, game.last_hand().map_or(0, |r| r.hand_id))))
+                .unwrap();
+            let finish = Finish { player_id: winner.id, name: winner.name.clone(), place: 1, hand_id, prize: 0, bounties: 0 };
+            if let Some(bounties) = &mut self.bounties {
+                bounties.crown(finish.player_id);
+            }
+            self.finishes.push(finish);
+        }
+        if let Some(err) = failed {
+            return Err(err);
+        }
+        self.show_bounties();
+        self.balance()?;
+        Ok(true)
+    }
+
+    // plays rounds until there is a winner, or until no hand can be dealt
+    pub fn play(&mut self) -> Result<(), PokerError> {
+        while self.play_round()? {}
+        Ok(())
+    }
+
+    // Everyone who has finished so far, best place first, with the payouts
+    // and any bounties applied as in a single table Tournament.
+    pub fn results(&self) -> Vec<Finish> {
+        with_bounties(pay(&self.finishes, &self.payouts), self.bounties.as_ref())
+    }
+
+    // every table shows the bounties of the whole field, so they still hold
+    // for players moved between tables
+    fn show_bounties(&mut self) {
+        if let Some(bounties) = &self.bounties {
+            for (_, game) in &mut self.tables {
+                game.set_bounties(bounties.bounties().clone());
+            }
+        }
+    }
+
+    fn seated(&self, idx: usize) -> usize {
+        self.tables[idx].1.players().len()
+    }
+
+    fn balance(&mut self) -> Result<(), PokerError> {
+        let was_split = self.tables.len() > 1;
+        while self.tables.len() > 1 && self.players_left() <= (self.tables.len() - 1) * self.seats_per_table {
+            // of the smallest tables the highest numbered breaks first
+            let broken = (0..self.tables.len()).min_by_key(|&t| (self.seated(t), Reverse(self.tables[t].0))).unwrap();
+            while let Some(seat) = next_big_blind(&self.tables[broken].1) {
+                let to = (0..self.tables.len())
+                    .filter(|&t| t != broken)
+                    .min_by_key(|&t| (self.seated(t), self.tables[t].0))
+                    .unwrap();
+                self.move_player(broken, to, seat, true)?;
+            }
+            self.tables.remove(broken);
+        }
+        if was_split && self.tables.len() == 1 && self.players_left() > 1 {
+            self.draw_final_table()?;
+        }
+
+        loop {
+            let n_tables = self.tables.len();
+            let biggest = (0..n_tables).max_by_key(|&t| (self.seated(t), Reverse(self.tables[t].0))).unwrap();
+            let smallest = (0..n_tables).min_by_key(|&t| (self.seated(t), self.tables[t].0)).unwrap();
+            if self.seated(biggest) <= self.seated(smallest) + 1 {
+                return Ok(());
+            }
+            let seat = next_big_blind(&self.tables[biggest].1).unwrap();
+            self.move_player(biggest, smallest, seat, false)?;
+        }
+    }
+
+    fn move_player(&mut self, from: usize, to: usize, seat: usize, broken: bool) -> Result<(), PokerError> {
+        let to_seat = open_seat(&self.tables[to].1)
+            .ok_or(PokerError::TooManyPlayers { requested: self.seats_per_table + 1, max: self.seats_per_table })?;
+        let from_game = &mut self.tables[from].1;
+        let player = from_game.leave(seat).ok_or(PokerError::SeatEmpty(seat))?;
+        let strategy = from_game.take_strategy(player.id);
+        let player_id = player.id;
+
+        let to_game = &mut self.tables[to].1;
+        to_game.sit(to_seat, player)?;
+        if let Some(strategy) = strategy {
+            to_game.set_strategy(player_id, strategy);
+        }
+        self.moves.push(Move {
+            player_id,
+            from_table: self.tables[from].0,
+            to_table: self.tables[to].0,
+            seat: to_seat,
+            round: self.rounds_played,
+            broken,
+        });
+        Ok(())
+    }
+
+    // everyone at the last table draws for a new seat and the button
+    fn draw_final_table(&mut self) -> Result<(), PokerError> {
+        let mut rng = crate::rng::rng();
+        let game = &mut self.tables[0].1;
+        let seats: Vec<usize> = game.table().occupied().map(|(seat, _)| seat).collect();
+        let mut players: Vec<Player> = seats.into_iter().filter_map(|seat| game.leave(seat)).collect();
+        players.shuffle(&mut rng);
+        let n_players = players.len();
+        for (idx, player) in players.into_iter().enumerate() {
+            game.sit(idx + 1, player)?;
+        }
+        game.set_button(rng.gen_range(1..=n_players));
+        game.set_table_name("Final Table");
+        self.final_table_round = Some(self.rounds_played);
+        Ok(())
+    }
+}
+
+// The seat of whoever is due the big blind next hand, or of anyone still
+// seated when nobody there can be dealt in.
+fn next_big_blind(game: &Game) -> Option<usize> {
+    let last = game.positions().map_or(0, |p| p.big_blind);
+    game.table().next_dealt_in(last).or_else(|| game.table().occupied().next().map(|(seat, _)| seat))
+}
+
+// the open seat nearest after the last big blind, going round the table
+fn open_seat(game: &Game) -> Option<usize> {
+    let last = game.positions().map_or(0, |p| p.big_blind);
+    let max_seats = game.table().max_seats();
+    (0..max_seats).map(|n| (last + n) % max_seats + 1).find(|&seat| game.table().player(seat).is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::deck::Card;
+    use crate::game::Ante;
+    use crate::player::Action;
+    use crate::strategy::{Aggressive, CallingStation, Tight};
+    use crate::table::Position;
+    use crate::tournament::{split_prize_pool, BlindLevel, LevelLength};
+
+    fn schedule() -> BlindSchedule {
+        BlindSchedule {
+            levels: vec![BlindLevel::new(5, 10, Ante::None), BlindLevel::new(25, 50, Ante::BigBlind(50)), BlindLevel::new(100, 200, Ante::BigBlind(200))],
+            length: LevelLength::Hands(10),
+        }
+    }
+
+    fn mtt(n_players: usize, seats_per_table: usize) -> MultiTableTournament {
+        let players = (0..n_players).map(|id| Player::new(id, format!("P{}", id + 1), 1000)).collect();
+        let mut mtt = MultiTableTournament::new(players, seats_per_table, schedule()).unwrap();
+        for id in 0..n_players {
+            mtt.set_strategy(id, Box::new(CallingStation));
+        }
+        mtt
+    }
+
+    fn sizes(mtt: &MultiTableTournament) -> Vec<usize> {
+        mtt.tables().map(|(_, game)| game.players().len()).collect()
+    }
+
+    // takes the player out as if they had busted
+    fn remove(mtt: &mut MultiTableTournament, player_id: usize) {
+        let (number, seat) = mtt.table_of(player_id).unwrap();
+        mtt.table_mut(number).unwrap().leave(seat);
+    }
+
+    #[test]
+    fn test_seating() {
+        let mtt = mtt(100, 9);
+        assert_eq!(mtt.tables().map(|(number, _)| number).collect::<Vec<_>>(), (1..=12).collect::<Vec<_>>());
+        assert_eq!(sizes(&mtt), [9, 9, 9, 9, 8, 8, 8, 8, 8, 8, 8, 8]);
+        assert!((0..100).all(|id| mtt.table_of(id).is_some()));
+        assert_eq!(mtt.players_left(), 100);
+        assert!(mtt.final_table().is_none());
+
+        let players = vec![Player::new(0, "A".to_string(), 100), Player::new(0, "B".to_string(), 100)];
+        assert_eq!(MultiTableTournament::new(players, 9, schedule()).err(), Some(PokerError::AlreadySeated(0)));
+        assert!(MultiTableTournament::new(Vec::new(), 23, schedule()).is_err());
+    }
+
+    #[test]
+    fn test_balancing() {
+        let mut mtt = mtt(18, 6);
+        assert!(mtt.play_round().unwrap());
+        assert!(mtt.moves().is_empty());
+
+        let due_big_blind = |mtt: &MultiTableTournament, number: usize| {
+            let game = mtt.table(number).unwrap();
+            game.table().player(next_big_blind(game).unwrap()).unwrap().id
+        };
+        let first_to_move = due_big_blind(&mtt, 2);
+        // the three players after the big blind at table 1 bust
+        let table_1 = mtt.table(1).unwrap();
+        let mut seat = table_1.positions().unwrap().big_blind;
+        let mut busting = Vec::new();
+        for _ in 0..3 {
+            seat = table_1.table().next_dealt_in(seat).unwrap();
+            busting.push(table_1.table().player(seat).unwrap().id);
+        }
+        for id in busting {
+            remove(&mut mtt, id);
+        }
+        let open = open_seat(mtt.table(1).unwrap()).unwrap();
+        mtt.balance().unwrap();
+        assert_eq!(sizes(&mtt), [5, 5, 5]);
+        let moves = mtt.moves();
+        assert_eq!(moves.len(), 2);
+        assert_eq!(
+            moves[0],
+            Move { player_id: first_to_move, from_table: 2, to_table: 1, seat: open, round: 1, broken: false }
+        );
+        assert_eq!((moves[1].from_table, moves[1].to_table), (3, 1));
+        assert_eq!(mtt.table_of(first_to_move), Some((1, open)));
+
+        // the moved player posts the big blind on their first hand at the new table
+        mtt.play_round().unwrap();
+        let record = mtt.table(1).unwrap().last_hand().unwrap();
+        assert_eq!(record.seat(first_to_move).map(|s| s.seat), Some(open));
+        assert!(record.positions().contains(&(first_to_move, Position::BigBlind)));
+    }
+
+    #[test]
+    fn test_breaking_and_final_table() {
+        let mut mtt = mtt(13, 6);
+        assert_eq!(sizes(&mtt), [5, 4, 4]);
+        mtt.play_round().unwrap();
+        let leaving: Vec<usize> = mtt.table(1).unwrap().players().iter().map(|p| p.id).take(1).collect();
+        remove(&mut mtt, leaving[0]);
+        mtt.balance().unwrap();
+        // twelve players fit at two tables, so table 3 breaks
+        assert_eq!(mtt.tables().map(|(number, _)| number).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(sizes(&mtt), [6, 6]);
+        assert!(mtt.moves().iter().all(|m| m.broken && m.from_table == 3));
+        assert!(mtt.final_table_round().is_none());
+
+        for id in mtt.stacks().iter().map(|s| s.0).take(6).collect::<Vec<_>>() {
+            remove(&mut mtt, id);
+        }
+        mtt.balance().unwrap();
+        let final_table = mtt.final_table().unwrap();
+        assert_eq!(final_table.table().occupied().map(|(seat, _)| seat).collect::<Vec<_>>(), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(mtt.final_table_round(), Some(1));
+        assert_eq!(mtt.players_left(), 6);
+    }
+
+    #[test]
+    fn test_failed_table_keeps_earlier_busts() {
+        struct AlwaysCheck;
+        impl Strategy for AlwaysCheck {
+            fn decide(&mut self, _player: &Player, _pot: u32, _board: &[Card], _to_call: u32, _action: &[Vec<Action>]) -> Action {
+                Action::Check
+            }
+        }
+        let players = (0..6).map(|id| Player::new(id, format!("P{}", id + 1), 10)).collect();
+        let mut mtt = MultiTableTournament::new(players, 3, schedule()).unwrap();
+        let ids = |mtt: &MultiTableTournament, number: usize| mtt.table(number).unwrap().players().iter().map(|p| p.id).collect::<Vec<_>>();
+        let (first, second) = (ids(&mtt, 1), ids(&mtt, 2));
+        for &id in &first {
+            mtt.set_strategy(id, Box::new(CallingStation));
+        }
+        // everyone at table 1 is all in from the blinds and the aces win it all
+        let cards = |codes: &str| codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect::<Vec<_>>();
+        let hole_cards = HashMap::from([(first[0], cards("Ah Ad")), (first[1], cards("Kh Kd")), (first[2], cards("Qh Qd"))]);
+        mtt.table_mut(1).unwrap().force_cards(hole_cards, cards("2c 7d 9s Js 5c")).unwrap();
+        // and table 2 checks into the big blind
+        for &id in &second {
+            mtt.set_strategy(id, Box::new(AlwaysCheck));
+        }
+
+        assert!(matches!(mtt.play_round(), Err(PokerError::InvalidAction { .. })));
+        let mut busted: Vec<usize> = mtt.results().iter().map(|f| f.player_id).collect();
+        busted.sort();
+        assert_eq!(busted, [first[1].min(first[2]), first[1].max(first[2])]);
+        assert_eq!(mtt.players_left(), 4);
+    }
+
+    #[test]
+    fn test_play_to_the_end() {
+        crate::rng::seed(7);
+        let players = (0..300).map(|id| Player::new(id, format!("P{}", id + 1), 1000)).collect();
+        let mut mtt = MultiTableTournament::new(players, 9, schedule()).unwrap();
+        for id in 0..300 {
+            match id % 3 {
+                0 => mtt.set_strategy(id, Box::new(Aggressive { pot_fraction: 0.5 })),
+                1 => mtt.set_strategy(id, Box::new(Tight { pot_fraction: 1.0 })),
+                _ => {},
+            }
+        }
+        mtt.set_payouts(split_prize_pool(4000, &[50, 30, 20]));
+        mtt.set_bounties(BountyFormat::Progressive, 10);
+        mtt.play().unwrap();
+        crate::rng::unseed();
+
+        assert!(mtt.is_finished());
+        assert!(mtt.final_table_round().is_some());
+        let results = mtt.results();
+        assert_eq!(results.len(), 300);
+        assert_eq!(results[0].place, 1);
+        assert_eq!(mtt.stacks(), [(results[0].player_id, 300_000)]);
+        assert_eq!(results.iter().map(|f| f.prize).sum::<u32>(), 4000);
+        // every bounty is paid out in the end, to players at any table
+        assert_eq!(results.iter().map(|f| f.bounties).sum::<u32>(), 3000);
+        let knocked_out: HashSet<usize> = mtt.bounties().unwrap().knockouts().iter().map(|k| k.player_id).collect();
+        assert_eq!(knocked_out.len(), 299);
+        assert!(results.windows(2).all(|w| w[0].place <= w[1].place));
+        assert!(!mtt.play_round().unwrap());
+    }
+}
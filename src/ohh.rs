@@ -0,0 +1,364 @@
+use std::io::{self, Write};
+
+use crate::betting::BettingStructure;
+use crate::deck::Card;
+use crate::game::BettingRoundName;
+use crate::history::{civil_from_timestamp, ActionKind, ActionRecord, HandHistoryWriter, HandRecord};
+use crate::json::Json;
+use crate::rake::RakeAttribution;
+use crate::variant::Variant;
+
+// The Open Hand History standard (https://hh-specs.handhistory.org) wraps each
+// hand in an {"ohh": ...} object. Raises are written "to" a street total like
+// the site text, every other amount is what the action put in.
+
+pub const SPEC_VERSION: &str = "1.4.6";
+
+// Streams hands one JSON object per line, so a long simulation never has to
+// hold more than the hand it is writing.
+pub struct OhhWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> OhhWriter<W> {
+    pub fn new(out: W) -> OhhWriter<W> {
+        OhhWriter { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> HandHistoryWriter for OhhWriter<W> {
+    fn write_hand(&mut self, hand: &HandRecord) -> io::Result<()> {
+        if let Some(message) = self.unsupported(hand.variant, hand.betting) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+        writeln!(self.out, "{}", format_hand(hand))?;
+        self.out.flush()
+    }
+
+    // the standard has no actions for drawing cards
+    fn unsupported(&self, variant: Variant, _betting: BettingStructure) -> Option<String> {
+        (variant.draws() > 0).then(|| format!("Open Hand History cannot record the draws of {}", variant.name()))
+    }
+}
+
+static STREETS: &[(BettingRoundName, &str, usize)] = &[
+    (BettingRoundName::Preflop, "Preflop", 0),
+    (BettingRoundName::Flop, "Flop", 3),
+    (BettingRoundName::Turn, "Turn", 4),
+    (BettingRoundName::River, "River", 5),
+];
+
+// with how many cards each player holds once the street is dealt
+static STUD_STREETS: &[(BettingRoundName, &str, usize)] = &[
+    (BettingRoundName::ThirdStreet, "Third Street", 3),
+    (BettingRoundName::FourthStreet, "Fourth Street", 4),
+    (BettingRoundName::FifthStreet, "Fifth Street", 5),
+    (BettingRoundName::SixthStreet, "Sixth Street", 6),
+    (BettingRoundName::SeventhStreet, "Seventh Street", 7),
+];
+
+fn cards(cards: &[Card]) -> Json {
+    Json::from(cards.iter().map(|c| c.code()).collect::<Vec<_>>())
+}
+
+fn action_name(kind: ActionKind) -> &'static str {
+    match kind {
+        ActionKind::Ante => "Post Ante",
+        ActionKind::SmallBlind => "Post SB",
+        ActionKind::BigBlind => "Post BB",
+        ActionKind::Straddle => "Straddle",
+        ActionKind::DeadBlind => "Post Dead",
+        ActionKind::BringIn => "Post Bring In",
+        ActionKind::Fold => "Fold",
+        ActionKind::Check => "Check",
+        ActionKind::Call => "Call",
+        ActionKind::Bet => "Bet",
+        ActionKind::Raise => "Raise",
+    }
+}
+
+fn action_json(number: usize, action: &ActionRecord) -> Json {
+    let amount = if action.kind == ActionKind::Raise { action.to } else { action.amount };
+    let json = Json::object()
+        .with("action_number", number)
+        .with("player_id", action.player_id)
+        .with("action", action_name(action.kind));
+    match action.kind {
+        ActionKind::Fold | ActionKind::Check => json,
+        _ => json.with("amount", amount).with("is_allin", action.all_in),
+    }
+}
+
+// "YYYY-MM-DDTHH:MM:SSZ"
+fn iso_date(timestamp: u64) -> String {
+    let (year, month, day, hour, minute, second) = civil_from_timestamp(timestamp);
+    format!("{}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, hour, minute, second)
+}
+
+pub(crate) fn format_hand(hand: &HandRecord) -> Json {
+    let players = hand
+        .seats
+        .iter()
+        .map(|seat| {
+            Json::object()
+                .with("id", seat.player_id)
+                .with("seat", seat.seat)
+                .with("name", seat.name.as_str())
+                .with("display", seat.name.as_str())
+                .with("starting_stack", seat.stack)
+        })
+        .collect();
+
+    let mut rounds = Vec::new();
+    let mut action_number = 0;
+    let mut next_number = || {
+        action_number += 1;
+        action_number
+    };
+    // stud deals everyone's cards street by street instead of a board
+    let stud = hand.variant.is_stud();
+    let mut held = 0;
+    for (street, name, size) in if stud { STUD_STREETS } else { STREETS } {
+        let (board_size, dealt) = match street {
+            _ if stud => (0, held..*size),
+            BettingRoundName::Preflop => (0, 0..hand.variant.hole_cards()),
+            _ => (*size, 0..0),
+        };
+        if hand.board.len() < board_size || (stud && hand.seats.iter().all(|s| s.cards.len() < dealt.end)) {
+            break;
+        }
+        held = dealt.end;
+        let mut actions = Vec::new();
+        let (blinds, rest): (Vec<&ActionRecord>, Vec<&ActionRecord>) = hand
+            .actions_on(*street)
+            .partition(|a| a.kind.is_forced());
+        for action in blinds {
+            actions.push(action_json(next_number(), action));
+        }
+        // the cards come between the blinds and the first voluntary action
+        for seat in hand.seats.iter().filter(|s| !dealt.is_empty() && s.cards.len() >= dealt.end) {
+            actions.push(Json::object()
+                .with("action_number", next_number())
+                .with("player_id", seat.player_id)
+                .with("action", "Dealt Cards")
+                .with("cards", cards(&seat.cards[dealt.clone()])));
+        }
+        for action in rest {
+            actions.push(action_json(next_number(), action));
+        }
+
+        let mut round = Json::object().with("id", rounds.len()).with("street", *name);
+        if board_size > 0 {
+            let dealt_before = if board_size == 3 { 0 } else { board_size - 1 };
+            round = round.with("cards", cards(&hand.board[dealt_before..board_size]));
+        }
+        rounds.push(round.with("actions", Json::Array(actions)));
+    }
+    if !hand.showdown.is_empty() {
+        let actions = hand
+            .showdown
+            .iter()
+            .map(|shown| {
+                Json::object()
+                    .with("action_number", next_number())
+                    .with("player_id", shown.player_id)
+                    .with("action", "Shows Cards")
+                    .with("cards", cards(&hand.seat(shown.player_id).map_or(Vec::new(), |s| s.cards.clone())))
+            })
+            .collect();
+        rounds.push(Json::object().with("id", rounds.len()).with("street", "Showdown").with("actions", Json::Array(actions)));
+    }
+
+    // what a winner paid towards the hand's rake, shared out between the
+    // pots the way the rake itself was
+    let contributed_rake = |player_id: usize, pot_rake: u32| {
+        if hand.rake == 0 {
+            return 0.0;
+        }
+        hand.rake_paid(player_id, RakeAttribution::Contributed) * pot_rake as f64 / hand.rake as f64
+    };
+    let pots = hand
+        .pots
+        .iter()
+        .enumerate()
+        .map(|(number, pot)| {
+            let wins = pot
+                .winners
+                .iter()
+                .map(|(player_id, amount)| {
+                    Json::object()
+                        .with("player_id", *player_id)
+                        .with("win_amount", *amount)
+                        .with("contributed_rake", contributed_rake(*player_id, pot.rake))
+                })
+                .collect();
+            Json::object()
+                .with("number", number)
+                .with("amount", pot.amount + pot.rake)
+                .with("rake", pot.rake)
+                .with("player_wins", Json::Array(wins))
+        })
+        .collect();
+
+    // bet_cap caps what a whole hand can cost, which none of these do
+    let bet_type = match hand.betting {
+        BettingStructure::NoLimit => "NL",
+        BettingStructure::PotLimit => "PL",
+        BettingStructure::FixedLimit { .. } => "FL",
+    };
+    Json::object().with("ohh", Json::object()
+        .with("spec_version", SPEC_VERSION)
+        .with("site_name", env!("CARGO_PKG_NAME"))
+        .with("network_name", env!("CARGO_PKG_NAME"))
+        .with("internal_version", env!("CARGO_PKG_VERSION"))
+        .with("tournament", false)
+        .with("game_number", hand.hand_id.to_string())
+        .with("start_date_utc", iso_date(hand.timestamp))
+        .with("table_name", hand.table_name.as_str())
+        .with("game_type", match hand.variant {
+            // OHH has no short deck game type of its own
+            Variant::Holdem | Variant::ShortDeck { .. } => "Holdem",
+            Variant::Omaha { .. } => "Omaha",
+            Variant::OmahaHiLo { .. } => "OmahaHiLo",
+            Variant::Stud => "Stud",
+            Variant::Razz => "Razz",
+            Variant::FiveCardDraw | Variant::DeuceToSevenTripleDraw | Variant::Badugi => "Draw",
+        })
+        .with("bet_limit", Json::object().with("bet_type", bet_type).with("bet_cap", 0u32))
+        .with("table_size", hand.max_seats)
+        .with("currency", "Chips")
+        .with("dealer_seat", hand.button_seat)
+        .with("small_blind_amount", hand.small_blind)
+        .with("big_blind_amount", hand.big_blind)
+        .with("ante_amount", hand.ante)
+        .with("flags", Json::Array(Vec::new()))
+        .with("players", Json::Array(players))
+        .with("rounds", Json::Array(rounds))
+        .with("pots", Json::Array(pots)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Ante, Game, Straddle};
+    use crate::history::PotRecord;
+    use crate::variant::Variant;
+    use crate::json;
+    use crate::pokerstars;
+
+    #[test]
+    fn test_format_hand() {
+        let hands = pokerstars::parse_hands(include_str!("../corpus/pokerstars.txt")).unwrap();
+        let ohh = format_hand(&hands[0]);
+        let ohh = ohh.get("ohh").unwrap();
+        assert_eq!(ohh.get("spec_version").unwrap().as_str(), Some(SPEC_VERSION));
+        assert_eq!(ohh.get("game_number").unwrap().as_str(), Some("1001"));
+        assert_eq!(ohh.get("start_date_utc").unwrap().as_str(), Some("2024-05-04T18:00:00Z"));
+        assert_eq!(ohh.get("dealer_seat").unwrap().as_i64(), Some(3));
+        assert_eq!(ohh.get("players").unwrap().as_array().unwrap().len(), 6);
+        assert_eq!(ohh.get("bet_limit").unwrap().to_string(), r#"{"bet_type":"NL","bet_cap":0}"#);
+        let limit = HandRecord { betting: BettingStructure::FixedLimit { cap: 4 }, ..hands[0].clone() };
+        assert_eq!(format_hand(&limit).get("ohh").unwrap().get("bet_limit").unwrap().get("bet_type").unwrap().as_str(), Some("FL"));
+        assert_eq!(ohh.get("game_type").unwrap().as_str(), Some("Holdem"));
+        let omaha = HandRecord { variant: Variant::omaha(), ..hands[0].clone() };
+        assert_eq!(format_hand(&omaha).get("ohh").unwrap().get("game_type").unwrap().as_str(), Some("Omaha"));
+
+        let rounds = ohh.get("rounds").unwrap().as_array().unwrap();
+        assert_eq!(rounds.len(), 2);
+        let preflop = rounds[0].get("actions").unwrap().as_array().unwrap();
+        assert_eq!(preflop[0].to_string(), r#"{"action_number":1,"player_id":3,"action":"Post SB","amount":1,"is_allin":false}"#);
+        assert_eq!(preflop[2].to_string(), r#"{"action_number":3,"player_id":5,"action":"Dealt Cards","cards":["As","Qs"]}"#);
+        assert_eq!(preflop[3].to_string(), r#"{"action_number":4,"player_id":5,"action":"Raise","amount":6,"is_allin":false}"#);
+        assert_eq!(rounds[1].get("cards").unwrap().to_string(), r#"["Kd","7s","2c"]"#);
+
+        let pots = ohh.get("pots").unwrap().as_array().unwrap();
+        assert_eq!(pots[0].to_string(), r#"{"number":0,"amount":13,"rake":0,"player_wins":[{"player_id":5,"win_amount":13,"contributed_rake":0}]}"#);
+    }
+
+    #[test]
+    fn test_format_showdown() {
+        let hands = pokerstars::parse_hands(include_str!("../corpus/pokerstars.txt")).unwrap();
+        let ohh = format_hand(&hands[1]);
+        let rounds = ohh.get("ohh").unwrap().get("rounds").unwrap().as_array().unwrap();
+        let streets: Vec<&str> = rounds.iter().map(|r| r.get("street").unwrap().as_str().unwrap()).collect();
+        assert_eq!(streets, ["Preflop", "Flop", "Turn", "River", "Showdown"]);
+        assert_eq!(rounds[2].get("cards").unwrap().to_string(), r#"["Js"]"#);
+        let shows = rounds[4].get("actions").unwrap().as_array().unwrap();
+        assert_eq!(shows[0].to_string(), r#"{"action_number":9,"player_id":0,"action":"Shows Cards","cards":["Ah","Ad"]}"#);
+        assert_eq!(ohh.get("ohh").unwrap().get("pots").unwrap().as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_format_side_pot_rake() {
+        let mut hand = pokerstars::parse_hands(include_str!("../corpus/pokerstars.txt")).unwrap()[1].clone();
+        hand.rake = 61;
+        hand.pots[0] = PotRecord { amount: 118, rake: 32, winners: vec![(0, 118)] };
+        hand.pots[1] = PotRecord { amount: 111, rake: 29, winners: vec![(1, 111)] };
+        let ohh = format_hand(&hand);
+        let pots = ohh.get("ohh").unwrap().get("pots").unwrap().as_array().unwrap();
+        assert_eq!(pots[0].get("amount").unwrap().as_i64(), Some(150));
+        assert_eq!(pots[0].get("rake").unwrap().as_i64(), Some(32));
+        // Ben put in 120 of the 290, so paid 12 of the side pot's 29
+        assert_eq!(pots[1].to_string(), r#"{"number":1,"amount":140,"rake":29,"player_wins":[{"player_id":1,"win_amount":111,"contributed_rake":12}]}"#);
+    }
+
+    #[test]
+    fn test_format_antes_and_straddle() {
+        let mut game = Game::new(4, 200).unwrap();
+        game.set_ante(Ante::Everyone(2));
+        game.set_straddle(Straddle::UnderTheGun);
+        game.play_round().unwrap();
+        let ohh = format_hand(game.last_hand().unwrap());
+        let ohh = ohh.get("ohh").unwrap();
+        assert_eq!(ohh.get("ante_amount").unwrap().as_i64(), Some(2));
+        let preflop = ohh.get("rounds").unwrap().as_array().unwrap()[0].get("actions").unwrap().as_array().unwrap();
+        assert_eq!(preflop[0].to_string(), r#"{"action_number":1,"player_id":1,"action":"Post Ante","amount":2,"is_allin":false}"#);
+        assert_eq!(preflop[6].to_string(), r#"{"action_number":7,"player_id":3,"action":"Straddle","amount":4,"is_allin":false}"#);
+        assert_eq!(preflop[7].get("action").unwrap().as_str(), Some("Dealt Cards"));
+    }
+
+    #[test]
+    fn test_format_stud() {
+        let mut game = Game::new(3, 200).unwrap();
+        game.set_verbose(false);
+        game.set_variant(Variant::Stud).unwrap();
+        for id in 0..3 {
+            game.set_strategy(id, Box::new(crate::strategy::CallingStation));
+        }
+        game.play_round().unwrap();
+        let ohh = format_hand(game.last_hand().unwrap());
+        let ohh = ohh.get("ohh").unwrap();
+        assert_eq!(ohh.get("game_type").unwrap().as_str(), Some("Stud"));
+        let rounds = ohh.get("rounds").unwrap().as_array().unwrap();
+        let streets: Vec<&str> = rounds.iter().map(|r| r.get("street").unwrap().as_str().unwrap()).collect();
+        assert_eq!(streets, ["Third Street", "Fourth Street", "Fifth Street", "Sixth Street", "Seventh Street", "Showdown"]);
+        // the bring-in, then three cards each on third street and one on every street after
+        let third = rounds[0].get("actions").unwrap().as_array().unwrap();
+        assert_eq!(third[0].get("action").unwrap().as_str(), Some("Post Bring In"));
+        assert_eq!(third[1].get("cards").unwrap().as_array().unwrap().len(), 3);
+        let fourth = rounds[1].get("actions").unwrap().as_array().unwrap();
+        assert_eq!(fourth[0].get("cards").unwrap().as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_writer_streams_lines() {
+        let mut game = Game::new(4, 200).unwrap();
+        let mut writer = OhhWriter::new(Vec::new());
+        for _ in 0..20 {
+            game.play_round().unwrap();
+            if let Some(hand) = game.last_hand() {
+                writer.write_hand(hand).unwrap();
+            }
+        }
+        let text = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(text.lines().count() > 0);
+        for line in text.lines() {
+            let hand = json::parse(line).unwrap();
+            assert!(hand.get("ohh").unwrap().get("rounds").is_some());
+        }
+    }
+}
@@ -0,0 +1,895 @@
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use crate::betting::{BettingStructure, DEFAULT_CAP};
+use crate::deck::Card;
+use crate::game::{BestHands, BestLows, BettingRoundName, Game, DRAW_ROUND_ORDER, ROUND_ORDER, STUD_ROUND_ORDER};
+use crate::history::{
+    civil_from_timestamp, combine_runs, timestamp_from_civil, ActionKind, ActionRecord, DrawRecord, HandHistoryWriter, HandRecord,
+    RunRecord, SeatRecord, ShowdownRecord,
+};
+use crate::toml::{self, Table, Value};
+use crate::utils::{compare_badugi, HandComparator};
+use crate::variant::{BestHand, Variant};
+
+// The Poker Hand History standard (https://phh.readthedocs.io) lists players
+// by position, starting left of the button and ending on it, as p1, p2, ...
+// Blinds come from `blinds_or_straddles` rather than from actions, every bet
+// or raise is "to" a street total, and dealing is written out as `d dh`/`d db`.
+// Stud is dealt with a `d dh` on every street and brings in with `pb`, and a
+// draw is a `sd` of the cards thrown away followed by a `d dh` of new ones.
+
+// Writes hands as a .phhs file: one [n] section per hand.
+pub struct PhhWriter<W: Write> {
+    out: W,
+    written: usize,
+}
+
+impl<W: Write> PhhWriter<W> {
+    pub fn new(out: W) -> PhhWriter<W> {
+        PhhWriter { out, written: 0 }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> HandHistoryWriter for PhhWriter<W> {
+    fn write_hand(&mut self, hand: &HandRecord) -> io::Result<()> {
+        if let Some(message) = self.unsupported(hand.variant, hand.betting) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+        self.written += 1;
+        write!(self.out, "[{}]\n{}\n", self.written, format_hand(hand))?;
+        self.out.flush()
+    }
+
+    fn unsupported(&self, variant: Variant, betting: BettingStructure) -> Option<String> {
+        variant_code(variant, betting).is_none().then(|| format!("PHH has no variant for {} {}", betting.name(), variant.name()))
+    }
+}
+
+// the PHH variants we play, by their codes
+static VARIANTS: &[(&str, Variant, BettingStructure)] = &[
+    ("NT", Variant::Holdem, BettingStructure::NoLimit),
+    ("FT", Variant::Holdem, BettingStructure::FixedLimit { cap: DEFAULT_CAP }),
+    ("PO", Variant::Omaha { hole_cards: 4 }, BettingStructure::PotLimit),
+    ("FO/8", Variant::OmahaHiLo { hole_cards: 4 }, BettingStructure::FixedLimit { cap: DEFAULT_CAP }),
+    // PHH's short deck ranks a straight above trips
+    ("NS", Variant::ShortDeck { trips_beat_straight: false }, BettingStructure::NoLimit),
+    ("F7S", Variant::Stud, BettingStructure::FixedLimit { cap: DEFAULT_CAP }),
+    ("FR", Variant::Razz, BettingStructure::FixedLimit { cap: DEFAULT_CAP }),
+    ("F2L3D", Variant::DeuceToSevenTripleDraw, BettingStructure::FixedLimit { cap: DEFAULT_CAP }),
+    ("FB", Variant::Badugi, BettingStructure::FixedLimit { cap: DEFAULT_CAP }),
+];
+
+// any raise cap writes as fixed limit
+fn variant_code(variant: Variant, betting: BettingStructure) -> Option<&'static str> {
+    let same = |b: BettingStructure| std::mem::discriminant(&b) == std::mem::discriminant(&betting);
+    VARIANTS.iter().find(|(_, v, b)| *v == variant && same(*b)).map(|v| v.0)
+}
+
+static BOARD_SIZES: &[usize] = &[0, 3, 4, 5];
+// how many cards a stud player holds after each street's deal
+static STUD_DEALT: &[usize] = &[3, 4, 5, 6, 7];
+
+fn streets(variant: Variant) -> &'static [BettingRoundName] {
+    if variant.is_stud() {
+        STUD_ROUND_ORDER
+    } else if variant.draws() > 0 {
+        &DRAW_ROUND_ORDER[..=variant.draws()]
+    } else {
+        ROUND_ORDER
+    }
+}
+
+// seats in PHH order: left of the button first, the button last
+fn positions(hand: &HandRecord) -> Vec<&SeatRecord> {
+    let button = hand.seats.iter().position(|s| s.seat == hand.button_seat).unwrap_or(0);
+    let n = hand.seats.len();
+    (1..=n).map(|i| &hand.seats[(button + i) % n]).collect()
+}
+
+fn cards_string(cards: &[Card]) -> String {
+    cards.iter().map(|c| c.code()).collect()
+}
+
+fn list<T: ToString>(items: impl Iterator<Item = T>) -> String {
+    format!("[{}]", items.map(|i| i.to_string()).collect::<Vec<_>>().join(", "))
+}
+
+pub fn format_hand(hand: &HandRecord) -> String {
+    let order = positions(hand);
+    let player = |player_id: usize| order.iter().position(|s| s.player_id == player_id).unwrap() + 1;
+    let posted = |seat: &SeatRecord, kind: ActionKind| hand.actions.iter().any(|a| a.player_id == seat.player_id && a.kind == kind);
+    let blind = |seat: &SeatRecord| {
+        if posted(seat, ActionKind::SmallBlind) {
+            hand.small_blind
+        } else if posted(seat, ActionKind::BigBlind) {
+            hand.big_blind
+        } else if posted(seat, ActionKind::Straddle) {
+            2 * hand.big_blind
+        } else {
+            0
+        }
+    };
+
+    let stud = hand.variant.is_stud();
+    let mut actions = Vec::new();
+    if !stud {
+        for seat in &order {
+            if !seat.cards.is_empty() {
+                actions.push(format!("d dh p{} {}", player(seat.player_id), cards_string(&seat.cards)));
+            }
+        }
+    }
+    for (street_idx, street) in streets(hand.variant).iter().enumerate() {
+        if stud {
+            let (from, to) = (if street_idx == 0 { 0 } else { STUD_DEALT[street_idx - 1] }, STUD_DEALT[street_idx]);
+            for seat in order.iter().filter(|s| s.cards.len() >= to) {
+                actions.push(format!("d dh p{} {}", player(seat.player_id), cards_string(&seat.cards[from..to])));
+            }
+        } else if hand.variant.draws() > 0 {
+            for draw in hand.draws.iter().filter(|d| d.street == *street) {
+                let p = player(draw.player_id);
+                // standing pat throws nothing away and is dealt nothing
+                actions.push(format!("p{} sd {}", p, cards_string(&draw.discarded)).trim_end().to_string());
+                if !draw.drawn.is_empty() {
+                    actions.push(format!("d dh p{} {}", p, cards_string(&draw.drawn)));
+                }
+            }
+        } else if street_idx > 0 {
+            let dealt = BOARD_SIZES[street_idx];
+            if hand.board.len() < dealt {
+                break;
+            }
+            actions.push(format!("d db {}", cards_string(&hand.board[BOARD_SIZES[street_idx - 1]..dealt])));
+        }
+        for action in hand.actions_on(*street) {
+            let p = player(action.player_id);
+            match action.kind {
+                ActionKind::Ante | ActionKind::SmallBlind | ActionKind::BigBlind | ActionKind::Straddle | ActionKind::DeadBlind => {}
+                ActionKind::BringIn => actions.push(format!("p{} pb", p)),
+                ActionKind::Fold => actions.push(format!("p{} f", p)),
+                ActionKind::Check | ActionKind::Call => actions.push(format!("p{} cc", p)),
+                ActionKind::Bet | ActionKind::Raise => actions.push(format!("p{} cbr {}", p, action.to)),
+            }
+        }
+    }
+    // a draw game shows the hand as it is after the last draw
+    for shown in &hand.showdown {
+        let mut cards = hand.seat(shown.player_id).unwrap().cards.clone();
+        for draw in hand.draws.iter().filter(|d| d.player_id == shown.player_id) {
+            cards.retain(|card| !draw.discarded.contains(card));
+            cards.extend(&draw.drawn);
+        }
+        actions.push(format!("p{} sm {}", player(shown.player_id), cards_string(&cards)));
+    }
+
+    let mut out = String::new();
+    let limit = matches!(hand.betting, BettingStructure::FixedLimit { .. });
+    writeln!(out, "variant = \"{}\"", variant_code(hand.variant, hand.betting).unwrap_or("NT")).unwrap();
+    // a dead small blind is dead money like an ante
+    let dead_blind = |seat: &SeatRecord| {
+        hand.actions.iter().filter(|a| a.player_id == seat.player_id && a.kind == ActionKind::DeadBlind).map(|a| a.amount).sum::<u32>()
+    };
+    let antes = order.iter().map(|s| if posted(s, ActionKind::Ante) { hand.ante } else { 0 } + dead_blind(s));
+    writeln!(out, "antes = {}", list(antes)).unwrap();
+    // stud has no blinds, only the bring-in
+    if stud {
+        writeln!(out, "bring_in = {}", hand.small_blind).unwrap();
+    } else {
+        writeln!(out, "blinds_or_straddles = {}", list(order.iter().map(|s| blind(s)))).unwrap();
+    }
+    if limit {
+        writeln!(out, "small_bet = {}", hand.big_blind).unwrap();
+        writeln!(out, "big_bet = {}", 2 * hand.big_blind).unwrap();
+    } else {
+        writeln!(out, "min_bet = {}", hand.big_blind).unwrap();
+    }
+    writeln!(out, "starting_stacks = {}", list(order.iter().map(|s| s.stack))).unwrap();
+    writeln!(out, "actions = [").unwrap();
+    for action in actions {
+        writeln!(out, "  {},", toml::quote(&action)).unwrap();
+    }
+    writeln!(out, "]").unwrap();
+    writeln!(out, "hand = {}", hand.hand_id).unwrap();
+    writeln!(out, "seats = {}", list(order.iter().map(|s| s.seat))).unwrap();
+    writeln!(out, "seat_count = {}", hand.max_seats).unwrap();
+    writeln!(out, "players = {}", list(order.iter().map(|s| toml::quote(&s.name)))).unwrap();
+    writeln!(out, "finishing_stacks = {}", list(order.iter().map(|s| hand.finishing_stack(s.player_id)))).unwrap();
+    let (year, month, day, hour, minute, second) = civil_from_timestamp(hand.timestamp);
+    writeln!(out, "time_zone = \"UTC\"").unwrap();
+    writeln!(out, "year = {}", year).unwrap();
+    writeln!(out, "month = {}", month).unwrap();
+    writeln!(out, "day = {}", day).unwrap();
+    writeln!(out, "time = {:02}:{:02}:{:02}", hour, minute, second).unwrap();
+    writeln!(out, "_table_name = {}", toml::quote(&hand.table_name)).unwrap();
+    // PHH has no field for the rake, so it is a user-defined one
+    if hand.rake > 0 {
+        writeln!(out, "_rake = {}", hand.rake).unwrap();
+    }
+    // and so is every board of a hand run more than once, the actions
+    // dealing only the first
+    if !hand.runs.is_empty() {
+        writeln!(out, "_boards = {}", list(hand.runs.iter().map(|run| toml::quote(&cards_string(&run.board))))).unwrap();
+    }
+    out
+}
+
+// Reads a single .phh hand, or every hand of a .phhs file in section order.
+pub fn parse_hands(text: &str) -> Result<Vec<HandRecord>, String> {
+    let doc = toml::parse(text)?;
+    if doc.contains_key("variant") {
+        return Ok(vec![hand_from_table(&doc)?]);
+    }
+    let mut sections: Vec<(&String, &Table)> = doc
+        .iter()
+        .filter_map(|(name, value)| value.as_table().map(|t| (name, t)))
+        .collect();
+    sections.sort_by_key(|(name, _)| (name.parse::<u64>().unwrap_or(u64::MAX), name.to_string()));
+    sections
+        .into_iter()
+        .map(|(name, table)| hand_from_table(table).map_err(|err| format!("hand [{}]: {}", name, err)))
+        .collect()
+}
+
+pub fn parse_hand(text: &str) -> Result<HandRecord, String> {
+    let mut hands = parse_hands(text)?;
+    match hands.len() {
+        1 => Ok(hands.remove(0)),
+        n => Err(format!("expected one hand but found {}", n)),
+    }
+}
+
+fn field<'a>(table: &'a Table, key: &str) -> Result<&'a Value, String> {
+    table.get(key).ok_or_else(|| format!("missing field '{}'", key))
+}
+
+fn integer(value: &Value, key: &str) -> Result<u64, String> {
+    match value.as_integer() {
+        Some(i) if i >= 0 => Ok(i as u64),
+        _ => Err(format!("'{}' must be a whole number of chips", key)),
+    }
+}
+
+fn integers(table: &Table, key: &str) -> Result<Vec<u32>, String> {
+    field(table, key)?
+        .as_array()
+        .ok_or_else(|| format!("'{}' must be an array", key))?
+        .iter()
+        .map(|v| integer(v, key).map(|i| i as u32))
+        .collect()
+}
+
+fn parse_cards(text: &str) -> Result<Vec<Card>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut cards = Vec::new();
+    for pair in chars.chunks(2) {
+        let code: String = pair.iter().collect();
+        if code == "??" {
+            continue;
+        }
+        cards.push(Card::from_code(&code).ok_or_else(|| format!("invalid card '{}'", code))?);
+    }
+    Ok(cards)
+}
+
+pub fn hand_from_table(table: &Table) -> Result<HandRecord, String> {
+    // limit hands do not say how many bets a street allows, so get the usual cap
+    let code = field(table, "variant")?.as_str().unwrap_or("");
+    let (variant, betting) = match VARIANTS.iter().find(|v| v.0 == code) {
+        Some(&(_, variant, betting)) => (variant, betting),
+        None => return Err(format!("unsupported variant '{}'", code)),
+    };
+    let stud = variant.is_stud();
+    let draws = variant.draws() > 0;
+    let streets = streets(variant);
+    let stacks = integers(table, "starting_stacks")?;
+    let blinds = match table.get("blinds_or_straddles") {
+        None if stud => vec![0; stacks.len()],
+        _ => integers(table, "blinds_or_straddles")?,
+    };
+    let antes = integers(table, "antes")?;
+    let n = stacks.len();
+    if n < 2 || blinds.len() != n || antes.len() != n {
+        return Err("starting_stacks, blinds_or_straddles and antes must list the same players".to_string());
+    }
+
+    let seats: Vec<usize> = match table.get("seats") {
+        Some(_) => integers(table, "seats")?.into_iter().map(|s| s as usize).collect(),
+        None => (1..=n).collect(),
+    };
+    let names: Vec<String> = match table.get("players").and_then(|v| v.as_array()) {
+        Some(names) => names.iter().map(|v| v.as_str().unwrap_or("").to_string()).collect(),
+        None => (1..=n).map(|i| format!("p{}", i)).collect(),
+    };
+    if seats.len() != n || names.len() != n {
+        return Err("seats and players must list every player".to_string());
+    }
+    // seats count from one
+    let max_seats = match table.get("seat_count") {
+        Some(count) => integer(count, "seat_count")? as usize,
+        None => seats.iter().copied().max().unwrap_or(0).max(n),
+    };
+    if let Some(seat) = seats.iter().find(|&&seat| seat < 1 || seat > max_seats) {
+        return Err(format!("seat {} is not one of the table's seats 1 to {}", seat, max_seats));
+    }
+    let timestamp = match (table.get("year"), table.get("month"), table.get("day")) {
+        (Some(year), Some(month), Some(day)) => {
+            let time = table.get("time").and_then(|t| t.as_str()).unwrap_or("00:00:00");
+            let hms: Vec<u32> = time.split(':').map(|part| part.parse().unwrap_or(0)).collect();
+            timestamp_from_civil(
+                year.as_integer().unwrap_or(1970),
+                integer(month, "month")? as u32,
+                integer(day, "day")? as u32,
+                hms.first().copied().unwrap_or(0),
+                hms.get(1).copied().unwrap_or(0),
+                hms.get(2).copied().unwrap_or(0),
+            )
+        }
+        _ => 0,
+    };
+
+    // the smallest blind is the small blind, the next the big blind and a
+    // third one a straddle
+    let mut posted: Vec<usize> = (0..n).filter(|&p| blinds[p] > 0).collect();
+    posted.sort_by_key(|&p| (blinds[p], p));
+    if posted.len() > 3 {
+        return Err("only one straddle is supported".to_string());
+    }
+    // stud's small blind is its bring-in and its big blind the small bet
+    let (small_blind, big_blind) = match posted.as_slice() {
+        [] if stud => (
+            integer(field(table, "bring_in")?, "bring_in")? as u32,
+            integer(field(table, "small_bet")?, "small_bet")? as u32,
+        ),
+        [] => (0, 0),
+        [big] => (0, blinds[*big]),
+        [small, big, ..] => (blinds[*small], blinds[*big]),
+    };
+    let blind_kinds: &[ActionKind] = match posted.len() {
+        1 => &[ActionKind::BigBlind],
+        _ => &[ActionKind::SmallBlind, ActionKind::BigBlind, ActionKind::Straddle],
+    };
+
+    let mut hand = HandRecord {
+        hand_id: table.get("hand").map_or(Ok(0), |v| integer(v, "hand"))?,
+        table_name: table.get("_table_name").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+        timestamp,
+        max_seats,
+        button_seat: seats[n - 1],
+        small_blind,
+        big_blind,
+        ante: 0,
+        variant,
+        betting,
+        seats: (0..n)
+            .map(|p| SeatRecord { seat: seats[p], player_id: seats[p] - 1, name: names[p].clone(), stack: stacks[p], cards: Vec::new(), up_cards: Vec::new() })
+            .collect(),
+        actions: Vec::new(),
+        draws: Vec::new(),
+        board: Vec::new(),
+        uncalled: None,
+        rake: table.get("_rake").map_or(Ok(0), |v| integer(v, "_rake"))? as u32,
+        pots: Vec::new(),
+        showdown: Vec::new(),
+        runs: Vec::new(),
+    };
+
+    let mut remaining = stacks.clone();
+    let mut street_bets = vec![0u32; n];
+    let mut total_bets = vec![0u32; n];
+    let mut folded = vec![false; n];
+    let mut cards: Vec<Vec<Card>> = vec![Vec::new(); n];
+    // the cards in the hand now, after any draws
+    let mut held: Vec<Vec<Card>> = vec![Vec::new(); n];
+    let mut stud_dealt = vec![0; n];
+    let mut shown: Vec<usize> = Vec::new();
+    let mut street = 0;
+
+    // antes are posted first and kept apart from the bets, though a short
+    // stack covers their blind before their ante, as in Game
+    let mut dead = vec![0u32; n];
+    for p in 0..n {
+        let amount = antes[p].min(remaining[p] - blinds[p].min(remaining[p]));
+        if amount == 0 {
+            continue;
+        }
+        remaining[p] -= amount;
+        dead[p] = amount;
+        hand.actions.push(ActionRecord {
+            street: BettingRoundName::Preflop,
+            player_id: hand.seats[p].player_id,
+            kind: ActionKind::Ante,
+            amount,
+            to: 0,
+            all_in: remaining[p] == 0,
+        });
+    }
+    hand.ante = dead.iter().copied().max().unwrap_or(0);
+    for (&p, kind) in posted.iter().zip(blind_kinds) {
+        let amount = blinds[p].min(remaining[p]);
+        if amount == 0 {
+            continue;
+        }
+        remaining[p] -= amount;
+        street_bets[p] += amount;
+        total_bets[p] += amount;
+        hand.actions.push(ActionRecord {
+            street: BettingRoundName::Preflop,
+            player_id: hand.seats[p].player_id,
+            kind: *kind,
+            amount,
+            to: street_bets[p],
+            all_in: remaining[p] == 0,
+        });
+    }
+    let mut high = if stud { 0 } else { street_bets.iter().copied().max().unwrap_or(0).max(big_blind) };
+    // the blinds count as the first bet, as a bring-in does not
+    let mut raises = if stud { 0 } else { 1 };
+
+    let lines = field(table, "actions")?.as_array().ok_or("'actions' must be an array")?;
+    for line in lines {
+        let line = line.as_str().ok_or("actions must be strings")?;
+        let line = line.split('#').next().unwrap();
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let player = |token: &str| -> Result<usize, String> {
+            match token.strip_prefix('p').and_then(|i| i.parse::<usize>().ok()) {
+                Some(i) if (1..=n).contains(&i) => Ok(i - 1),
+                _ => Err(format!("invalid player '{}' in action '{}'", token, line)),
+            }
+        };
+        match tokens.as_slice() {
+            ["d", "dh", p, dealt] => {
+                let p = player(p)?;
+                let player_id = hand.seats[p].player_id;
+                let dealt_cards = parse_cards(dealt)?;
+                let drawing = hand.draws.iter_mut().rev().find(|d| d.player_id == player_id && d.street == streets[street] && !d.discarded.is_empty() && d.drawn.is_empty());
+                if stud {
+                    // the first card of a new street moves the betting on to it
+                    stud_dealt[p] += dealt.len() / 2;
+                    let reached = stud_dealt[p].saturating_sub(3).min(streets.len() - 1);
+                    if reached > street {
+                        street = reached;
+                        street_bets = vec![0; n];
+                        high = 0;
+                        raises = 0;
+                    }
+                    cards[p].extend(&dealt_cards);
+                    held[p].extend(dealt_cards);
+                } else if let Some(draw) = drawing {
+                    held[p].extend(&dealt_cards);
+                    draw.drawn = dealt_cards;
+                } else {
+                    cards[p] = dealt_cards.clone();
+                    held[p] = dealt_cards;
+                }
+            }
+            ["d", "db", dealt] => {
+                if stud || draws {
+                    return Err(format!("unexpected board cards in '{}'", line));
+                }
+                hand.board.extend(parse_cards(dealt)?);
+                street += 1;
+                if street >= streets.len() || hand.board.len() != BOARD_SIZES[street] {
+                    return Err(format!("unexpected board cards in '{}'", line));
+                }
+                street_bets = vec![0; n];
+                high = 0;
+                raises = 0;
+            }
+            [p, "sd", ..] if draws => {
+                let p = player(p)?;
+                let player_id = hand.seats[p].player_id;
+                // everyone still in draws once a street, so drawing again
+                // starts the next one
+                if street == 0 || hand.draws.iter().any(|d| d.player_id == player_id && d.street == streets[street]) {
+                    street += 1;
+                    if street >= streets.len() {
+                        return Err(format!("one draw too many in '{}'", line));
+                    }
+                    street_bets = vec![0; n];
+                    high = 0;
+                    raises = 0;
+                }
+                let discarded = match tokens.get(2) {
+                    Some(discarded) => parse_cards(discarded)?,
+                    None => Vec::new(),
+                };
+                held[p].retain(|card| !discarded.contains(card));
+                hand.draws.push(DrawRecord { street: streets[street], player_id, discarded, drawn: Vec::new() });
+            }
+            [p, "pb"] if stud => {
+                let p = player(p)?;
+                let amount = small_blind.min(remaining[p]);
+                remaining[p] -= amount;
+                street_bets[p] += amount;
+                total_bets[p] += amount;
+                high = high.max(small_blind);
+                hand.actions.push(ActionRecord {
+                    street: streets[street],
+                    player_id: hand.seats[p].player_id,
+                    kind: ActionKind::BringIn,
+                    amount,
+                    to: street_bets[p],
+                    all_in: remaining[p] == 0,
+                });
+            }
+            [p, "f"] => {
+                let p = player(p)?;
+                folded[p] = true;
+                hand.actions.push(ActionRecord {
+                    street: streets[street],
+                    player_id: hand.seats[p].player_id,
+                    kind: ActionKind::Fold,
+                    amount: 0,
+                    to: street_bets[p],
+                    all_in: false,
+                });
+            }
+            [p, "cc"] | [p, "cbr", _] => {
+                let p = player(p)?;
+                let to = match tokens.get(2) {
+                    Some(to) => to.parse::<u32>().map_err(|_| format!("invalid amount in '{}'", line))?,
+                    None => high,
+                };
+                let kind = if to <= high {
+                    if high > street_bets[p] { ActionKind::Call } else { ActionKind::Check }
+                } else if high == 0 || raises == 0 {
+                    ActionKind::Bet
+                } else {
+                    ActionKind::Raise
+                };
+                if to > high {
+                    raises += 1;
+                }
+                let amount = to.saturating_sub(street_bets[p]).min(remaining[p]);
+                remaining[p] -= amount;
+                street_bets[p] += amount;
+                total_bets[p] += amount;
+                high = high.max(street_bets[p]);
+                hand.actions.push(ActionRecord {
+                    street: streets[street],
+                    player_id: hand.seats[p].player_id,
+                    kind,
+                    amount,
+                    to: street_bets[p],
+                    all_in: amount > 0 && remaining[p] == 0,
+                });
+            }
+            [p, "sm", ..] => {
+                let p = player(p)?;
+                if let Some(shown_cards) = tokens.get(2) {
+                    let shown_cards = parse_cards(shown_cards)?;
+                    if !shown_cards.is_empty() {
+                        // a draw game's seat keeps the cards first dealt
+                        if !draws {
+                            cards[p] = shown_cards.clone();
+                        }
+                        held[p] = shown_cards;
+                    }
+                }
+                shown.push(p);
+            }
+            _ => return Err(format!("unsupported action '{}'", line)),
+        }
+    }
+    for (seat, cards) in hand.seats.iter_mut().zip(cards) {
+        // stud's third to sixth cards are dealt face up
+        if stud {
+            seat.up_cards = cards.get(2..cards.len().min(6)).unwrap_or_default().to_vec();
+        }
+        seat.cards = cards;
+    }
+
+    // settle the pots the same way Game does
+    if let Some((p, amount)) = Game::return_uncalled(&mut total_bets) {
+        hand.uncalled = Some((hand.seats[p].player_id, amount));
+    }
+    for (bet, ante) in total_bets.iter_mut().zip(&dead) {
+        *bet += ante;
+    }
+    let live: Vec<bool> = folded.iter().map(|f| !f).collect();
+    let boards = match table.get("_boards") {
+        None => vec![hand.board.clone()],
+        Some(boards) => boards
+            .as_array()
+            .ok_or("'_boards' must be an array")?
+            .iter()
+            .map(|board| parse_cards(board.as_str().ok_or("boards must be strings")?))
+            .collect::<Result<Vec<_>, String>>()?,
+    };
+    if boards.len() > 1 && (boards[0] != hand.board || boards.iter().any(|board| board.len() != 5)) {
+        return Err("'_boards' must be whole boards, the first the one dealt".to_string());
+    }
+    let mut runs: Vec<BestHands> = vec![vec![None; n]; boards.len()];
+    let mut lows: Vec<BestLows> = if variant.is_hi_lo() { vec![vec![None; n]; boards.len()] } else { Vec::new() };
+    // a badugi is not five cards and is ordered on its own
+    let mut badugis: Vec<Vec<Option<Vec<Card>>>> = vec![vec![None; n]; boards.len()];
+    let mut showdowns = vec![Vec::new(); boards.len()];
+    if live.iter().filter(|&&l| l).count() > 1 {
+        if !stud && !draws && boards[0].len() != 5 {
+            return Err("the hand reaches showdown without a full board".to_string());
+        }
+        if shown.is_empty() {
+            shown = (0..n).filter(|&p| live[p]).collect();
+            shown.sort_by_key(|&p| hand.seats[p].seat);
+        }
+        for p in shown.into_iter().filter(|&p| live[p]) {
+            if held[p].len() != variant.hole_cards() {
+                return Err(format!("{} reaches showdown without known hole cards", hand.seats[p].name));
+            }
+            for (run, board) in boards.iter().enumerate() {
+                let Some(best) = variant.best_hand(&held[p], board) else {
+                    return Err(format!("{} cannot make a hand", hand.seats[p].name));
+                };
+                let low = variant.best_low(&held[p], board);
+                showdowns[run].push(ShowdownRecord { player_id: hand.seats[p].player_id, cards: best.cards().to_vec(), hand: best.hand(), low });
+                match best {
+                    BestHand::Five(cards, rank) => runs[run][p] = Some((cards, rank)),
+                    BestHand::Badugi(cards) => badugis[run][p] = Some(cards),
+                }
+                if let Some(lows) = lows.get_mut(run) {
+                    lows[p] = low;
+                }
+            }
+        }
+    }
+    let ranking = variant.ranking();
+    let mut pots = if variant == Variant::Badugi {
+        Game::split_pots(&total_bets, &live, &badugis, &lows, |pro, opp| compare_badugi(pro, opp), hand.rake)
+    } else {
+        Game::split_pots(&total_bets, &live, &runs, &lows, |pro, opp| HandComparator::compare_ranked(pro, opp, ranking), hand.rake)
+    }.map_err(|err| err.to_string())?;
+    for pot in pots.iter_mut().flatten() {
+        for (p, _) in pot.winners.iter_mut() {
+            *p = hand.seats[*p].player_id;
+        }
+    }
+    hand.pots = combine_runs(&pots);
+    hand.showdown = showdowns[0].clone();
+    if boards.len() > 1 {
+        hand.runs = boards
+            .into_iter()
+            .zip(showdowns)
+            .zip(pots)
+            .map(|((board, showdown), pots)| RunRecord { board, showdown, pots })
+            .collect();
+    }
+
+    // HandRecord lists seats in table order, PHH by position
+    hand.seats.sort_by_key(|s| s.seat);
+    Ok(hand)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Ante, Straddle};
+    use crate::history::PotRecord;
+    use crate::rake::Rake;
+
+    #[test]
+    fn test_rejects_unsupported() {
+        let two_straddles = "variant = 'NT'\nantes = [0, 0, 0, 0]\nblinds_or_straddles = [1, 2, 4, 8]\nmin_bet = 2\n\
+                             starting_stacks = [100, 100, 100, 100]\nactions = []\n";
+        assert_eq!(parse_hand(two_straddles).unwrap_err(), "only one straddle is supported");
+        assert!(parse_hand("variant = 'N2L1D'\n").unwrap_err().contains("unsupported variant"));
+        let bad_action = "variant = 'NT'\nantes = [0, 0]\nblinds_or_straddles = [1, 2]\nmin_bet = 2\n\
+                          starting_stacks = [100, 100]\nactions = ['p3 f']\n";
+        assert!(parse_hand(bad_action).unwrap_err().contains("invalid player"));
+        // seats count from one and fit the table
+        let seated = |seats: &str| format!("variant = 'NT'\nantes = [0, 0]\nblinds_or_straddles = [1, 2]\nmin_bet = 2\n\
+                                           starting_stacks = [100, 100]\nseats = {}\nseat_count = 6\nactions = []\n", seats);
+        assert!(parse_hands(&format!("[1]\n{}", seated("[0, 2]"))).unwrap_err().contains("seat 0"));
+        assert!(parse_hand(&seated("[2, 7]")).unwrap_err().contains("seat 7"));
+        assert!(!parse_hand(&seated("[2, 6]")).unwrap_err().contains("seat"));
+    }
+
+    #[test]
+    fn test_parse_antes() {
+        // the big blind covers their blind before the ante, so is all in
+        let text = "variant = 'NT'\n\
+                    ante_trimming_status = true\n\
+                    antes = [500, 500, 500]\n\
+                    blinds_or_straddles = [1000, 2000, 0]\n\
+                    min_bet = 2000\n\
+                    starting_stacks = [1125600, 2300, 553500]\n\
+                    actions = ['d dh p1 ????', 'd dh p2 AhAd', 'd dh p3 KhKd', 'p3 cbr 4000', 'p1 f',\n\
+                               'd db 2c7d9h', 'd db Js', 'd db 3s']\n";
+        let hand = parse_hand(text).unwrap();
+        assert_eq!(hand.ante, 500);
+        assert_eq!(hand.actions.iter().filter(|a| a.kind == ActionKind::Ante).map(|a| a.amount).collect::<Vec<_>>(), [500, 300, 500]);
+        assert_eq!(hand.actions[4], ActionRecord {
+            street: BettingRoundName::Preflop,
+            player_id: 1,
+            kind: ActionKind::BigBlind,
+            amount: 2000,
+            to: 2000,
+            all_in: true,
+        });
+        // antes count towards the pots, so the one the big blind could not
+        // cover is a side pot
+        assert_eq!(hand.uncalled, Some((2, 2000)));
+        assert_eq!(hand.pots, vec![
+            PotRecord { amount: 6100, rake: 0, winners: vec![(1, 6100)] },
+            PotRecord { amount: 200, rake: 0, winners: vec![(2, 200)] },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_hand() {
+        let text = "variant = 'NT'\n\
+                    antes = [0, 0, 0]\n\
+                    blinds_or_straddles = [1, 2, 0]\n\
+                    min_bet = 2\n\
+                    starting_stacks = [100, 50, 200]\n\
+                    actions = [\n\
+                      'd dh p1 AhAd', 'd dh p2 KhKd', 'd dh p3 QhQd',\n\
+                      'p3 cbr 6', 'p1 cc', 'p2 cbr 50',\n\
+                      'p3 cbr 200', 'p1 cc',\n\
+                      'd db 2c7d9h', 'd db Js', 'd db 3s',\n\
+                      'p1 sm AhAd', 'p2 sm KhKd', 'p3 sm QhQd',\n\
+                    ]\n\
+                    players = ['Alice', 'Bob', 'Carol']\n";
+        let hand = parse_hand(text).unwrap();
+        assert_eq!(hand.button_seat, 3);
+        assert_eq!(hand.seats[1].name, "Bob");
+        assert_eq!(hand.seats[0].cards, parse_cards("AhAd").unwrap());
+        assert_eq!(hand.board.len(), 5);
+        assert_eq!(hand.actions[0].kind, ActionKind::SmallBlind);
+        assert_eq!(hand.actions[3].kind, ActionKind::Call);
+        assert_eq!(hand.actions[4], ActionRecord {
+            street: BettingRoundName::Preflop,
+            player_id: 1,
+            kind: ActionKind::Raise,
+            amount: 48,
+            to: 50,
+            all_in: true,
+        });
+        // Alice can only call 94 more of the 200
+        assert!(hand.actions[6].all_in);
+        assert_eq!(hand.uncalled, Some((2, 100)));
+        assert_eq!(hand.pots, vec![
+            PotRecord { amount: 150, rake: 0, winners: vec![(0, 150)] },
+            PotRecord { amount: 100, rake: 0, winners: vec![(0, 100)] },
+        ]);
+        assert_eq!(hand.finishing_stack(0), 250);
+        assert_eq!(hand.finishing_stack(2), 100);
+        assert_eq!(hand.showdown.len(), 3);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for (ante, straddle) in [(Ante::None, Straddle::None), (Ante::Everyone(3), Straddle::UnderTheGun), (Ante::BigBlind(2), Straddle::Button)] {
+            let mut game = Game::new(5, 200).unwrap();
+            game.set_table_name("Round Trip");
+            game.set_ante(ante);
+            game.set_straddle(straddle);
+            game.set_rake(Rake::percent(10.0));
+            for _ in 0..30 {
+                game.play_round().unwrap();
+                let Some(hand) = game.last_hand() else { continue };
+                let text = format_hand(hand);
+                assert_eq!(&parse_hand(&text).unwrap(), hand, "{}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_it_more_than_once() {
+        let mut game = Game::new(4, 100).unwrap();
+        game.set_verbose(false);
+        game.set_run_it(3);
+        for id in 0..4 {
+            game.set_strategy(id, Box::new(crate::strategy::Aggressive { pot_fraction: 100.0 }));
+        }
+        game.play_round().unwrap();
+        let hand = game.last_hand().unwrap();
+        assert_eq!(hand.runs.len(), 3);
+        let text = format_hand(hand);
+        assert!(text.contains("\n_boards = [\""));
+        assert_eq!(&parse_hand(&text).unwrap(), hand);
+        let other = text.replace(&format!("\"{}\"", cards_string(&hand.board)), "\"AcAdAhAs2c\"");
+        assert_eq!(parse_hand(&other).unwrap_err(), "'_boards' must be whole boards, the first the one dealt");
+    }
+
+    #[test]
+    fn test_writer_sections() {
+        let mut game = Game::new(3, 100).unwrap();
+        let mut writer = PhhWriter::new(Vec::new());
+        let mut hands = Vec::new();
+        for _ in 0..3 {
+            game.play_round().unwrap();
+            let hand = game.last_hand().unwrap().clone();
+            writer.write_hand(&hand).unwrap();
+            hands.push(hand);
+        }
+        let text = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(text.starts_with("[1]\nvariant = \"NT\"\n"));
+        assert_eq!(parse_hands(&text).unwrap(), hands);
+    }
+
+    #[test]
+    fn test_betting_structures() {
+        let mut game = Game::new(4, 200).unwrap();
+        game.set_verbose(false);
+        game.set_betting_structure(BettingStructure::FixedLimit { cap: DEFAULT_CAP }).unwrap();
+        for _ in 0..20 {
+            game.play_round().unwrap();
+            let Some(hand) = game.last_hand() else { continue };
+            let text = format_hand(hand);
+            assert!(text.contains("variant = \"FT\"\n") && text.contains("small_bet = 2\nbig_bet = 4\n"));
+            assert_eq!(&parse_hand(&text).unwrap(), hand, "{}", text);
+        }
+
+        game.set_betting_structure(BettingStructure::PotLimit).unwrap();
+        game.play_round().unwrap();
+        assert!(PhhWriter::new(Vec::new()).write_hand(game.last_hand().unwrap()).is_err());
+
+        // pot limit Omaha is PO, though PHH has no five or six card Omaha
+        game.set_variant(Variant::omaha()).unwrap();
+        for _ in 0..20 {
+            game.play_round().unwrap();
+            let hand = game.last_hand().unwrap();
+            let text = format_hand(hand);
+            assert!(text.starts_with("variant = \"PO\"\n"));
+            assert_eq!(&parse_hand(&text).unwrap(), hand, "{}", text);
+        }
+
+        // no limit short deck is NS, played with antes
+        let mut game = Game::new(4, 200).unwrap();
+        game.set_verbose(false);
+        game.set_variant(Variant::ShortDeck { trips_beat_straight: false }).unwrap();
+        for _ in 0..20 {
+            game.play_round().unwrap();
+            let Some(hand) = game.last_hand() else { continue };
+            let text = format_hand(hand);
+            // everyone antes in full until someone is short
+            assert!(text.starts_with("variant = \"NS\"\nantes = ["), "{}", text);
+            if hand.hand_id == 1 {
+                assert!(text.contains("antes = [2, 2, 2, 2]\n"), "{}", text);
+            }
+            assert_eq!(&parse_hand(&text).unwrap(), hand, "{}", text);
+        }
+
+        // and fixed limit Omaha eight or better is FO/8
+        let mut game = Game::new(4, 200).unwrap();
+        game.set_verbose(false);
+        game.set_variant(Variant::OmahaHiLo { hole_cards: 4 }).unwrap();
+        game.set_betting_structure(BettingStructure::FixedLimit { cap: DEFAULT_CAP }).unwrap();
+        for _ in 0..20 {
+            game.play_round().unwrap();
+            let Some(hand) = game.last_hand() else { continue };
+            let text = format_hand(hand);
+            assert!(text.starts_with("variant = \"FO/8\"\n"));
+            assert_eq!(&parse_hand(&text).unwrap(), hand, "{}", text);
+        }
+        // stud, razz and the draw games are only played fixed limit
+        for (variant, code) in [(Variant::Stud, "F7S"), (Variant::Razz, "FR"), (Variant::DeuceToSevenTripleDraw, "F2L3D"), (Variant::Badugi, "FB")] {
+            let mut game = Game::new(4, 200).unwrap();
+            game.set_verbose(false);
+            game.set_betting_structure(BettingStructure::FixedLimit { cap: DEFAULT_CAP }).unwrap();
+            game.set_variant(variant).unwrap();
+            if variant.is_stud() {
+                game.set_ante(Ante::Everyone(1));
+            }
+            for _ in 0..30 {
+                game.play_round().unwrap();
+                let Some(hand) = game.last_hand() else { continue };
+                let text = format_hand(hand);
+                assert!(text.starts_with(&format!("variant = \"{}\"\n", code)));
+                assert_eq!(&parse_hand(&text).unwrap(), hand, "{}", text);
+            }
+        }
+        let mut game = Game::new(4, 200).unwrap();
+        game.set_verbose(false);
+        game.set_variant(Variant::Omaha { hole_cards: 5 }).unwrap();
+        game.set_betting_structure(BettingStructure::PotLimit).unwrap();
+        game.play_round().unwrap();
+        let error = PhhWriter::new(Vec::new()).write_hand(game.last_hand().unwrap()).unwrap_err();
+        assert_eq!(error.to_string(), "PHH has no variant for pot-limit omaha5");
+    }
+}
@@ -1,11 +1,18 @@
 use crate::deck::{Card,Deck};
+use crate::error::PokerError;
+use crate::variant::Variant;
 use rand::Rng;
 
+// the most hole cards any variant deals a player
+pub const MAX_HOLE_CARDS: usize = 7;
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum PlayerState {
     Active, 
     Folded, 
-    AllIn
+    AllIn,
+    // keeps their seat between hands but is not dealt in
+    SittingOut,
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -14,7 +21,37 @@ pub enum Action {
     Call, 
     Check,
     Raise(u32),
-    AllIn(u32)
+    AllIn(u32),
+    // the cards thrown away in a draw, a bit for each place in the hand
+    // from the first
+    Draw(u8),
+}
+
+// Decides actions in place of a player's own random play. Whatever it returns
+// is carried out through Player::apply.
+pub trait Strategy {
+    fn decide(&mut self, player: &Player, pot: u32, board: &[Card], to_call: u32, action: &[Vec<Action>]) -> Action;
+
+    // Once the players are all in before the river, how many times this one
+    // agrees to run the rest of the board, up to the times the table offers.
+    // The board is run the fewest times anyone agrees to.
+    fn run_it(&mut self, _player: &Player, _board: &[Card], offered: u32) -> u32 {
+        offered
+    }
+
+    // Before each hand of a bounty tournament, the bounty on the head of
+    // everyone dealt in, by player id.
+    fn see_bounties(&mut self, _bounties: &[(usize, u32)]) {}
+
+    // After each deal in a stud game, the cards face up in front of everyone
+    // still in the hand, by player id.
+    fn see_up_cards(&mut self, _up_cards: &[(usize, Vec<Card>)]) {}
+
+    // In a draw game, the Action::Draw of the cards to throw away for new
+    // ones. Draws as strategy::standard_discards unless a strategy knows better.
+    fn draw(&mut self, player: &Player, variant: Variant, _action: &[Vec<Action>]) -> Action {
+        Action::Draw(crate::strategy::standard_discards(&player.hand, variant))
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -23,6 +60,8 @@ pub struct Player {
     pub name: String, 
     pub chips: u32,
     pub hand: Vec<Card>,
+    // the cards of the hand dealt face up for everyone to see, in stud
+    pub up_cards: Vec<Card>,
     pub state: PlayerState,
     pub bet: u32
 }
@@ -30,12 +69,20 @@ pub struct Player {
 impl Player {
 
     pub fn new(id: usize, name: String, chips: u32) -> Player {
-        Player { id, name, chips, hand: Vec::new(), state: PlayerState::Active , bet: 0}
+        Player { id, name, chips, hand: Vec::new(), up_cards: Vec::new(), state: PlayerState::Active , bet: 0}
     }
     
-    pub fn act(&mut self, pot: u32, board: &[Card], to_call: u32, action:&Vec<Vec<Action>>) -> Action {
+    // Plays at random and carries the action out.
+    pub fn act(&mut self, pot: u32, board: &[Card], to_call: u32, action: &[Vec<Action>]) -> Action {
+        let decided = self.random_action(pot, board, to_call, action);
+        self.apply(decided, to_call).unwrap_or_else(|_| self.fold())
+    }
+
+    // The engine's own random play for players without a strategy, decided
+    // but not yet carried out.
+    pub fn random_action(&self, pot: u32, _board: &[Card], to_call: u32, _action: &[Vec<Action>]) -> Action {
         
-        let mut rng = rand::thread_rng();
+        let mut rng = crate::rng::rng();
 
         // If there's something to call
         if to_call > 0 {
@@ -44,29 +91,29 @@ impl Player {
                 let choice = rng.gen_range(0..100);
                 if choice < 40 {
                     // 40% chance to call
-                    return self.call(to_call);
+                    return Action::Call;
                 } else if choice < 70 {
                     // 30% chance to raise
                     let max_raise = self.chips - to_call;
                     if max_raise > 0 {
                         let raise_amount = rng.gen_range(1..=max_raise.min(pot / 2).max(1));
-                        return self.raise(to_call, raise_amount);
+                        return Action::Raise(raise_amount);
                     } else {
-                        return self.call(to_call);
+                        return Action::Call;
                     }
                 } else if choice < 90 {
                     // 20% chance to fold
-                    return self.fold();
+                    return Action::Fold;
                 } else {
                     // 10% chance to go all-in
-                    return self.go_all_in();
+                    return Action::AllIn(self.chips);
                 }
             } else {
                 // Not enough chips to call, decide between all-in or fold
                 if rng.gen_bool(0.7) {
-                    return self.go_all_in();
+                    return Action::AllIn(self.chips);
                 } else {
-                    return self.fold();
+                    return Action::Fold;
                 }
             }
         }
@@ -80,10 +127,10 @@ impl Player {
             } else if choice < 90 {
                 // 30% chance to bet/raise
                 let raise_amount = rng.gen_range(1..=self.chips.min(pot / 2).max(1));
-                return self.raise(0, raise_amount);
+                return Action::Raise(raise_amount);
             } else {
                 // 10% chance to go all-in
-                return self.go_all_in();
+                return Action::AllIn(self.chips);
             }
         }
 
@@ -91,21 +138,70 @@ impl Player {
 
     }
     
+    // the engine's own random draw for players without a strategy
+    pub fn random_draw(&self) -> Action {
+        Action::Draw(crate::rng::rng().gen_range(0..1u32 << self.hand.len()) as u8)
+    }
+
+    // Throws away the cards of a draw and returns them, refusing anything
+    // but a draw of cards the player holds.
+    pub fn discard(&mut self, action: Action) -> Result<Vec<Card>, PokerError> {
+        match action {
+            Action::Draw(discards) if (discards as u32) >> self.hand.len() == 0 => {
+                let thrown: Vec<Card> = self.hand.iter().enumerate().filter(|(i, _)| discards & (1 << i) != 0).map(|(_, card)| *card).collect();
+                self.hand.retain(|card| !thrown.contains(card));
+                Ok(thrown)
+            },
+            action => Err(PokerError::InvalidAction { player_id: self.id, action, to_call: 0 }),
+        }
+    }
+
+    // Carries out an action decided elsewhere, e.g. by a Strategy. Anything
+    // that would take every chip left becomes an all in and calling nothing is
+    // a check; checking a bet or raising by nothing is refused.
+    pub fn apply(&mut self, action: Action, to_call: u32) -> Result<Action, PokerError> {
+        let applied = match action {
+            Action::Fold => self.fold(),
+            Action::Check | Action::Call if to_call == 0 => Action::Check,
+            Action::Call if to_call >= self.chips => self.go_all_in(),
+            Action::Call => self.call(to_call),
+            Action::Raise(raise) if raise > 0 && to_call.saturating_add(raise) >= self.chips => self.go_all_in(),
+            Action::Raise(raise) if raise > 0 => self.raise(to_call, raise),
+            Action::AllIn(_) if self.chips > 0 => self.go_all_in(),
+            action => return Err(PokerError::InvalidAction { player_id: self.id, action, to_call }),
+        };
+        Ok(applied)
+    }
+
     pub fn display(&self) {
         println!("{}: Stack: {}, Bet: {}, State: {:?}",self.name, self.chips, self.bet, self.state);
-        Deck::print_cards(&[self.hand[0],self.hand[1]]);
+        Deck::print_cards(&self.hand);
     }
 
     pub fn deal_chips(&mut self, chips: u32) {
-        println!("{} got {} chips",self.name, chips);
         self.chips += chips; 
     }
 
-    pub fn deal_card(&mut self, card: Card) {
-        assert!(self.hand.len()<2);
+    pub fn deal_card(&mut self, card: Card) -> Result<(), PokerError> {
+        if self.hand.len() >= MAX_HOLE_CARDS {
+            return Err(PokerError::HandFull { player_id: self.id });
+        }
         self.hand.push(card); 
+        Ok(())
+    }
+
+    pub fn deal_card_up(&mut self, card: Card) -> Result<(), PokerError> {
+        self.deal_card(card)?;
+        self.up_cards.push(card);
+        Ok(())
+    }
+
+    // the cards only the player sees
+    pub fn down_cards(&self) -> Vec<Card> {
+        self.hand.iter().copied().filter(|card| !self.up_cards.contains(card)).collect()
     }
 
+    #[allow(clippy::needless_return)]
     pub fn go_all_in(&mut self) -> Action {
         let chips = self.chips; 
         self.chips = 0; 
@@ -114,17 +210,24 @@ impl Player {
         return Action::AllIn(chips);
     }
 
+    #[allow(clippy::needless_return)]
     fn fold(&mut self) -> Action {
         self.state = PlayerState::Folded;
         return Action::Fold;
     }
 
+    #[allow(clippy::needless_return)]
     fn raise(&mut self, call_amount: u32, raise_amount: u32) -> Action {
         self.bet += raise_amount + call_amount;
         self.chips -= raise_amount + call_amount;
+        // a raise of everything left is still all in
+        if self.chips == 0 {
+            self.state = PlayerState::AllIn;
+        }
         return Action::Raise(raise_amount);
     }
 
+    #[allow(clippy::needless_return)]
     fn call(&mut self, call_amount: u32) -> Action {
         self.bet += call_amount;
         self.chips -= call_amount;
@@ -139,9 +242,21 @@ impl Player {
         self.bet = blind;
         self.chips -= blind; 
     }
+
+    // Antes are dead money: they come out of the stack but do not count
+    // towards the bet. Returns what was posted, which is less when short.
+    pub fn post_ante(&mut self, ante: u32) -> u32 {
+        let posted = ante.min(self.chips);
+        self.chips -= posted;
+        if self.chips == 0 {
+            self.state = PlayerState::AllIn;
+        }
+        posted
+    }
     
     pub fn reset(&mut self) {
         self.hand = Vec::new(); 
+        self.up_cards = Vec::new();
         self.state = PlayerState::Active; 
         self.bet = 0;
     }
@@ -165,22 +280,41 @@ mod tests {
         let mut player = Player::new(0,"Bob".to_string(), 500);
         let mut d = Deck::new(); 
         let card = d.deal().unwrap(); 
-        player.deal_card(card.clone());
+        player.deal_card(card).unwrap();
         assert_eq!(player.hand.len(), 1);
         assert_eq!(player.hand[0], card);
+        let up = d.deal().unwrap();
+        player.deal_card_up(up).unwrap();
+        assert_eq!((player.up_cards.clone(), player.down_cards()), (vec![up], vec![card]));
+        player.reset();
+        assert!(player.hand.is_empty() && player.up_cards.is_empty());
     }
 
     #[test]
-    #[should_panic]
-    fn test_player_deal_card_panic() {
+    fn test_player_discard() {
+        let mut player = Player::new(0,"Carol".to_string(), 500);
+        let cards: Vec<Card> = ["2c", "3d", "4h", "5s", "7c"].iter().map(|c| Card::from_code(c).unwrap()).collect();
+        for &card in &cards {
+            player.deal_card(card).unwrap();
+        }
+        // the second and last cards
+        assert_eq!(player.discard(Action::Draw(0b10010)), Ok(vec![cards[1], cards[4]]));
+        assert_eq!(player.hand, vec![cards[0], cards[2], cards[3]]);
+        assert!(player.discard(Action::Draw(0b1000)).is_err());
+        assert!(player.discard(Action::Check).is_err());
+        assert_eq!(player.discard(Action::Draw(0)), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_player_deal_card_hand_full() {
         let mut player = Player::new(0,"Charlie".to_string(), 300);
         let mut d = Deck::new();
-        let card1 = d.deal().unwrap();
-        let card2 = d.deal().unwrap();
-        let card3 = d.deal().unwrap();
-        player.deal_card(card1);
-        player.deal_card(card2);
-        player.deal_card(card3); // Should panic
+        let cards: Vec<Card> = (0..MAX_HOLE_CARDS).map(|_| d.deal().unwrap()).collect();
+        for &card in &cards {
+            player.deal_card(card).unwrap();
+        }
+        assert_eq!(player.deal_card(d.deal().unwrap()), Err(PokerError::HandFull { player_id: 0 }));
+        assert_eq!(player.hand, cards);
     }
 
     #[test]
@@ -192,6 +326,38 @@ mod tests {
         assert_eq!(player.state, PlayerState::AllIn);
     }
 
+    #[test]
+    fn test_player_raise_everything() {
+        let mut player = Player::new(0,"Gina".to_string(), 100);
+        assert_eq!(player.raise(20, 80), Action::Raise(80));
+        assert_eq!(player.chips, 0);
+        assert_eq!(player.bet, 100);
+        assert_eq!(player.state, PlayerState::AllIn);
+    }
+
+    #[test]
+    fn test_player_apply() {
+        let mut player = Player::new(0,"Hank".to_string(), 100);
+        assert_eq!(player.apply(Action::Check, 10), Err(PokerError::InvalidAction { player_id: 0, action: Action::Check, to_call: 10 }));
+        assert_eq!(player.apply(Action::Raise(0), 10), Err(PokerError::InvalidAction { player_id: 0, action: Action::Raise(0), to_call: 10 }));
+        assert_eq!((player.chips, player.bet), (100, 0));
+        assert_eq!(player.apply(Action::Call, 0), Ok(Action::Check));
+        assert_eq!(player.apply(Action::Raise(20), 10), Ok(Action::Raise(20)));
+        assert_eq!(player.chips, 70);
+        assert_eq!(player.apply(Action::Call, 70), Ok(Action::AllIn(70)));
+        assert_eq!(player.state, PlayerState::AllIn);
+        assert_eq!(player.bet, 100);
+    }
+
+    #[test]
+    fn test_player_post_ante() {
+        let mut player = Player::new(0,"Eve".to_string(), 30);
+        assert_eq!(player.post_ante(25), 25);
+        assert_eq!((player.chips, player.bet, player.state), (5, 0, PlayerState::Active));
+        assert_eq!(player.post_ante(25), 5);
+        assert_eq!(player.state, PlayerState::AllIn);
+    }
+
     #[test]
     fn test_player_bet_blind() {
         let mut player = Player::new(0,"Eve".to_string(), 100);
@@ -0,0 +1,1169 @@
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use crate::betting::{BettingStructure, DEFAULT_CAP};
+use crate::deck::{Card, Rank};
+use crate::game::{BettingRoundName, Game};
+use crate::history::{civil_from_timestamp, combine_runs, run_share, timestamp_from_civil, ActionKind, ActionRecord, HandHistoryWriter, HandRecord, PotRecord, RunRecord, SeatRecord, ShowdownRecord};
+use crate::utils::Hand;
+use crate::variant::Variant;
+
+// Writes hands in the PokerStars text format, separated by blank lines
+// the way the site's own history files are. Like the site's, they are the
+// histories of one player, the hero: only their hole cards are dealt face
+// up, and everyone else's appear only if shown down. Without a hero nobody's
+// are.
+pub struct PokerStarsWriter<W: Write> {
+    out: W,
+    hero: Option<usize>,
+}
+
+impl<W: Write> PokerStarsWriter<W> {
+    pub fn new(out: W) -> PokerStarsWriter<W> {
+        PokerStarsWriter { out, hero: None }
+    }
+
+    // the histories of whoever sits in `seat`
+    pub fn with_hero(out: W, seat: usize) -> PokerStarsWriter<W> {
+        PokerStarsWriter { out, hero: Some(seat) }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> HandHistoryWriter for PokerStarsWriter<W> {
+    fn write_hand(&mut self, hand: &HandRecord) -> io::Result<()> {
+        if let Some(message) = self.unsupported(hand.variant, hand.betting) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+        write!(self.out, "{}\n\n\n", format_hand(hand, self.hero))?;
+        self.out.flush()
+    }
+
+    // the site's stud and draw histories are laid out street by street quite differently
+    fn unsupported(&self, variant: Variant, _betting: BettingStructure) -> Option<String> {
+        (variant.is_stud() || variant.draws() > 0).then(|| format!("PokerStars hand histories of {} are not supported", variant.name()))
+    }
+}
+
+// what the site calls each variant in the header, the longer names first so
+// they are found before the names they contain
+static VARIANT_NAMES: &[(&str, Variant)] = &[
+    ("6 Card Omaha Hi/Lo", Variant::OmahaHiLo { hole_cards: 6 }),
+    ("5 Card Omaha Hi/Lo", Variant::OmahaHiLo { hole_cards: 5 }),
+    ("Omaha Hi/Lo", Variant::OmahaHiLo { hole_cards: 4 }),
+    ("6 Card Omaha", Variant::Omaha { hole_cards: 6 }),
+    ("5 Card Omaha", Variant::Omaha { hole_cards: 5 }),
+    ("Omaha", Variant::Omaha { hole_cards: 4 }),
+    // the site's own short deck ranks trips above a straight
+    ("6+ Hold'em", Variant::ShortDeck { trips_beat_straight: true }),
+    ("Short Deck Hold'em", Variant::ShortDeck { trips_beat_straight: false }),
+    ("Hold'em", Variant::Holdem),
+];
+
+static STREET_HEADERS: &[(BettingRoundName, &str, usize)] = &[
+    (BettingRoundName::Flop, "FLOP", 3),
+    (BettingRoundName::Turn, "TURN", 4),
+    (BettingRoundName::River, "RIVER", 5),
+];
+
+// the hand as seen by the player in the hero seat, if any
+pub fn format_hand(hand: &HandRecord, hero: Option<usize>) -> String {
+    let mut out = String::new();
+
+    // limit games are named by their bet sizes rather than the blinds
+    let (game, stakes) = match hand.betting {
+        BettingStructure::NoLimit => ("No Limit", (hand.small_blind, hand.big_blind)),
+        BettingStructure::PotLimit => ("Pot Limit", (hand.small_blind, hand.big_blind)),
+        BettingStructure::FixedLimit { .. } => ("Limit", (hand.big_blind, 2 * hand.big_blind)),
+    };
+    let variant = VARIANT_NAMES.iter().find(|(_, variant)| *variant == hand.variant).map_or("Hold'em", |v| v.0);
+    writeln!(
+        out,
+        "PokerStars Hand #{}:  {} {} ({}/{}) - {} UTC",
+        hand.hand_id,
+        variant,
+        game,
+        stakes.0,
+        stakes.1,
+        format_timestamp(hand.timestamp)
+    ).unwrap();
+    writeln!(out, "Table '{}' {}-max Seat #{} is the button", hand.table_name, hand.max_seats, hand.button_seat).unwrap();
+    for seat in &hand.seats {
+        writeln!(out, "Seat {}: {} ({} in chips)", seat.seat, seat.name, seat.stack).unwrap();
+    }
+
+    let mut high = 0;
+    for action in hand.actions_on(BettingRoundName::Preflop) {
+        if action.kind.is_forced() {
+            write_action(&mut out, hand, action, &mut high);
+        }
+    }
+
+    writeln!(out, "*** HOLE CARDS ***").unwrap();
+    for seat in hand.seats.iter().filter(|seat| Some(seat.seat) == hero && !seat.cards.is_empty()) {
+        writeln!(out, "Dealt to {} [{}]", seat.name, format_cards(&seat.cards)).unwrap();
+    }
+    for action in hand.actions_on(BettingRoundName::Preflop) {
+        if !action.kind.is_forced() {
+            write_action(&mut out, hand, action, &mut high);
+        }
+    }
+
+    // a board run more than once has the streets dealt after everyone was
+    // all in once for each run, named by the run
+    let shared = hand.runs.iter().map(|run| run.board.iter().zip(&hand.board).take_while(|(a, b)| a == b).count()).min().unwrap_or(5);
+    let write_street = |out: &mut String, prefix: &str, name: &str, board: &[Card], n_cards: usize| {
+        if n_cards == 3 {
+            writeln!(out, "*** {}{} *** [{}]", prefix, name, format_cards(&board[..3])).unwrap();
+        } else {
+            writeln!(out, "*** {}{} *** [{}] [{}]", prefix, name, format_cards(&board[..n_cards - 1]), board[n_cards - 1].code()).unwrap();
+        }
+    };
+    for (street, name, n_cards) in STREET_HEADERS {
+        if hand.board.len() < *n_cards || *n_cards > shared {
+            break;
+        }
+        write_street(&mut out, "", name, &hand.board, *n_cards);
+        let mut high = 0;
+        for action in hand.actions_on(*street) {
+            write_action(&mut out, hand, action, &mut high);
+        }
+    }
+    for (run, record) in hand.runs.iter().enumerate() {
+        for (_, name, n_cards) in STREET_HEADERS.iter().filter(|(_, _, n_cards)| *n_cards > shared) {
+            write_street(&mut out, &format!("{} ", ordinal(run)), name, &record.board, *n_cards);
+        }
+    }
+
+    if let Some((player_id, amount)) = hand.uncalled {
+        writeln!(out, "Uncalled bet ({}) returned to {}", amount, hand.name(player_id)).unwrap();
+    }
+
+    let write_showdown = |out: &mut String, showdown: &[ShowdownRecord], pots: &[PotRecord]| {
+        for shown in showdown {
+            let seat = hand.seat(shown.player_id).unwrap();
+            writeln!(
+                out,
+                "{}: shows [{}] ({})",
+                seat.name,
+                format_cards(&seat.cards),
+                describe_shown(hand, shown)
+            ).unwrap();
+        }
+        for (idx, pot) in pots.iter().enumerate() {
+            for (player_id, amount) in &pot.winners {
+                writeln!(out, "{} collected {} from {}", hand.name(*player_id), amount, pot_name(hand, idx)).unwrap();
+            }
+        }
+    };
+    if hand.runs.is_empty() {
+        if !hand.showdown.is_empty() {
+            writeln!(out, "*** SHOW DOWN ***").unwrap();
+        }
+        write_showdown(&mut out, &hand.showdown, &hand.pots);
+    }
+    for (run, record) in hand.runs.iter().enumerate() {
+        writeln!(out, "*** {} SHOW DOWN ***", ordinal(run)).unwrap();
+        write_showdown(&mut out, &record.showdown, &record.pots);
+    }
+    if hand.showdown.is_empty() {
+        for pot in hand.pots.iter().take(1) {
+            for (player_id, _) in &pot.winners {
+                writeln!(out, "{}: doesn't show hand", hand.name(*player_id)).unwrap();
+            }
+        }
+    }
+
+    writeln!(out, "*** SUMMARY ***").unwrap();
+    if hand.pots.len() > 1 {
+        let mut line = format!("Total pot {} Main pot {}.", hand.total_pot(), hand.pots[0].amount);
+        for (idx, pot) in hand.pots.iter().enumerate().skip(1) {
+            write!(line, " Side pot-{} {}.", idx, pot.amount).unwrap();
+        }
+        writeln!(out, "{} | Rake {}", line, hand.rake).unwrap();
+    } else {
+        writeln!(out, "Total pot {} | Rake {}", hand.total_pot(), hand.rake).unwrap();
+    }
+    match hand.runs.len() {
+        0 => {},
+        2 => writeln!(out, "Hand was run twice").unwrap(),
+        n => writeln!(out, "Hand was run {} times", n).unwrap(),
+    }
+    for (run, record) in hand.runs.iter().enumerate() {
+        writeln!(out, "{} Board [{}]", ordinal(run), format_cards(&record.board)).unwrap();
+    }
+    if !hand.board.is_empty() && hand.runs.is_empty() {
+        writeln!(out, "Board [{}]", format_cards(&hand.board)).unwrap();
+    }
+
+    let blind_seat = |kind: ActionKind| {
+        hand.actions
+            .iter()
+            .find(|a| a.kind == kind)
+            .map(|a| a.player_id)
+    };
+    let small_blind = blind_seat(ActionKind::SmallBlind);
+    let big_blind = blind_seat(ActionKind::BigBlind);
+    for seat in &hand.seats {
+        let mut line = format!("Seat {}: {}", seat.seat, seat.name);
+        if seat.seat == hand.button_seat {
+            line.push_str(" (button)");
+        }
+        if Some(seat.player_id) == small_blind {
+            line.push_str(" (small blind)");
+        } else if Some(seat.player_id) == big_blind {
+            line.push_str(" (big blind)");
+        }
+
+        let won = hand.won_by(seat.player_id);
+        let shown = hand.showdown.iter().find(|s| s.player_id == seat.player_id);
+        match (hand.folded_on(seat.player_id), shown) {
+            (Some(BettingRoundName::Preflop), _) => line.push_str(" folded before Flop"),
+            (Some(street), _) => write!(line, " folded on the {:?}", street).unwrap(),
+            (None, Some(shown)) if won > 0 => write!(
+                line,
+                " showed [{}] and won ({}) with {}",
+                format_cards(&seat.cards),
+                won,
+                describe_shown(hand, shown)
+            ).unwrap(),
+            (None, Some(shown)) => write!(
+                line,
+                " showed [{}] and lost with {}",
+                format_cards(&seat.cards),
+                describe_shown(hand, shown)
+            ).unwrap(),
+            (None, None) if won > 0 => write!(line, " collected ({})", won).unwrap(),
+            (None, None) => line.push_str(" mucked"),
+        }
+        writeln!(out, "{}", line).unwrap();
+    }
+
+    out
+}
+
+fn write_action(out: &mut String, hand: &HandRecord, action: &ActionRecord, high: &mut u32) {
+    let name = hand.name(action.player_id);
+    let line = match action.kind {
+        ActionKind::Ante => format!("{}: posts the ante {}", name, action.amount),
+        ActionKind::SmallBlind => format!("{}: posts small blind {}", name, action.amount),
+        ActionKind::BigBlind => match dead_blind(hand, action.player_id) {
+            Some(dead) => format!("{}: posts small & big blinds {}", name, action.amount + dead.amount),
+            None => format!("{}: posts big blind {}", name, action.amount),
+        },
+        ActionKind::Straddle => format!("{}: posts straddle {}", name, action.amount),
+        ActionKind::BringIn => format!("{}: brings in for {}", name, action.amount),
+        // written with the big blind, or on its own as the dead money it is
+        ActionKind::DeadBlind if hand.actions.iter().any(|a| a.player_id == action.player_id && a.kind == ActionKind::BigBlind) => return,
+        ActionKind::DeadBlind => format!("{}: posts the ante {}", name, action.amount),
+        ActionKind::Fold => format!("{}: folds", name),
+        ActionKind::Check => format!("{}: checks", name),
+        ActionKind::Call => format!("{}: calls {}", name, action.amount),
+        ActionKind::Bet => format!("{}: bets {}", name, action.amount),
+        ActionKind::Raise => format!("{}: raises {} to {}", name, action.to - *high, action.to),
+    };
+    *high = (*high).max(action.to);
+    if action.all_in {
+        writeln!(out, "{} and is all-in", line).unwrap();
+    } else {
+        writeln!(out, "{}", line).unwrap();
+    }
+}
+
+// the missed small blind a player posted along with the big blind
+fn dead_blind(hand: &HandRecord, player_id: usize) -> Option<&ActionRecord> {
+    hand.actions.iter().find(|a| a.player_id == player_id && a.kind == ActionKind::DeadBlind)
+}
+
+static ORDINALS: &[&str] = &["FIRST", "SECOND", "THIRD", "FOURTH", "FIFTH", "SIXTH", "SEVENTH", "EIGHTH", "NINTH", "TENTH"];
+
+// how the site names each run of a board, by index from 0
+fn ordinal(run: usize) -> String {
+    match ORDINALS.get(run) {
+        Some(name) => name.to_string(),
+        None => format!("RUN {}", run + 1),
+    }
+}
+
+fn parse_ordinal(name: &str) -> Option<usize> {
+    match ORDINALS.iter().position(|&o| o == name) {
+        Some(run) => Some(run),
+        None => name.strip_prefix("RUN ")?.parse::<usize>().ok()?.checked_sub(1),
+    }
+}
+
+fn pot_name(hand: &HandRecord, idx: usize) -> String {
+    match (hand.pots.len(), idx) {
+        (1, _) => "pot".to_string(),
+        (_, 0) => "main pot".to_string(),
+        (_, idx) => format!("side pot-{}", idx),
+    }
+}
+
+pub fn format_cards(cards: &[Card]) -> String {
+    cards.iter().map(|c| c.code()).collect::<Vec<_>>().join(" ")
+}
+
+fn rank_name(rank: Rank) -> &'static str {
+    match rank {
+        Rank::Two => "Two",
+        Rank::Three => "Three",
+        Rank::Four => "Four",
+        Rank::Five => "Five",
+        Rank::Six => "Six",
+        Rank::Seven => "Seven",
+        Rank::Eight => "Eight",
+        Rank::Nine => "Nine",
+        Rank::Ten => "Ten",
+        Rank::Jack => "Jack",
+        Rank::Queen => "Queen",
+        Rank::King => "King",
+        Rank::Ace => "Ace",
+    }
+}
+
+fn rank_plural(rank: Rank) -> String {
+    match rank {
+        Rank::Six => "Sixes".to_string(),
+        rank => format!("{}s", rank_name(rank)),
+    }
+}
+
+// Hand descriptions as PokerStars words them, e.g. "two pair, Kings and Tens".
+// The cards are expected in the order best_hand returns them.
+pub fn describe_hand(cards: &[Card], hand: Hand) -> String {
+    match hand {
+        Hand::RoyalFlush => "a Royal Flush".to_string(),
+        Hand::StraightFlush => format!("a straight flush, {} to {}", rank_name(cards[4].0), rank_name(cards[0].0)),
+        Hand::Quads => format!("four of a kind, {}", rank_plural(cards[0].0)),
+        Hand::FullHouse => format!("a full house, {} full of {}", rank_plural(cards[0].0), rank_plural(cards[3].0)),
+        Hand::Flush => format!("a flush, {} high", rank_name(cards[0].0)),
+        Hand::Straight => format!("a straight, {} to {}", rank_name(cards[4].0), rank_name(cards[0].0)),
+        Hand::Trips => format!("three of a kind, {}", rank_plural(cards[0].0)),
+        Hand::TwoPair => format!("two pair, {} and {}", rank_plural(cards[0].0), rank_plural(cards[2].0)),
+        Hand::Pair => format!("a pair of {}", rank_plural(cards[0].0)),
+        Hand::HighCard => format!("high card {}", rank_name(cards[0].0)),
+    }
+}
+
+// a low as the site writes it, e.g. "8,5,4,2,A"
+pub fn describe_low(low: &[Card; 5]) -> String {
+    low.iter().map(|c| c.code()[..1].to_string()).collect::<Vec<_>>().join(",")
+}
+
+// what a hand shown at showdown made, both ways in a hi-lo game
+fn describe_shown(hand: &HandRecord, shown: &ShowdownRecord) -> String {
+    let high = describe_hand(&shown.cards, shown.hand);
+    match shown.low {
+        _ if !hand.variant.is_hi_lo() => high,
+        Some(low) => format!("HI: {}; LO: {}", high, describe_low(&low)),
+        None => format!("HI: {}", high),
+    }
+}
+
+// "YYYY/MM/DD HH:MM:SS" for a unix timestamp, in UTC
+fn format_timestamp(timestamp: u64) -> String {
+    let (year, month, day, hour, minute, second) = civil_from_timestamp(timestamp);
+    format!("{}/{:02}/{:02} {:02}:{:02}:{:02}", year, month, day, hour, minute, second)
+}
+
+// Reads a file of PokerStars hand histories, as the site or PokerStarsWriter
+// writes them. Cash game amounts like "$0.25" are read in cents.
+pub fn parse_hands(text: &str) -> Result<Vec<HandRecord>, String> {
+    let mut blocks: Vec<Vec<&str>> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_start_matches('\u{feff}').trim_end();
+        if line.starts_with("PokerStars ") && line.contains(" #") {
+            blocks.push(Vec::new());
+        }
+        if let Some(block) = blocks.last_mut() {
+            block.push(line);
+        }
+    }
+    blocks
+        .iter()
+        .enumerate()
+        .map(|(n, lines)| parse_lines(lines).map_err(|err| format!("hand {}: {}", n + 1, err)))
+        .collect()
+}
+
+pub fn parse_hand(text: &str) -> Result<HandRecord, String> {
+    let mut hands = parse_hands(text)?;
+    match hands.len() {
+        1 => Ok(hands.remove(0)),
+        n => Err(format!("expected one hand but found {}", n)),
+    }
+}
+
+// an amount of chips, or of cents when the hand is played for money
+fn parse_amount(text: &str, cents: bool) -> Result<u32, String> {
+    let number = text.trim().trim_start_matches(['$', '€', '£']).trim_end_matches(" USD").trim_end_matches(" EUR");
+    let amount = if cents {
+        number.parse::<f64>().ok().filter(|a| *a >= 0.0).map(|a| (a * 100.0).round() as u32)
+    } else {
+        number.parse::<u32>().ok()
+    };
+    amount.ok_or_else(|| format!("invalid amount '{}'", text.trim()))
+}
+
+fn parse_cards(text: &str) -> Result<Vec<Card>, String> {
+    text.split_whitespace()
+        .map(|code| Card::from_code(code).ok_or_else(|| format!("invalid card '{}'", code)))
+        .collect()
+}
+
+// every card in the [..] groups of a line, in order
+fn bracketed_cards(line: &str) -> Result<Vec<Card>, String> {
+    let mut cards = Vec::new();
+    for group in line.split('[').skip(1) {
+        let Some((inside, _)) = group.split_once(']') else { continue };
+        cards.extend(parse_cards(inside)?);
+    }
+    Ok(cards)
+}
+
+// "YYYY/MM/DD H:MM:SS" anywhere in the header; the time zone is not kept
+fn parse_timestamp(header: &str) -> Result<u64, String> {
+    let tokens: Vec<&str> = header.split_whitespace().collect();
+    for pair in tokens.windows(2) {
+        let date: Vec<&str> = pair[0].trim_start_matches('[').split('/').collect();
+        let time: Vec<&str> = pair[1].split(':').collect();
+        if date.len() != 3 || time.len() != 3 {
+            continue;
+        }
+        let (Ok(year), Ok(month), Ok(day)) = (date[0].parse(), date[1].parse(), date[2].parse()) else { continue };
+        let (Ok(hour), Ok(minute), Ok(second)) = (time[0].parse(), time[1].parse(), time[2].parse()) else { continue };
+        return Ok(timestamp_from_civil(year, month, day, hour, minute, second));
+    }
+    Err("no date in the header".to_string())
+}
+
+// the first "(small/big)" group in the header
+fn parse_blinds(header: &str, cents: bool) -> Result<(u32, u32), String> {
+    for group in header.split('(').skip(1) {
+        let Some((inside, _)) = group.split_once(')') else { continue };
+        let Some((small, big)) = inside.split_once('/') else { continue };
+        if let (Ok(small), Ok(big)) = (parse_amount(small, cents), parse_amount(big, cents)) {
+            return Ok((small, big));
+        }
+    }
+    Err("no blinds in the header".to_string())
+}
+
+fn pot_index(name: &str) -> Result<usize, String> {
+    match name {
+        "pot" | "main pot" => Ok(0),
+        "side pot" => Ok(1),
+        name => name
+            .strip_prefix("side pot-")
+            .and_then(|n| n.parse().ok())
+            .ok_or_else(|| format!("unknown pot '{}'", name)),
+    }
+}
+
+// the player whose name starts the text followed by the separator, and the text after it
+fn find_player<'a>(by_name: &[(String, usize)], text: &'a str, separator: &str) -> Option<(usize, &'a str)> {
+    by_name
+        .iter()
+        .find(|(name, _)| text.starts_with(name.as_str()) && text[name.len()..].starts_with(separator))
+        .map(|(name, id)| (*id, &text[name.len() + separator.len()..]))
+}
+
+fn set_cards(seats: &mut [SeatRecord], player_id: usize, cards: Vec<Card>) {
+    if let Some(seat) = seats.iter_mut().find(|s| s.player_id == player_id) {
+        seat.cards = cards;
+    }
+}
+
+fn parse_lines(lines: &[&str]) -> Result<HandRecord, String> {
+    let header = lines[0];
+    let (name, variant) = *VARIANT_NAMES
+        .iter()
+        .find(|(name, _)| header.contains(&format!(" {} ", name)))
+        .ok_or("unsupported game in the header")?;
+    // the site always caps limit games at four bets
+    let betting = if header.contains(&format!("{} No Limit", name)) {
+        BettingStructure::NoLimit
+    } else if header.contains(&format!("{} Pot Limit", name)) {
+        BettingStructure::PotLimit
+    } else if header.contains(&format!("{} Limit", name)) {
+        BettingStructure::FixedLimit { cap: DEFAULT_CAP }
+    } else {
+        return Err("unsupported betting structure in the header".to_string());
+    };
+    let hand_id = header
+        .split_once('#')
+        .and_then(|(_, rest)| rest.split(':').next())
+        .and_then(|id| id.trim().parse().ok())
+        .ok_or("no hand number in the header")?;
+    let cents = header.contains(['$', '€', '£']);
+    let (mut small_blind, big_blind) = match (betting, parse_blinds(header, cents)?) {
+        (BettingStructure::FixedLimit { .. }, (small_bet, _)) => (small_bet / 2, small_bet),
+        (_, blinds) => blinds,
+    };
+    let timestamp = parse_timestamp(header)?;
+
+    let table = lines.get(1).copied().unwrap_or("");
+    let (table_name, table_rest) = match (table.find('\''), table.rfind('\'')) {
+        (Some(open), Some(close)) if table.starts_with("Table ") && open < close => (&table[open + 1..close], &table[close + 1..]),
+        _ => return Err("missing the table line".to_string()),
+    };
+    let mut max_seats = 0;
+    let mut button_seat = 0;
+    for token in table_rest.split_whitespace() {
+        if let Some(n) = token.strip_suffix("-max") {
+            max_seats = n.parse().map_err(|_| format!("invalid table size '{}'", token))?;
+        } else if let Some(n) = token.strip_prefix('#') {
+            button_seat = n.parse().map_err(|_| format!("invalid button seat '{}'", token))?;
+        }
+    }
+
+    let mut seats: Vec<SeatRecord> = Vec::new();
+    let mut body = 2;
+    while let Some(line) = lines.get(body) {
+        let Some(rest) = line.strip_prefix("Seat ") else { break };
+        body += 1;
+        let (number, rest) = rest.split_once(": ").ok_or_else(|| format!("invalid seat line '{}'", line))?;
+        let seat: usize = number.parse().map_err(|_| format!("invalid seat line '{}'", line))?;
+        // seats count from one, up to the table size when the header gives it
+        if seat < 1 || (max_seats > 0 && seat > max_seats) {
+            return Err(format!("invalid seat line '{}'", line));
+        }
+        let chips = rest.rfind(" in chips").ok_or_else(|| format!("invalid seat line '{}'", line))?;
+        let open = rest[..chips].rfind(" (").ok_or_else(|| format!("invalid seat line '{}'", line))?;
+        if rest.ends_with("is sitting out") || rest.contains(") out of hand") {
+            continue;
+        }
+        seats.push(SeatRecord {
+            seat,
+            player_id: seat - 1,
+            name: rest[..open].to_string(),
+            stack: parse_amount(&rest[open + 2..chips], cents)?,
+            cards: Vec::new(),
+            up_cards: Vec::new(),
+        });
+    }
+    if seats.len() < 2 {
+        return Err("a hand needs at least two players".to_string());
+    }
+    max_seats = max_seats.max(seats.iter().map(|s| s.seat).max().unwrap_or(0));
+
+    // longest names first, so a name that starts with another one wins
+    let mut by_name: Vec<(String, usize)> = seats.iter().map(|s| (s.name.clone(), s.player_id)).collect();
+    by_name.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+    let mut street = BettingRoundName::Preflop;
+    let mut street_bets: Vec<(usize, u32)> = Vec::new();
+    let mut actions = Vec::new();
+    let mut board = Vec::new();
+    let mut uncalled = None;
+    let mut pots: Vec<PotRecord> = Vec::new();
+    let mut shown = Vec::new();
+    let mut at_showdown = false;
+    let mut in_summary = false;
+    let mut rake = 0;
+    let mut run_boards: Vec<Vec<Card>> = Vec::new();
+    let mut run_pots: Vec<Vec<PotRecord>> = Vec::new();
+    let mut current_run = 0;
+
+    for line in &lines[body..] {
+        if let Some(section) = line.strip_prefix("*** ") {
+            let name = section.split(" ***").next().unwrap_or("");
+            // the streets and showdown of one run of a board run more than once
+            let run = ["FLOP", "TURN", "RIVER", "SHOW DOWN"]
+                .iter()
+                .find_map(|&suffix| Some((parse_ordinal(name.strip_suffix(suffix)?.strip_suffix(' ')?)?, suffix)));
+            if let Some((run, suffix)) = run {
+                if run_boards.len() <= run {
+                    run_boards.resize(run + 1, Vec::new());
+                    run_pots.resize(run + 1, Vec::new());
+                }
+                if suffix == "SHOW DOWN" {
+                    at_showdown = true;
+                    current_run = run;
+                } else {
+                    run_boards[run] = bracketed_cards(line)?;
+                }
+                continue;
+            }
+            match name {
+                "HOLE CARDS" => {},
+                "FLOP" | "TURN" | "RIVER" => {
+                    street = match name {
+                        "FLOP" => BettingRoundName::Flop,
+                        "TURN" => BettingRoundName::Turn,
+                        _ => BettingRoundName::River,
+                    };
+                    street_bets.clear();
+                    board = bracketed_cards(line)?;
+                },
+                "SHOW DOWN" => at_showdown = true,
+                "SUMMARY" => in_summary = true,
+                _ => return Err(format!("unsupported section '{}'", name)),
+            }
+            continue;
+        }
+
+        if in_summary {
+            if let Some((_, taken)) = line.split_once("| Rake ") {
+                rake = parse_amount(taken.split_whitespace().next().unwrap_or(""), cents)?;
+            }
+            if let Some(rest) = line.strip_prefix("Seat ")
+                && let Some(player_id) = rest.split(':').next().and_then(|n| n.parse::<usize>().ok()).and_then(|n| n.checked_sub(1))
+                && (rest.contains(" showed [") || rest.contains(" mucked ["))
+                && seats.iter().any(|s| s.player_id == player_id && s.cards.is_empty()) {
+                set_cards(&mut seats, player_id, bracketed_cards(rest)?);
+            }
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("Dealt to ") {
+            if let Some((player_id, cards)) = find_player(&by_name, rest, " [") {
+                set_cards(&mut seats, player_id, parse_cards(cards.trim_end_matches(']'))?);
+            }
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("Uncalled bet (") {
+            let (amount, name) = rest.split_once(") returned to ").ok_or_else(|| format!("invalid line '{}'", line))?;
+            let (_, player_id) = by_name
+                .iter()
+                .find(|(n, _)| n == name)
+                .ok_or_else(|| format!("unknown player '{}'", name))?;
+            uncalled = Some((*player_id, parse_amount(amount, cents)?));
+            continue;
+        }
+        if let Some((player_id, rest)) = find_player(&by_name, line, " collected ") {
+            let (amount, pot) = rest.split_once(" from ").ok_or_else(|| format!("invalid line '{}'", line))?;
+            let amount = parse_amount(amount, cents)?;
+            let idx = pot_index(pot)?;
+            let pots = if run_boards.is_empty() { &mut pots } else { &mut run_pots[current_run] };
+            if pots.len() <= idx {
+                pots.resize(idx + 1, PotRecord { amount: 0, rake: 0, winners: Vec::new() });
+            }
+            pots[idx].amount += amount;
+            pots[idx].winners.push((player_id, amount));
+            continue;
+        }
+        let Some((player_id, rest)) = find_player(&by_name, line, ": ") else { continue };
+
+        let (rest, all_in) = match rest.strip_suffix(" and is all-in") {
+            Some(rest) => (rest, true),
+            None => (rest, false),
+        };
+        let mut before = street_bets.iter().find(|(id, _)| *id == player_id).map_or(0, |(_, bet)| *bet);
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        let (kind, amount) = match words.as_slice() {
+            ["posts", "small", "blind", amount] => (ActionKind::SmallBlind, parse_amount(amount, cents)?),
+            ["posts", "big", "blind", amount] => (ActionKind::BigBlind, parse_amount(amount, cents)?),
+            ["posts", "the", "ante", amount] => (ActionKind::Ante, parse_amount(amount, cents)?),
+            ["posts", "straddle", amount] => (ActionKind::Straddle, parse_amount(amount, cents)?),
+            // coming back from sitting out: the big blind live, the rest dead
+            ["posts", "small", "&", "big", "blinds", amount] => {
+                let total = parse_amount(amount, cents)?;
+                let live = total.min(big_blind);
+                match street_bets.iter_mut().find(|(id, _)| *id == player_id) {
+                    Some((_, bet)) => *bet = before + live,
+                    None => street_bets.push((player_id, before + live)),
+                }
+                let all_in_live = all_in && live == total;
+                actions.push(ActionRecord { street, player_id, kind: ActionKind::BigBlind, amount: live, to: before + live, all_in: all_in_live });
+                before += live;
+                if live == total {
+                    continue;
+                }
+                (ActionKind::DeadBlind, total - live)
+            },
+            ["posts", ..] => return Err(format!("unsupported post '{}'", line)),
+            ["folds", ..] => {
+                if rest.contains('[') {
+                    set_cards(&mut seats, player_id, bracketed_cards(rest)?);
+                }
+                (ActionKind::Fold, 0)
+            },
+            ["checks"] => (ActionKind::Check, 0),
+            ["calls", amount] => (ActionKind::Call, parse_amount(amount, cents)?),
+            ["bets", amount] => (ActionKind::Bet, parse_amount(amount, cents)?),
+            ["raises", _, "to", to] => (ActionKind::Raise, parse_amount(to, cents)?.saturating_sub(before)),
+            ["shows", ..] => {
+                let cards = bracketed_cards(rest.split(" (").next().unwrap_or(rest))?;
+                set_cards(&mut seats, player_id, cards.clone());
+                if at_showdown && !shown.iter().any(|(id, _)| *id == player_id) {
+                    shown.push((player_id, cards));
+                }
+                continue;
+            },
+            _ => continue,
+        };
+        // antes are dead money, not part of the bet
+        let to = if matches!(kind, ActionKind::Ante | ActionKind::DeadBlind) { before } else { before + amount };
+        match street_bets.iter_mut().find(|(id, _)| *id == player_id) {
+            Some((_, bet)) => *bet = to,
+            None => street_bets.push((player_id, to)),
+        }
+        actions.push(ActionRecord { street, player_id, kind, amount, to, all_in });
+    }
+
+    if pots.iter().chain(run_pots.iter().flatten()).any(|pot| pot.winners.is_empty()) {
+        return Err("a pot was never collected".to_string());
+    }
+    if run_boards.iter().any(|run| run.len() != 5) {
+        return Err("a run of the board is missing cards".to_string());
+    }
+    // the summary only gives the whole rake, so each pot's part of it is
+    // worked out from the bets the same way Game takes it
+    let bets: Vec<u32> = seats
+        .iter()
+        .map(|seat| {
+            let put_in: u32 = actions.iter().filter(|a| a.player_id == seat.player_id).map(|a| a.amount).sum();
+            put_in - uncalled.filter(|u| u.0 == seat.player_id).map_or(0, |u| u.1)
+        })
+        .collect();
+    let live: Vec<bool> = seats
+        .iter()
+        .map(|seat| !actions.iter().any(|a| a.player_id == seat.player_id && a.kind == ActionKind::Fold))
+        .collect();
+    let n_runs = run_pots.len() as u32;
+    for (idx, (_, _, pot_rake)) in Game::contested_pots(&bets, &live, rake).into_iter().enumerate() {
+        if let Some(pot) = pots.get_mut(idx) {
+            pot.rake = pot_rake;
+        }
+        for (run, pots) in run_pots.iter_mut().enumerate() {
+            if let Some(pot) = pots.get_mut(idx) {
+                pot.rake = run_share(pot_rake, n_runs, run);
+            }
+        }
+    }
+    let showdown_on = |board: &[Card]| -> Result<Vec<ShowdownRecord>, String> {
+        if board.len() < 5 {
+            return Ok(Vec::new());
+        }
+        shown
+            .iter()
+            .map(|(player_id, cards)| {
+                let best = variant.best_hand(cards, board).ok_or_else(|| {
+                    let name = seats.iter().find(|s| s.player_id == *player_id).map_or("", |s| s.name.as_str());
+                    format!("{} shows too few cards to make a hand", name)
+                })?;
+                Ok(ShowdownRecord { player_id: *player_id, cards: best.cards().to_vec(), hand: best.hand(), low: variant.best_low(cards, board) })
+            })
+            .collect()
+    };
+    let runs: Vec<RunRecord> = run_boards
+        .into_iter()
+        .zip(run_pots)
+        .map(|(board, pots)| Ok(RunRecord { showdown: showdown_on(&board)?, board, pots }))
+        .collect::<Result<_, String>>()?;
+    if let Some(first) = runs.first() {
+        board = first.board.clone();
+        pots = combine_runs(&runs.iter().map(|run| run.pots.clone()).collect::<Vec<_>>());
+    }
+    let showdown = showdown_on(&board)?;
+
+    // a limit small blind is not always half the big blind, e.g. 1 of 3
+    if let BettingStructure::FixedLimit { .. } = betting
+        && let Some(posted) = actions.iter().find(|a| a.kind == ActionKind::SmallBlind && !a.all_in) {
+        small_blind = posted.amount;
+    }
+
+    Ok(HandRecord {
+        hand_id,
+        table_name: table_name.to_string(),
+        timestamp,
+        max_seats,
+        button_seat,
+        small_blind,
+        big_blind,
+        ante: actions.iter().filter(|a| a.kind == ActionKind::Ante).map(|a| a.amount).max().unwrap_or(0),
+        variant,
+        betting,
+        seats,
+        actions,
+        draws: Vec::new(),
+        board,
+        uncalled,
+        rake,
+        pots,
+        showdown,
+        runs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Suit;
+    use crate::game::{Ante, Game, Straddle};
+    use crate::history::{PotRecord, SeatRecord, ShowdownRecord};
+    use crate::rake::Rake;
+
+    fn cards(codes: &str) -> Vec<Card> {
+        codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect()
+    }
+
+    fn action(street: BettingRoundName, player_id: usize, kind: ActionKind, amount: u32, to: u32) -> ActionRecord {
+        ActionRecord { street, player_id, kind, amount, to, all_in: false }
+    }
+
+    // the hand as a history written for the hero records it, without
+    // anyone else's cards unless they were shown down
+    fn seen_by(hand: &HandRecord, hero: Option<usize>) -> HandRecord {
+        let mut seen = hand.clone();
+        let shown: Vec<usize> = hand.showdown.iter().chain(hand.runs.iter().flat_map(|run| &run.showdown)).map(|s| s.player_id).collect();
+        for seat in seen.seats.iter_mut().filter(|seat| Some(seat.seat) != hero && !shown.contains(&seat.player_id)) {
+            seat.cards.clear();
+        }
+        seen
+    }
+
+    fn sample_hand() -> HandRecord {
+        use ActionKind::*;
+        use BettingRoundName::*;
+        HandRecord {
+            hand_id: 42,
+            table_name: "Test".to_string(),
+            timestamp: 1_700_000_000,
+            max_seats: 3,
+            button_seat: 1,
+            small_blind: 1,
+            big_blind: 2,
+            ante: 0,
+            variant: Variant::Holdem,
+            betting: BettingStructure::NoLimit,
+            seats: vec![
+                SeatRecord { seat: 1, player_id: 0, name: "Alice".to_string(), stack: 100, cards: cards("Ah Kh"), up_cards: Vec::new() },
+                SeatRecord { seat: 2, player_id: 1, name: "Bob".to_string(), stack: 100, cards: cards("7c 2d"), up_cards: Vec::new() },
+                SeatRecord { seat: 3, player_id: 2, name: "Carol".to_string(), stack: 100, cards: cards("Qs Qd"), up_cards: Vec::new() },
+            ],
+            actions: vec![
+                action(Preflop, 1, SmallBlind, 1, 1),
+                action(Preflop, 2, BigBlind, 2, 2),
+                action(Preflop, 0, Raise, 6, 6),
+                action(Preflop, 1, Fold, 0, 1),
+                action(Preflop, 2, Call, 4, 6),
+                action(Flop, 2, Check, 0, 0),
+                action(Flop, 0, Bet, 10, 10),
+                action(Flop, 2, Call, 10, 10),
+                action(Turn, 2, Check, 0, 0),
+                action(Turn, 0, Bet, 20, 20),
+                action(Turn, 2, Fold, 0, 0),
+            ],
+            draws: Vec::new(),
+            board: cards("Ac 7h 2s Td"),
+            uncalled: Some((0, 20)),
+            rake: 0,
+            pots: vec![PotRecord { amount: 33, rake: 0, winners: vec![(0, 33)] }],
+            showdown: vec![],
+            runs: vec![],
+        }
+    }
+
+    #[test]
+    fn test_format_hand() {
+        let text = format_hand(&sample_hand(), Some(1));
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "PokerStars Hand #42:  Hold'em No Limit (1/2) - 2023/11/14 22:13:20 UTC");
+        assert_eq!(lines[1], "Table 'Test' 3-max Seat #1 is the button");
+        assert_eq!(lines[2], "Seat 1: Alice (100 in chips)");
+        assert_eq!(lines[5], "Bob: posts small blind 1");
+        assert_eq!(lines[6], "Carol: posts big blind 2");
+        assert_eq!(lines[7], "*** HOLE CARDS ***");
+        assert_eq!(lines[8], "Dealt to Alice [Ah Kh]");
+        assert_eq!(text.matches("Dealt to").count(), 1);
+        assert!(!format_hand(&sample_hand(), None).contains("Dealt to"));
+        assert_eq!(lines[9], "Alice: raises 4 to 6");
+        assert_eq!(lines[11], "Carol: calls 4");
+        assert_eq!(lines[12], "*** FLOP *** [Ac 7h 2s]");
+        assert_eq!(lines[16], "*** TURN *** [Ac 7h 2s] [Td]");
+        assert!(text.contains("Uncalled bet (20) returned to Alice\nAlice collected 33 from pot\nAlice: doesn't show hand\n"));
+        assert!(text.contains("Total pot 33 | Rake 0\nBoard [Ac 7h 2s Td]\n"));
+        assert!(text.contains("Seat 1: Alice (button) collected (33)\n"));
+        assert!(text.contains("Seat 2: Bob (small blind) folded before Flop\n"));
+        assert!(text.contains("Seat 3: Carol (big blind) folded on the Turn\n"));
+        assert!(!text.contains("RIVER"));
+    }
+
+    #[test]
+    fn test_format_showdown_and_side_pots() {
+        let mut hand = sample_hand();
+        hand.actions.truncate(8);
+        hand.board = cards("Ac 7h 2s Td 3c");
+        hand.uncalled = None;
+        hand.pots = vec![
+            PotRecord { amount: 20, rake: 0, winners: vec![(0, 20)] },
+            PotRecord { amount: 13, rake: 0, winners: vec![(0, 13)] },
+        ];
+        hand.showdown = vec![
+            ShowdownRecord {
+                player_id: 0,
+                cards: vec![Card(Rank::Ace, Suit::Hearts), Card(Rank::Ace, Suit::Clubs), Card(Rank::King, Suit::Hearts), Card(Rank::Ten, Suit::Diamonds), Card(Rank::Seven, Suit::Hearts)],
+                hand: Hand::Pair,
+                low: None,
+            },
+            ShowdownRecord {
+                player_id: 2,
+                cards: vec![Card(Rank::Queen, Suit::Spades), Card(Rank::Queen, Suit::Diamonds), Card(Rank::Ace, Suit::Clubs), Card(Rank::Ten, Suit::Diamonds), Card(Rank::Seven, Suit::Hearts)],
+                hand: Hand::Pair,
+                low: None,
+            },
+        ];
+        let text = format_hand(&hand, Some(1));
+        assert!(text.contains("*** RIVER *** [Ac 7h 2s Td] [3c]\n*** SHOW DOWN ***\nAlice: shows [Ah Kh] (a pair of Aces)\n"));
+        assert!(text.contains("Alice collected 20 from main pot\nAlice collected 13 from side pot-1\n"));
+        assert!(text.contains("Total pot 33 Main pot 20. Side pot-1 13. | Rake 0\n"));
+        assert!(text.contains("Seat 1: Alice (button) showed [Ah Kh] and won (33) with a pair of Aces\n"));
+        assert!(text.contains("Seat 3: Carol (big blind) showed [Qs Qd] and lost with a pair of Queens\n"));
+    }
+
+    #[test]
+    fn test_describe_hand() {
+        let straight = [
+            Card(Rank::Five, Suit::Hearts),
+            Card(Rank::Four, Suit::Spades),
+            Card(Rank::Three, Suit::Diamonds),
+            Card(Rank::Two, Suit::Spades),
+            Card(Rank::Ace, Suit::Spades),
+        ];
+        assert_eq!(describe_hand(&straight, Hand::Straight), "a straight, Ace to Five");
+        let full_house = [
+            Card(Rank::Six, Suit::Hearts),
+            Card(Rank::Six, Suit::Spades),
+            Card(Rank::Six, Suit::Diamonds),
+            Card(Rank::Two, Suit::Spades),
+            Card(Rank::Two, Suit::Hearts),
+        ];
+        assert_eq!(describe_hand(&full_house, Hand::FullHouse), "a full house, Sixes full of Twos");
+    }
+
+    #[test]
+    fn test_write_game_hands() {
+        let mut game = Game::new(4, 500).unwrap();
+        game.play_round().unwrap();
+        let hand = game.last_hand().unwrap().clone();
+        let mut writer = PokerStarsWriter::new(Vec::new());
+        writer.write_hand(&hand).unwrap();
+        let text = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(text.starts_with("PokerStars Hand #1:"));
+        assert!(text.contains("*** SUMMARY ***"));
+        assert_eq!(hand.seats.iter().map(|s| s.stack).sum::<u32>(), 2000);
+        assert_eq!(hand.total_pot() + hand.uncalled.map_or(0, |u| u.1), hand.actions.iter().map(|a| a.amount).sum::<u32>());
+
+        // stud histories are laid out differently and not written
+        let mut game = Game::new(4, 500).unwrap();
+        game.set_variant(Variant::Razz).unwrap();
+        game.play_round().unwrap();
+        let err = PokerStarsWriter::new(Vec::new()).write_hand(game.last_hand().unwrap()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        assert_eq!(parse_hand(&format_hand(&sample_hand(), Some(1))).unwrap(), seen_by(&sample_hand(), Some(1)));
+
+        let mut game = Game::new(6, 300).unwrap();
+        game.set_rake(Rake { cap: Some(3), ..Rake::percent(5.0) });
+        let mut writer = PokerStarsWriter::with_hero(Vec::new(), 1);
+        let mut hands = Vec::new();
+        for _ in 0..30 {
+            game.play_round().unwrap();
+            if let Some(hand) = game.last_hand() {
+                writer.write_hand(hand).unwrap();
+                hands.push(hand.clone());
+            }
+        }
+        let text = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(parse_hands(&text).unwrap(), hands.iter().map(|hand| seen_by(hand, Some(1))).collect::<Vec<_>>());
+        assert!(hands.iter().any(|hand| hand.rake > 0));
+        for hand in &hands {
+            crate::replay::verify(hand).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_omaha() {
+        for (variant, header) in [(Variant::omaha(), "Omaha Pot Limit (1/2)"), (Variant::Omaha { hole_cards: 6 }, "6 Card Omaha Pot Limit (1/2)"), (Variant::OmahaHiLo { hole_cards: 4 }, "Omaha Hi/Lo Pot Limit (1/2)"), (Variant::ShortDeck { trips_beat_straight: true }, "6+ Hold'em Pot Limit (1/2)")] {
+            let mut game = Game::new(6, 200).unwrap();
+            game.set_verbose(false);
+            game.set_variant(variant).unwrap();
+            game.set_betting_structure(BettingStructure::PotLimit).unwrap();
+            for id in 0..6 {
+                game.set_strategy(id, Box::new(crate::strategy::CallingStation));
+            }
+            game.play_round().unwrap();
+            let hand = game.last_hand().unwrap();
+            let text = format_hand(hand, Some(1));
+            assert!(text.lines().next().unwrap().contains(header), "{}", text);
+            assert_eq!(parse_hand(&text).unwrap(), seen_by(hand, Some(1)));
+            crate::replay::verify(hand).unwrap();
+            // an Omaha hand shown with one card cannot be ranked
+            if variant == Variant::omaha() {
+                let shows = text.find(": shows [").unwrap() + ": shows [".len();
+                let end = shows + text[shows..].find(']').unwrap();
+                let one_card = format!("{}{}{}", &text[..shows], &text[shows..shows + 2], &text[end..]);
+                assert!(parse_hand(&one_card).unwrap_err().ends_with("shows too few cards to make a hand"), "{}", one_card);
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_it_twice() {
+        let mut game = Game::new(3, 100).unwrap();
+        game.set_verbose(false);
+        game.set_run_it(2);
+        for id in 0..3 {
+            game.set_strategy(id, Box::new(crate::strategy::Aggressive { pot_fraction: 100.0 }));
+        }
+        game.play_round().unwrap();
+        let hand = game.last_hand().unwrap();
+        assert_eq!(hand.runs.len(), 2);
+        let text = format_hand(hand, Some(1));
+        assert!(text.contains("*** FIRST FLOP *** ["));
+        assert!(text.contains("*** SECOND RIVER *** ["));
+        assert!(text.contains("*** SECOND SHOW DOWN ***\n"));
+        assert!(text.contains("Hand was run twice\nFIRST Board ["));
+        assert_eq!(parse_hand(&text).unwrap(), seen_by(hand, Some(1)));
+        crate::replay::verify(hand).unwrap();
+
+        let three = text.replace("run twice", "run 3 times");
+        assert_eq!(parse_hand(&three).unwrap().runs.len(), 2);
+        let missing = text.lines().filter(|line| !line.starts_with("*** SECOND RIVER")).collect::<Vec<_>>().join("\n");
+        assert_eq!(parse_hand(&missing).unwrap_err(), "hand 1: a run of the board is missing cards");
+    }
+
+    #[test]
+    fn test_parse_missed_blinds() {
+        let mut game = Game::new(4, 300).unwrap();
+        game.set_verbose(false);
+        for id in 0..4 {
+            game.set_strategy(id, Box::new(crate::strategy::CallingStation));
+        }
+        game.play_round().unwrap();
+        game.sit_out(4).unwrap();
+        game.play_round().unwrap();
+        game.play_round().unwrap();
+        game.sit_in(4).unwrap();
+        game.play_round().unwrap();
+        let hand = game.last_hand().unwrap();
+        let text = format_hand(hand, Some(1));
+        assert!(text.contains("Player 4: posts small & big blinds 3"));
+        assert_eq!(parse_hand(&text).unwrap(), seen_by(hand, Some(1)));
+    }
+
+    #[test]
+    fn test_parse_antes_and_straddles() {
+        let mut game = Game::new(6, 300).unwrap();
+        game.set_ante(Ante::Everyone(5));
+        game.set_straddle(Straddle::UnderTheGun);
+        let mut hands = Vec::new();
+        for _ in 0..20 {
+            game.play_round().unwrap();
+            hands.extend(game.last_hand().cloned());
+        }
+        let text: String = hands.iter().map(|hand| format_hand(hand, Some(1)) + "\n\n").collect();
+        assert!(text.contains(": posts the ante 5\n") && text.contains(": posts straddle 4\n"));
+        assert_eq!(parse_hands(&text).unwrap(), hands.iter().map(|hand| seen_by(hand, Some(1))).collect::<Vec<_>>());
+
+        let hand = &hands[0];
+        assert_eq!(hand.ante, 5);
+        assert_eq!(hand.actions[0], action(BettingRoundName::Preflop, 1, ActionKind::Ante, 5, 0));
+        assert_eq!(hand.actions[8], action(BettingRoundName::Preflop, 3, ActionKind::Straddle, 4, 4));
+    }
+
+    #[test]
+    fn test_parse_betting_structures() {
+        for (betting, header) in [
+            (BettingStructure::PotLimit, "Hold'em Pot Limit (1/2)"),
+            (BettingStructure::FixedLimit { cap: DEFAULT_CAP }, "Hold'em Limit (2/4)"),
+        ] {
+            let mut game = Game::new(6, 300).unwrap();
+            game.set_verbose(false);
+            game.set_betting_structure(betting).unwrap();
+            let mut hands = Vec::new();
+            for _ in 0..20 {
+                game.play_round().unwrap();
+                hands.extend(game.last_hand().cloned());
+            }
+            let text: String = hands.iter().map(|hand| format_hand(hand, Some(1)) + "\n\n").collect();
+            assert!(text.starts_with(&format!("PokerStars Hand #1:  {} - ", header)));
+            assert_eq!(parse_hands(&text).unwrap(), hands.iter().map(|hand| seen_by(hand, Some(1))).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_parse_site_hand() {
+        let text = "\u{feff}PokerStars Hand #250000000001:  Hold'em No Limit ($0.01/$0.02 USD) - 2024/03/01 12:30:05 ET [2024/03/01 17:30:05 ET]
+Table 'Alcyone II' 6-max Seat #2 is the button
+Seat 1: bob ($2 in chips)
+Seat 2: bobby ($1.50 in chips)
+Seat 4: carol ($2.13 in chips)
+Seat 6: dave ($3 in chips) is sitting out
+bob: posts small blind $0.01
+carol: posts big blind $0.02
+*** HOLE CARDS ***
+Dealt to bobby [Ah Kh]
+bobby: raises $0.04 to $0.06
+bob: folds
+carol: calls $0.04
+*** FLOP *** [Kd 7s 2c]
+carol: checks
+bobby: bets $1.44 and is all-in
+carol: calls $1.44
+*** TURN *** [Kd 7s 2c] [3h]
+*** RIVER *** [Kd 7s 2c 3h] [9d]
+*** SHOW DOWN ***
+carol: shows [Qs Qd] (a pair of Queens)
+bobby: shows [Ah Kh] (a pair of Kings)
+bobby collected $3.01 from pot
+*** SUMMARY ***
+Total pot $3.01 | Rake $0
+Board [Kd 7s 2c 3h 9d]
+Seat 1: bob (small blind) folded before Flop
+Seat 2: bobby (button) showed [Ah Kh] and won ($3.01) with a pair of Kings
+Seat 4: carol (big blind) showed [Qs Qd] and lost with a pair of Queens
+";
+        let hand = parse_hand(text).unwrap();
+        assert_eq!(hand.hand_id, 250000000001);
+        assert_eq!((hand.small_blind, hand.big_blind), (1, 2));
+        assert_eq!(hand.timestamp, timestamp_from_civil(2024, 3, 1, 12, 30, 5));
+        assert_eq!((hand.table_name.as_str(), hand.max_seats, hand.button_seat), ("Alcyone II", 6, 2));
+        assert_eq!(hand.seats.len(), 3);
+        assert_eq!(hand.seats[1].name, "bobby");
+        assert_eq!(hand.seats[1].stack, 150);
+        assert_eq!(hand.seats[2].cards, vec![Card::from_code("Qs").unwrap(), Card::from_code("Qd").unwrap()]);
+        assert_eq!(hand.actions[2], ActionRecord { street: BettingRoundName::Preflop, player_id: 1, kind: ActionKind::Raise, amount: 6, to: 6, all_in: false });
+        assert_eq!(hand.actions[6], ActionRecord { street: BettingRoundName::Flop, player_id: 1, kind: ActionKind::Bet, amount: 144, to: 144, all_in: true });
+        assert_eq!(hand.board.len(), 5);
+        assert_eq!(hand.pots, vec![PotRecord { amount: 301, rake: 0, winners: vec![(1, 301)] }]);
+        assert_eq!(hand.showdown.len(), 2);
+        assert_eq!(hand.showdown[1].hand, Hand::Pair);
+        assert_eq!(hand.finishing_stack(1), 301);
+    }
+
+    #[test]
+    fn test_parse_side_pot_rake() {
+        let text = include_str!("../corpus/pokerstars.txt")
+            .replace("Ben collected 140 from side pot-1", "Ben collected 111 from side pot-1")
+            .replace("Ann collected 150 from main pot", "Ann collected 118 from main pot")
+            .replace("Total pot 290 Main pot 150. Side pot-1 140. | Rake 0", "Total pot 290 Main pot 118. Side pot-1 111. | Rake 61");
+        let hand = &parse_hands(&text).unwrap()[1];
+        // shared the way Game shares it, by the size of each pot
+        assert_eq!(hand.pots, vec![
+            PotRecord { amount: 118, rake: 32, winners: vec![(0, 118)] },
+            PotRecord { amount: 111, rake: 29, winners: vec![(1, 111)] },
+        ]);
+        assert_eq!(hand.total_pot(), 290);
+        assert!(format_hand(hand, None).contains("Total pot 290 Main pot 118. Side pot-1 111. | Rake 61\n"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let hand = format_hand(&sample_hand(), Some(1));
+        assert_eq!(parse_hand(&hand.replace("Hold'em No Limit", "Courchevel Pot Limit")).unwrap_err(), "hand 1: unsupported game in the header");
+        assert_eq!(parse_hand(&hand.replace("Bob: posts small blind 1", "Bob: posts a bounty 1")).unwrap_err(), "hand 1: unsupported post 'Bob: posts a bounty 1'");
+        assert_eq!(parse_hand(&hand.replace("(100 in chips)", "(lots in chips)")).unwrap_err(), "hand 1: invalid amount 'lots'");
+        assert_eq!(parse_hand(&hand.replace("Seat 1:", "Seat 0:")).unwrap_err(), "hand 1: invalid seat line 'Seat 0: Alice (100 in chips)'");
+        assert!(parse_hand("").is_err());
+    }
+}
@@ -0,0 +1,80 @@
+// The house's cut of each hand: a percentage of the pot, in hundredths of a
+// percent so 5% is 500, rounded down and capped. The cap can depend on how
+// many players were dealt in, e.g. 1 heads up and 3 at a full table, and
+// under "no flop, no drop" a hand that ends before the flop is not raked;
+// stud's flop is fourth street and a draw game's is the first draw.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Rake {
+    pub basis_points: u32,
+    // the most taken from any hand
+    pub cap: Option<u32>,
+    // (players dealt in, cap) from the fewest players up; the last entry
+    // for no more players than were dealt in applies
+    pub caps_by_players: Vec<(usize, u32)>,
+    pub no_flop_no_drop: bool,
+}
+
+// How the rake of a hand is charged to the players in it: an equal share to
+// everyone dealt in, or shares weighted by what each put into the pot.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum RakeAttribution {
+    Dealt,
+    Contributed,
+}
+
+impl Rake {
+    // no rake at all
+    pub fn none() -> Rake {
+        Rake::default()
+    }
+
+    pub fn percent(percent: f64) -> Rake {
+        Rake { basis_points: (percent * 100.0).round() as u32, ..Rake::default() }
+    }
+
+    // the most this hand can be raked with so many players dealt in
+    pub fn cap_for(&self, n_players: usize) -> Option<u32> {
+        let by_players = self.caps_by_players.iter().rfind(|(players, _)| *players <= n_players).map(|c| c.1);
+        match (self.cap, by_players) {
+            (Some(cap), Some(by_players)) => Some(cap.min(by_players)),
+            (cap, by_players) => cap.or(by_players),
+        }
+    }
+
+    // What is taken from a pot of this size, everything anyone put in
+    // apart from an uncalled bet.
+    pub fn take(&self, pot: u32, n_players: usize, saw_flop: bool) -> u32 {
+        if self.no_flop_no_drop && !saw_flop {
+            return 0;
+        }
+        let rake = (pot as u64 * self.basis_points as u64 / 10_000) as u32;
+        self.cap_for(n_players).map_or(rake, |cap| rake.min(cap)).min(pot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take() {
+        assert_eq!(Rake::none().take(1000, 6, true), 0);
+        let rake = Rake::percent(5.0);
+        assert_eq!(rake.take(199, 6, false), 9);
+        assert_eq!(Rake { cap: Some(3), ..rake.clone() }.take(1000, 6, true), 3);
+        assert_eq!(Rake::percent(4.5).take(1000, 6, true), 45);
+        assert_eq!(Rake { no_flop_no_drop: true, ..rake.clone() }.take(1000, 6, false), 0);
+        assert_eq!(Rake { no_flop_no_drop: true, ..rake }.take(1000, 6, true), 50);
+    }
+
+    #[test]
+    fn test_caps_by_players() {
+        let rake = Rake { cap: Some(4), caps_by_players: vec![(2, 1), (3, 2), (5, 6)], ..Rake::percent(10.0) };
+        assert_eq!(rake.cap_for(2), Some(1));
+        assert_eq!(rake.cap_for(4), Some(2));
+        // the flat cap still holds with more players
+        assert_eq!(rake.cap_for(9), Some(4));
+        assert_eq!(rake.take(100, 3, true), 2);
+        assert_eq!(Rake { caps_by_players: vec![(3, 2)], ..Rake::percent(10.0) }.cap_for(2), None);
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::deck::Card;
+use crate::game::{Ante, Game, MissedBlinds, Straddle};
+use crate::history::{ActionKind, ActionRecord, HandRecord};
+use crate::player::{Action, Player, Strategy};
+use crate::rake::Rake;
+use crate::table::Table;
+use crate::variant::Variant;
+
+// Plays one player's recorded actions back in order, folding once they run
+// out, and in a draw game throws away the recorded cards, standing pat after.
+pub struct ReplayStrategy {
+    actions: VecDeque<ActionRecord>,
+    discards: VecDeque<Vec<Card>>,
+}
+
+impl ReplayStrategy {
+    pub fn new(actions: Vec<ActionRecord>) -> ReplayStrategy {
+        ReplayStrategy { actions: actions.into(), discards: VecDeque::new() }
+    }
+
+    // the cards thrown away in each draw, in order
+    pub fn with_discards(mut self, discards: Vec<Vec<Card>>) -> ReplayStrategy {
+        self.discards = discards.into();
+        self
+    }
+}
+
+impl Strategy for ReplayStrategy {
+    fn decide(&mut self, _player: &Player, _pot: u32, _board: &[Card], to_call: u32, _action: &[Vec<Action>]) -> Action {
+        let Some(recorded) = self.actions.pop_front() else { return Action::Fold };
+        match recorded.kind {
+            ActionKind::Fold | ActionKind::Ante | ActionKind::SmallBlind | ActionKind::BigBlind | ActionKind::Straddle | ActionKind::DeadBlind | ActionKind::BringIn => Action::Fold,
+            ActionKind::Check => Action::Check,
+            _ if recorded.all_in => Action::AllIn(recorded.amount),
+            ActionKind::Call => Action::Call,
+            ActionKind::Bet | ActionKind::Raise => Action::Raise(recorded.amount.saturating_sub(to_call)),
+        }
+    }
+
+    fn draw(&mut self, player: &Player, _variant: Variant, _action: &[Vec<Action>]) -> Action {
+        let discarded = self.discards.pop_front().unwrap_or_default();
+        Action::Draw(player.hand.iter().enumerate().filter(|(_, card)| discarded.contains(card)).fold(0, |discards, (i, _)| discards | 1 << i))
+    }
+}
+
+// Plays a recorded hand again through the engine, with the same seats, stacks,
+// cards and actions, and returns the hand as the engine recorded it.
+pub fn replay(hand: &HandRecord) -> Result<HandRecord, String> {
+    let mut seats: Vec<_> = hand.seats.iter().collect();
+    seats.sort_by_key(|s| s.seat);
+    let dealer = seats
+        .iter()
+        .position(|s| s.seat == hand.button_seat)
+        .ok_or("the button is on an empty seat")?;
+
+    // the engine posts the blinds from the two seats left of the button, or
+    // from the button and the other player heads up
+    let n_players = seats.len();
+    let small_blind = if n_players == 2 { dealer } else { (dealer + 1) % n_players };
+    let expected = [seats[small_blind].player_id, seats[(small_blind + 1) % n_players].player_id];
+    let posted = [ActionKind::SmallBlind, ActionKind::BigBlind]
+        .map(|kind| hand.actions.iter().find(|a| a.kind == kind).map(|a| a.player_id));
+    if posted.iter().zip(expected).any(|(posted, expected)| posted.is_some_and(|id| id != expected)) {
+        return Err(format!(
+            "the blinds were posted by {} and {} but the engine posts them from {} and {}",
+            posted[0].map_or("nobody", |id| hand.name(id)),
+            posted[1].map_or("nobody", |id| hand.name(id)),
+            hand.name(expected[0]),
+            hand.name(expected[1]),
+        ));
+    }
+
+    // whoever could reach a showdown needs known cards
+    let live: Vec<_> = seats.iter().filter(|s| hand.folded_on(s.player_id).is_none()).collect();
+    if live.len() > 1
+        && let Some(unknown) = live.iter().find(|s| s.cards.len() != hand.variant.hole_cards()) {
+        return Err(format!("the cards of {} are not known", unknown.name));
+    }
+
+    let mut table = Table::new(hand.max_seats.max(seats.last().map_or(0, |s| s.seat)));
+    for seat in &seats {
+        table.sit(seat.seat, Player::new(seat.player_id, seat.name.clone(), seat.stack)).map_err(|err| err.to_string())?;
+    }
+    let mut game = Game::with_table(table, hand.small_blind, hand.big_blind).map_err(|err| err.to_string())?;
+    game.set_table_name(&hand.table_name);
+    game.set_hand_number(hand.hand_id);
+    game.set_button(hand.button_seat);
+    game.set_variant(hand.variant).map_err(|err| err.to_string())?;
+    game.set_betting_structure(hand.betting).map_err(|err| err.to_string())?;
+    // the recorded rake, whatever it was worked out from
+    game.set_rake(Rake { basis_points: 10_000, cap: Some(hand.rake), ..Rake::none() });
+
+    // a single ante from the big blind is a big blind ante
+    let anted: Vec<usize> = hand.actions.iter().filter(|a| a.kind == ActionKind::Ante).map(|a| a.player_id).collect();
+    game.set_ante(match anted.as_slice() {
+        [] => Ante::None,
+        [id] if Some(*id) == posted[1] => Ante::BigBlind(hand.ante),
+        _ => Ante::Everyone(hand.ante),
+    });
+    let button = seats[dealer].player_id;
+    game.set_straddle(match hand.actions.iter().find(|a| a.kind == ActionKind::Straddle) {
+        None => Straddle::None,
+        Some(straddle) if straddle.player_id == button => Straddle::Button,
+        Some(_) => Straddle::UnderTheGun,
+    });
+    // anyone else posting a big blind was back from sitting out and owed it
+    for returning in hand.actions.iter().filter(|a| a.kind == ActionKind::BigBlind && Some(a.player_id) != posted[1]) {
+        let small_blind = hand.actions.iter().any(|a| a.player_id == returning.player_id && a.kind == ActionKind::DeadBlind);
+        game.set_missed_blinds(returning.player_id, MissedBlinds { small_blind, big_blind: true });
+    }
+
+    // stud hands folded early are dealt only some of their cards, and in a
+    // draw game the cards drawn follow the first hand
+    let drawn = |id: usize| hand.draws.iter().filter(move |d| d.player_id == id);
+    let hole_cards: HashMap<usize, Vec<Card>> = seats
+        .iter()
+        .filter(|s| !s.cards.is_empty())
+        .map(|s| (s.player_id, s.cards.iter().chain(drawn(s.player_id).flat_map(|d| &d.drawn)).copied().collect()))
+        .collect();
+    game.force_cards(hole_cards, hand.board.clone()).map_err(|err| err.to_string())?;
+    // everyone agrees to run it as many times as they did
+    if hand.runs.len() > 1 {
+        game.set_run_it(hand.runs.len() as u32);
+        game.force_runs(hand.runs[1..].iter().map(|run| run.board.clone()).collect()).map_err(|err| err.to_string())?;
+    }
+    for seat in &seats {
+        let actions = hand
+            .actions
+            .iter()
+            .filter(|a| a.player_id == seat.player_id && !a.kind.is_forced())
+            .cloned()
+            .collect();
+        let discards = drawn(seat.player_id).map(|d| d.discarded.clone()).collect();
+        game.set_strategy(seat.player_id, Box::new(ReplayStrategy::new(actions).with_discards(discards)));
+    }
+
+    game.play_round().map_err(|err| err.to_string())?;
+    game.last_hand().cloned().ok_or_else(|| "the engine did not play the hand".to_string())
+}
+
+// Replays a hand and checks the engine agrees with the record on every
+// action, the draws, the board, the uncalled bet, the pots and who won them,
+// and the stacks everyone finished with.
+pub fn verify(hand: &HandRecord) -> Result<(), String> {
+    let replayed = replay(hand)?;
+
+    for (idx, (recorded, played)) in hand.actions.iter().zip(replayed.actions.iter()).enumerate() {
+        if recorded != played {
+            return Err(format!("action {}: recorded {:?} but the engine played {:?}", idx + 1, recorded, played));
+        }
+    }
+    if hand.actions.len() != replayed.actions.len() {
+        return Err(format!(
+            "recorded {} actions but the engine played {}",
+            hand.actions.len(),
+            replayed.actions.len()
+        ));
+    }
+    if hand.board != replayed.board {
+        return Err(format!("recorded board {:?} but the engine dealt {:?}", hand.board, replayed.board));
+    }
+    if hand.draws != replayed.draws {
+        return Err(format!("recorded draws {:?} but the engine drew {:?}", hand.draws, replayed.draws));
+    }
+    let boards = |hand: &HandRecord| hand.runs.iter().map(|run| run.board.clone()).collect::<Vec<_>>();
+    if boards(hand) != boards(&replayed) {
+        return Err(format!("recorded runs of the board {:?} but the engine ran {:?}", boards(hand), boards(&replayed)));
+    }
+    if hand.uncalled != replayed.uncalled {
+        return Err(format!("recorded uncalled bet {:?} but the engine returned {:?}", hand.uncalled, replayed.uncalled));
+    }
+
+    let sorted = |hand: &HandRecord| {
+        let mut pots = hand.pots.clone();
+        for pot in pots.iter_mut() {
+            pot.winners.sort_unstable();
+        }
+        pots
+    };
+    if sorted(hand) != sorted(&replayed) {
+        return Err(format!("recorded pots {:?} but the engine paid {:?}", hand.pots, replayed.pots));
+    }
+    for seat in &hand.seats {
+        let (recorded, played) = (hand.finishing_stack(seat.player_id), replayed.finishing_stack(seat.player_id));
+        if recorded != played {
+            return Err(format!("{} finished with {} but the engine left them {}", seat.name, recorded, played));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{parse_json_hands, HandHistoryWriter, JsonWriter};
+    use crate::pokerstars;
+
+    #[test]
+    fn test_replay_corpus() {
+        let hands = pokerstars::parse_hands(include_str!("../corpus/pokerstars.txt")).unwrap();
+        assert_eq!(hands.len(), 4);
+        for hand in &hands {
+            if let Err(err) = verify(hand) {
+                panic!("hand #{}: {}", hand.hand_id, err);
+            }
+        }
+    }
+
+    #[test]
+    fn test_replay_game_hands() {
+        for (ante, straddle) in [(Ante::None, Straddle::None), (Ante::Everyone(5), Straddle::UnderTheGun), (Ante::BigBlind(2), Straddle::Button)] {
+            let mut game = Game::new(6, 300).unwrap();
+            game.set_ante(ante);
+            game.set_straddle(straddle);
+            let mut writer = JsonWriter::new(Vec::new());
+            for _ in 0..50 {
+                game.play_round().unwrap();
+                if let Some(hand) = game.last_hand() {
+                    writer.write_hand(hand).unwrap();
+                }
+            }
+            let text = String::from_utf8(writer.into_inner()).unwrap();
+            for hand in parse_json_hands(&text).unwrap() {
+                assert_eq!(verify(&hand), Ok(()), "hand #{}", hand.hand_id);
+                let replayed = replay(&hand).unwrap();
+                assert_eq!(replayed.seats, hand.seats);
+                assert_eq!(replayed.showdown, hand.showdown);
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_finds_differences() {
+        let mut hand = pokerstars::parse_hands(include_str!("../corpus/pokerstars.txt")).unwrap().remove(1);
+        hand.pots[0].winners[0].1 += 1;
+        hand.pots[0].amount += 1;
+        assert!(verify(&hand).unwrap_err().starts_with("recorded pots"));
+
+        let mut hand = pokerstars::parse_hands(include_str!("../corpus/pokerstars.txt")).unwrap().remove(0);
+        hand.button_seat = hand.seats[1].seat;
+        assert!(verify(&hand).unwrap_err().starts_with("the blinds were posted by"));
+
+        // Eve checks facing Fay's bet instead of folding
+        let mut hand = pokerstars::parse_hands(include_str!("../corpus/pokerstars.txt")).unwrap().remove(0);
+        hand.actions[10].kind = ActionKind::Check;
+        hand.seats[4].cards = vec![Card::from_code("9c").unwrap(), Card::from_code("9d").unwrap()];
+        assert_eq!(verify(&hand), Err("player 4 cannot Check facing 8 to call".to_string()));
+    }
+}
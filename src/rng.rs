@@ -0,0 +1,55 @@
+use std::cell::RefCell;
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+// Shuffling and random play draw from here. Unseeded it is the thread's own
+// random generator; once seeded, everything this thread deals and decides
+// repeats exactly from run to run.
+thread_local! {
+    static SEEDED: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+pub fn seed(seed: u64) {
+    SEEDED.with(|rng| *rng.borrow_mut() = Some(StdRng::seed_from_u64(seed)));
+}
+
+pub fn unseed() {
+    SEEDED.with(|rng| *rng.borrow_mut() = None);
+}
+
+// a generator for the next few draws, split off the seeded one if there is one
+pub(crate) fn rng() -> StdRng {
+    SEEDED.with(|seeded| match seeded.borrow_mut().as_mut() {
+        Some(rng) => StdRng::seed_from_u64(rng.next_u64()),
+        None => StdRng::from_rng(rand::thread_rng()).unwrap(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    // what happened in each hand, leaving out the time it was played
+    fn play(seed_value: u64) -> Vec<String> {
+        seed(seed_value);
+        let mut game = Game::new(4, 200).unwrap();
+        game.set_verbose(false);
+        let mut hands = Vec::new();
+        for _ in 0..20 {
+            game.play_round().unwrap();
+            let hand = game.last_hand().unwrap();
+            hands.push(format!("{:?} {:?} {:?}", hand.seats, hand.actions, hand.pots));
+        }
+        unseed();
+        hands
+    }
+
+    #[test]
+    fn test_seeded_games_repeat() {
+        assert_eq!(play(7), play(7));
+        let differs = (1..5).any(|s| play(s) != play(7));
+        assert!(differs);
+    }
+}
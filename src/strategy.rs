@@ -0,0 +1,169 @@
+use crate::deck::{Card, Rank};
+use crate::player::{Action, Player, Strategy};
+use crate::utils::{best_badugi, Hand, HandComparator};
+use crate::variant::Variant;
+
+// Simple built-in opponents, mostly useful as baselines in simulations.
+// Players without a strategy keep the engine's random play.
+
+// Checks when it can and calls everything else.
+pub struct CallingStation;
+
+impl Strategy for CallingStation {
+    fn decide(&mut self, _player: &Player, _pot: u32, _board: &[Card], to_call: u32, _action: &[Vec<Action>]) -> Action {
+        if to_call == 0 { Action::Check } else { Action::Call }
+    }
+}
+
+// Bets or raises a fraction of the pot every time it acts.
+pub struct Aggressive {
+    pub pot_fraction: f64,
+}
+
+impl Strategy for Aggressive {
+    fn decide(&mut self, _player: &Player, pot: u32, _board: &[Card], _to_call: u32, _action: &[Vec<Action>]) -> Action {
+        Action::Raise(pot_share(pot, self.pot_fraction))
+    }
+}
+
+// Plays only pairs and two high cards before the flop, and afterwards bets a
+// fraction of the pot with a pair or better, checking or folding otherwise.
+pub struct Tight {
+    pub pot_fraction: f64,
+}
+
+impl Strategy for Tight {
+    fn decide(&mut self, player: &Player, pot: u32, board: &[Card], to_call: u32, _action: &[Vec<Action>]) -> Action {
+        let strong = if board.is_empty() && player.hand.len() == 2 {
+            player.hand[0].0 == player.hand[1].0 || player.hand.iter().all(|c| c.0 >= Rank::Ten)
+        } else {
+            let mut cards = board.to_vec();
+            cards.extend_from_slice(&player.hand);
+            cards.len() >= 5 && HandComparator::best_hand(cards).1 >= Hand::Pair
+        };
+        match (strong, to_call) {
+            (true, _) => Action::Raise(pot_share(pot, self.pot_fraction)),
+            (false, 0) => Action::Check,
+            (false, _) => Action::Fold,
+        }
+    }
+}
+
+// at least one chip, so a raise is never refused for being empty
+fn pot_share(pot: u32, fraction: f64) -> u32 {
+    ((pot as f64 * fraction).round() as u32).max(1)
+}
+
+// A plain draw, as a mask of the places in the hand to throw away. A high
+// hand stands pat on a straight or better and otherwise keeps its pairs and
+// sets, or just its highest card; a lowball hand keeps one card of each
+// rank up to an eight. Badugi stands pat on four cards and otherwise keeps
+// those of its best badugi up to an eight.
+pub fn standard_discards(hand: &[Card], variant: Variant) -> u8 {
+    let ranking = variant.ranking();
+    let paired = |card: &Card| hand.iter().filter(|c| c.0 == card.0).count() > 1;
+    let keep: Vec<bool> = if variant == Variant::Badugi {
+        let badugi = best_badugi(hand);
+        hand.iter().map(|card| badugi.contains(card) && (badugi.len() == 4 || ranking.value(card.0) <= ranking.value(Rank::Eight))).collect()
+    } else if ranking.is_low() {
+        hand.iter().enumerate().map(|(i, card)| ranking.value(card.0) <= ranking.value(Rank::Eight) && hand[..i].iter().all(|c| c.0 != card.0)).collect()
+    } else if hand.len() == 5 && HandComparator::best_hand(hand.to_vec()).1 >= Hand::Straight {
+        vec![true; hand.len()]
+    } else if hand.iter().any(paired) {
+        hand.iter().map(paired).collect()
+    } else {
+        let highest = hand.iter().map(|c| c.0).max();
+        hand.iter().map(|c| Some(c.0) == highest).collect()
+    };
+    keep.iter().enumerate().filter(|(_, keep)| !**keep).fold(0, |discards, (i, _)| discards | 1 << i)
+}
+
+pub static STRATEGY_NAMES: &[&str] = &["random", "call", "raise", "tight"];
+
+// A strategy by name with its bet size as a fraction of the pot, e.g. from
+// the command line or a table file. "random" is the engine's own play, None.
+pub fn build(name: &str, pot_fraction: Option<f64>) -> Result<Option<Box<dyn Strategy>>, String> {
+    let pot_fraction = pot_fraction.unwrap_or(0.5);
+    if !(pot_fraction > 0.0 && pot_fraction.is_finite()) {
+        return Err(format!("a pot fraction must be positive, not {}", pot_fraction));
+    }
+    match name {
+        "random" => Ok(None),
+        "call" => Ok(Some(Box::new(CallingStation))),
+        "raise" => Ok(Some(Box::new(Aggressive { pot_fraction }))),
+        "tight" => Ok(Some(Box::new(Tight { pot_fraction }))),
+        name => Err(format!("unknown strategy '{}', expected one of {}", name, STRATEGY_NAMES.join(", "))),
+    }
+}
+
+// "name" or "name:fraction", e.g. "raise:0.75"
+pub fn parse_spec(spec: &str) -> Result<(&str, Option<f64>), String> {
+    match spec.split_once(':') {
+        Some((name, fraction)) => {
+            let fraction = fraction.parse().map_err(|_| format!("invalid pot fraction '{}'", fraction))?;
+            Ok((name, Some(fraction)))
+        },
+        None => Ok((spec, None)),
+    }
+}
+
+pub fn from_spec(spec: &str) -> Result<Option<Box<dyn Strategy>>, String> {
+    let (name, pot_fraction) = parse_spec(spec)?;
+    build(name, pot_fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(cards: &str) -> Player {
+        let mut player = Player::new(0, "Test".to_string(), 100);
+        for code in cards.split_whitespace() {
+            player.deal_card(Card::from_code(code).unwrap()).unwrap();
+        }
+        player
+    }
+
+    fn cards(codes: &str) -> Vec<Card> {
+        codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_tight() {
+        let mut tight = Tight { pot_fraction: 1.0 };
+        assert_eq!(tight.decide(&player("7c 2d"), 3, &[], 2, &[]), Action::Fold);
+        assert_eq!(tight.decide(&player("7c 2d"), 4, &[], 0, &[]), Action::Check);
+        assert_eq!(tight.decide(&player("Kc Jd"), 3, &[], 2, &[]), Action::Raise(3));
+        assert_eq!(tight.decide(&player("5c 5d"), 3, &[], 2, &[]), Action::Raise(3));
+        assert_eq!(tight.decide(&player("Kc Jd"), 10, &cards("2c 7d 9h"), 5, &[]), Action::Fold);
+        assert_eq!(tight.decide(&player("Kc Jd"), 10, &cards("2c 7d Jh"), 5, &[]), Action::Raise(10));
+    }
+
+    #[test]
+    fn test_standard_discards() {
+        let draw = Variant::FiveCardDraw;
+        assert_eq!(standard_discards(&cards("9c 9d 2h Ks 4c"), draw), 0b11100);
+        assert_eq!(standard_discards(&cards("9c Td Jh Qs Kc"), draw), 0);
+        assert_eq!(standard_discards(&cards("9c 3d Ah Qs 2c"), draw), 0b11011);
+        // lowball keeps one of each card to an eight, and the ace is high
+        let triple = Variant::DeuceToSevenTripleDraw;
+        assert_eq!(standard_discards(&cards("2c 2d 7h 9s Ac"), triple), 0b11010);
+        // badugi keeps the ace, deuce and three of different suits
+        assert_eq!(standard_discards(&cards("As 2c 3d Kd"), Variant::Badugi), 0b1000);
+        assert_eq!(standard_discards(&cards("Kc Qd Jh Ts"), Variant::Badugi), 0);
+        assert_eq!(standard_discards(&cards("As 9c 3s Kd"), Variant::Badugi), 0b1110);
+    }
+
+    #[test]
+    fn test_build() {
+        assert!(build("random", None).unwrap().is_none());
+        assert!(from_spec("raise:0.75").unwrap().is_some());
+        assert!(from_spec("call").unwrap().is_some());
+        assert!(from_spec("raise:-1").is_err());
+        assert!(from_spec("raise:lots").is_err());
+        assert!(from_spec("bluff").err().unwrap().starts_with("unknown strategy 'bluff'"));
+        let mut aggressive = Aggressive { pot_fraction: 0.5 };
+        assert_eq!(aggressive.decide(&player("7c 2d"), 1, &[], 0, &[]), Action::Raise(1));
+        assert_eq!(aggressive.decide(&player("7c 2d"), 30, &[], 0, &[]), Action::Raise(15));
+    }
+}
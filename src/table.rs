@@ -0,0 +1,226 @@
+use std::fmt;
+
+use crate::error::PokerError;
+use crate::player::{Player, PlayerState};
+
+// Numbered seats, from 1 to max_seats, each empty or taken by one player.
+// Players keep their seat number when others join, leave or bust.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Table {
+    seats: Vec<Option<Player>>,
+}
+
+// Where a player sits relative to the button in one hand. Seats between the
+// big blind and the cutoff count up from under the gun, so a full ring is
+// UTG, UTG+1, UTG+2, LJ, HJ, CO and a six handed table UTG, HJ, CO.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Position {
+    Button,
+    SmallBlind,
+    BigBlind,
+    UnderTheGun(usize),
+    Lojack,
+    Hijack,
+    Cutoff,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Position::Button => write!(f, "BTN"),
+            Position::SmallBlind => write!(f, "SB"),
+            Position::BigBlind => write!(f, "BB"),
+            Position::UnderTheGun(0) => write!(f, "UTG"),
+            Position::UnderTheGun(n) => write!(f, "UTG+{}", n),
+            Position::Lojack => write!(f, "LJ"),
+            Position::Hijack => write!(f, "HJ"),
+            Position::Cutoff => write!(f, "CO"),
+        }
+    }
+}
+
+impl Position {
+    // The position of each of these seats, which are everyone dealt in, in
+    // seat order. The button and small blind may be on seats nobody was dealt
+    // in from; heads up the button is also the small blind.
+    pub fn assign(seats: &[usize], button: usize, small_blind: Option<usize>, big_blind: usize) -> Vec<Position> {
+        let n_seats = seats.len();
+        let start = seats.iter().position(|&seat| seat > big_blind).unwrap_or(0);
+        let others: Vec<usize> = (0..n_seats)
+            .map(|i| seats[(start + i) % n_seats])
+            .filter(|&seat| seat != button && Some(seat) != small_blind && seat != big_blind)
+            .collect();
+        seats
+            .iter()
+            .map(|&seat| {
+                if seat == button {
+                    return Position::Button;
+                } else if Some(seat) == small_blind {
+                    return Position::SmallBlind;
+                } else if seat == big_blind {
+                    return Position::BigBlind;
+                }
+                let n = others.iter().position(|&s| s == seat).unwrap();
+                let from_button = others.len() - n;
+                match from_button {
+                    _ if n == 0 => Position::UnderTheGun(0),
+                    1 => Position::Cutoff,
+                    2 => Position::Hijack,
+                    3 => Position::Lojack,
+                    _ => Position::UnderTheGun(n),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Table {
+    pub fn new(max_seats: usize) -> Table {
+        Table { seats: vec![None; max_seats] }
+    }
+
+    pub fn max_seats(&self) -> usize {
+        self.seats.len()
+    }
+
+    // Adds or removes seats at the end; taken seats are never removed.
+    pub fn resize(&mut self, max_seats: usize) {
+        let last_taken = self.seats.iter().rposition(|s| s.is_some()).map_or(0, |idx| idx + 1);
+        self.seats.resize(max_seats.max(last_taken), None);
+    }
+
+    fn slot(&self, seat: usize) -> Result<&Option<Player>, PokerError> {
+        seat.checked_sub(1).and_then(|idx| self.seats.get(idx)).ok_or(PokerError::NoSuchSeat(seat))
+    }
+
+    fn slot_mut(&mut self, seat: usize) -> Result<&mut Option<Player>, PokerError> {
+        seat.checked_sub(1).and_then(|idx| self.seats.get_mut(idx)).ok_or(PokerError::NoSuchSeat(seat))
+    }
+
+    pub fn sit(&mut self, seat: usize, player: Player) -> Result<(), PokerError> {
+        if self.slot(seat)?.is_some() {
+            return Err(PokerError::SeatTaken(seat));
+        }
+        if self.seat_of(player.id).is_some() {
+            return Err(PokerError::AlreadySeated(player.id));
+        }
+        *self.slot_mut(seat)? = Some(player);
+        Ok(())
+    }
+
+    // empties the seat, handing back whoever sat there
+    pub fn leave(&mut self, seat: usize) -> Option<Player> {
+        self.slot_mut(seat).ok()?.take()
+    }
+
+    pub fn sit_out(&mut self, seat: usize) -> Result<(), PokerError> {
+        self.set_sitting_out(seat, true)
+    }
+
+    pub fn sit_in(&mut self, seat: usize) -> Result<(), PokerError> {
+        self.set_sitting_out(seat, false)
+    }
+
+    fn set_sitting_out(&mut self, seat: usize, sitting_out: bool) -> Result<(), PokerError> {
+        let player = self.slot_mut(seat)?.as_mut().ok_or(PokerError::SeatEmpty(seat))?;
+        player.state = if sitting_out { PlayerState::SittingOut } else { PlayerState::Active };
+        Ok(())
+    }
+
+    pub fn is_sitting_out(&self, seat: usize) -> bool {
+        self.player(seat).is_some_and(|p| p.state == PlayerState::SittingOut)
+    }
+
+    pub fn player(&self, seat: usize) -> Option<&Player> {
+        self.slot(seat).ok()?.as_ref()
+    }
+
+    pub fn player_mut(&mut self, seat: usize) -> Option<&mut Player> {
+        self.slot_mut(seat).ok()?.as_mut()
+    }
+
+    pub fn seat_of(&self, player_id: usize) -> Option<usize> {
+        self.occupied().find(|(_, p)| p.id == player_id).map(|(seat, _)| seat)
+    }
+
+    // every taken seat and its player, in seat order
+    pub fn occupied(&self) -> impl Iterator<Item = (usize, &Player)> {
+        self.seats
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, s)| s.as_ref().map(|p| (idx + 1, p)))
+    }
+
+    // the seats of everyone who gets cards next hand, in seat order
+    pub fn dealt_in(&self) -> Vec<usize> {
+        self.seats
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.as_ref().is_some_and(|p| p.state != PlayerState::SittingOut && p.chips > 0))
+            .map(|(idx, _)| idx + 1)
+            .collect()
+    }
+
+    // the first seat after this one, going round the table, of someone dealt in
+    pub fn next_dealt_in(&self, seat: usize) -> Option<usize> {
+        let dealt_in = self.dealt_in();
+        dealt_in.iter().find(|&&s| s > seat).or(dealt_in.first()).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sit_and_leave() {
+        let mut table = Table::new(6);
+        table.sit(2, Player::new(0, "Alice".to_string(), 100)).unwrap();
+        table.sit(5, Player::new(1, "Bob".to_string(), 100)).unwrap();
+        assert_eq!(table.sit(5, Player::new(2, "Carol".to_string(), 100)), Err(PokerError::SeatTaken(5)));
+        assert_eq!(table.sit(3, Player::new(0, "Alice".to_string(), 100)), Err(PokerError::AlreadySeated(0)));
+        assert_eq!(table.sit(7, Player::new(2, "Carol".to_string(), 100)), Err(PokerError::NoSuchSeat(7)));
+        assert_eq!(table.sit(0, Player::new(2, "Carol".to_string(), 100)), Err(PokerError::NoSuchSeat(0)));
+        assert_eq!(table.seat_of(1), Some(5));
+        assert_eq!(table.next_dealt_in(2), Some(5));
+        assert_eq!(table.next_dealt_in(5), Some(2));
+
+        assert_eq!(table.leave(2).map(|p| p.name), Some("Alice".to_string()));
+        assert_eq!(table.leave(2), None);
+        assert_eq!(table.occupied().map(|(seat, _)| seat).collect::<Vec<_>>(), [5]);
+
+        table.resize(3);
+        assert_eq!(table.max_seats(), 5);
+    }
+
+    #[test]
+    fn test_sitting_out() {
+        let mut table = Table::new(4);
+        for seat in 1..=3 {
+            table.sit(seat, Player::new(seat, format!("P{}", seat), 100)).unwrap();
+        }
+        table.player_mut(3).unwrap().chips = 0;
+        table.sit_out(1).unwrap();
+        assert!(table.is_sitting_out(1));
+        assert_eq!(table.sit_out(4), Err(PokerError::SeatEmpty(4)));
+        assert_eq!(table.dealt_in(), [2]);
+        table.sit_in(1).unwrap();
+        assert_eq!(table.dealt_in(), [1, 2]);
+    }
+
+    #[test]
+    fn test_positions() {
+        let names = |seats: &[usize], button, small_blind, big_blind| {
+            Position::assign(seats, button, small_blind, big_blind)
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+        };
+        let full_ring: Vec<usize> = (1..=9).collect();
+        assert_eq!(names(&full_ring, 9, Some(1), 2), ["SB", "BB", "UTG", "UTG+1", "UTG+2", "LJ", "HJ", "CO", "BTN"]);
+        assert_eq!(names(&[1, 2, 4, 5, 7, 8], 2, Some(4), 5), ["CO", "BTN", "SB", "BB", "UTG", "HJ"]);
+        assert_eq!(names(&[3, 6], 6, Some(6), 3), ["BB", "BTN"]);
+        // a dead small blind and a button on an empty seat
+        assert_eq!(names(&[1, 3, 4, 5], 2, None, 3), ["CO", "BB", "UTG", "HJ"]);
+    }
+}
@@ -0,0 +1,464 @@
+use std::collections::BTreeMap;
+
+// Just enough TOML for hand histories and table files: comments, bare, quoted
+// and dotted keys, strings, integers, floats, booleans, times and dates (kept
+// as text), arrays, inline tables, [tables] and [[arrays of tables]]. Like
+// json.rs it is kept in-tree so rand stays the only dependency; anything
+// malformed or outside that subset is an Err, never a panic.
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Datetime(String),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+pub type Table = BTreeMap<String, Value>;
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) | Value::Datetime(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(f) => Some(*f),
+            Value::Integer(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&Table> {
+        match self {
+            Value::Table(t) => Some(t),
+            _ => None,
+        }
+    }
+}
+
+// a string as a TOML basic string, quotes included
+pub fn quote(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub fn parse(text: &str) -> Result<Table, String> {
+    let mut parser = Parser { chars: text.chars().collect(), pos: 0, line: 1 };
+    parser.document()
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, String> {
+        Err(format!("line {}: {}", self.line, message))
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.bump() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => self.error(&format!("expected '{}' but found '{}'", expected, c)),
+            None => self.error(&format!("expected '{}' but found the end of the file", expected)),
+        }
+    }
+
+    // spaces and tabs only
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.bump();
+            }
+        }
+    }
+
+    // whitespace, newlines and comments
+    fn skip_all(&mut self) {
+        loop {
+            self.skip_blank();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n' | '\r') => {
+                    self.bump();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_blank();
+        self.skip_comment();
+        match self.peek() {
+            None | Some('\n') => Ok(()),
+            Some('\r') if self.peek_at(1) == Some('\n') => Ok(()),
+            Some(c) => self.error(&format!("unexpected '{}' after value", c)),
+        }
+    }
+
+    fn document(&mut self) -> Result<Table, String> {
+        let mut root = Table::new();
+        // the path of the table that keys currently go into
+        let mut current: Vec<String> = Vec::new();
+        loop {
+            self.skip_all();
+            match self.peek() {
+                None => return Ok(root),
+                Some('[') => {
+                    self.bump();
+                    let array = self.peek() == Some('[');
+                    if array {
+                        self.bump();
+                    }
+                    self.skip_blank();
+                    current = self.key()?;
+                    self.skip_blank();
+                    self.expect(']')?;
+                    if array {
+                        self.expect(']')?;
+                    }
+                    self.end_of_line()?;
+                    let line = self.line;
+                    let (last, parents) = current.split_last().unwrap();
+                    let parent = table_at(&mut root, parents, line)?;
+                    if array {
+                        match parent.entry(last.clone()).or_insert_with(|| Value::Array(Vec::new())) {
+                            Value::Array(tables) => tables.push(Value::Table(Table::new())),
+                            _ => return Err(format!("line {}: '{}' is not an array of tables", line, last)),
+                        }
+                    } else {
+                        match parent.entry(last.clone()).or_insert_with(|| Value::Table(Table::new())) {
+                            Value::Table(_) => {}
+                            _ => return Err(format!("line {}: '{}' is already defined", line, last)),
+                        }
+                    }
+                }
+                Some(_) => {
+                    let key = self.key()?;
+                    self.skip_blank();
+                    self.expect('=')?;
+                    self.skip_blank();
+                    let value = self.value()?;
+                    self.end_of_line()?;
+                    let line = self.line;
+                    let table = table_at(&mut root, &current, line)?;
+                    insert(table, &key, value, line)?;
+                }
+            }
+        }
+    }
+
+    fn key(&mut self) -> Result<Vec<String>, String> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_blank();
+            let part = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-' => {
+                    let mut part = String::new();
+                    while let Some(c) = self.peek() {
+                        if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                            part.push(c);
+                            self.bump();
+                        } else {
+                            break;
+                        }
+                    }
+                    part
+                }
+                _ => return self.error("expected a key"),
+            };
+            parts.push(part);
+            self.skip_blank();
+            if self.peek() == Some('.') {
+                self.bump();
+            } else {
+                return Ok(parts);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') => Ok(Value::String(self.basic_string()?)),
+            Some('\'') => Ok(Value::String(self.literal_string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) => self.bare_value(),
+            None => self.error("expected a value"),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return self.error("unterminated string"),
+                Some('"') => return Ok(out),
+                Some('\\') => match self.bump() {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.bump()).collect();
+                        match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                            Some(c) => out.push(c),
+                            None => return self.error("invalid unicode escape"),
+                        }
+                    }
+                    _ => return self.error("invalid escape"),
+                },
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.expect('\'')?;
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return self.error("unterminated string"),
+                Some('\'') => return Ok(out),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_all();
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_all();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some(']') => {}
+                _ => return self.error("expected ',' or ']' in array"),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut table = Table::new();
+        self.skip_blank();
+        if self.peek() == Some('}') {
+            self.bump();
+            return Ok(Value::Table(table));
+        }
+        loop {
+            let key = self.key()?;
+            self.skip_blank();
+            self.expect('=')?;
+            self.skip_blank();
+            let value = self.value()?;
+            insert(&mut table, &key, value, self.line)?;
+            self.skip_blank();
+            match self.bump() {
+                Some(',') => self.skip_blank(),
+                Some('}') => return Ok(Value::Table(table)),
+                _ => return self.error("expected ',' or '}' in inline table"),
+            }
+        }
+    }
+
+    fn bare_value(&mut self) -> Result<Value, String> {
+        let mut token = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '_' | '.' | ':') {
+                token.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        match token.as_str() {
+            "" => self.error("expected a value"),
+            "true" => Ok(Value::Boolean(true)),
+            "false" => Ok(Value::Boolean(false)),
+            _ if token.contains(':') || token[1..].contains('-') && !token.contains('e') => {
+                Ok(Value::Datetime(token))
+            }
+            _ => {
+                let digits = token.replace('_', "");
+                if let Ok(i) = digits.parse::<i64>() {
+                    Ok(Value::Integer(i))
+                } else if let Ok(f) = digits.parse::<f64>() {
+                    Ok(Value::Float(f))
+                } else {
+                    self.error(&format!("invalid value '{}'", token))
+                }
+            }
+        }
+    }
+}
+
+// the table at a path of keys, creating it if needed; a path through an
+// array of tables refers to its last table
+fn table_at<'a>(root: &'a mut Table, path: &[String], line: usize) -> Result<&'a mut Table, String> {
+    let mut table = root;
+    for key in path {
+        let value = table.entry(key.clone()).or_insert_with(|| Value::Table(Table::new()));
+        table = match value {
+            Value::Table(t) => t,
+            Value::Array(items) => match items.last_mut() {
+                Some(Value::Table(t)) => t,
+                _ => return Err(format!("line {}: '{}' is not a table", line, key)),
+            },
+            _ => return Err(format!("line {}: '{}' is not a table", line, key)),
+        };
+    }
+    Ok(table)
+}
+
+fn insert(table: &mut Table, key: &[String], value: Value, line: usize) -> Result<(), String> {
+    let (last, parents) = key.split_last().unwrap();
+    let table = table_at(table, parents, line)?;
+    if table.contains_key(last) {
+        return Err(format!("line {}: '{}' is defined twice", line, last));
+    }
+    table.insert(last.clone(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_values() {
+        let doc = parse(
+            "# a comment\n\
+             name = 'Table 1' # trailing\n\
+             quoted = \"a \\\"b\\\"\\n\"\n\
+             count = 1_000\n\
+             negative = -5\n\
+             ratio = 0.25\n\
+             flag = true\n\
+             time = 22:13:20\n\
+             stacks = [\n  100,\n  200, # comment\n]\n\
+             mixed = [[1, 2], ['a']]\n\
+             strategy = { name = \"random\", aggression = 0.5 }\n\
+             dotted.key = 3\n",
+        ).unwrap();
+        assert_eq!(doc["name"], Value::String("Table 1".to_string()));
+        assert_eq!(doc["quoted"].as_str(), Some("a \"b\"\n"));
+        assert_eq!(doc["count"].as_integer(), Some(1000));
+        assert_eq!(doc["negative"].as_integer(), Some(-5));
+        assert_eq!(doc["ratio"].as_float(), Some(0.25));
+        assert_eq!(doc["flag"].as_bool(), Some(true));
+        assert_eq!(doc["time"], Value::Datetime("22:13:20".to_string()));
+        assert_eq!(doc["stacks"], Value::Array(vec![Value::Integer(100), Value::Integer(200)]));
+        assert_eq!(doc["mixed"].as_array().unwrap().len(), 2);
+        assert_eq!(doc["strategy"].as_table().unwrap()["aggression"].as_float(), Some(0.5));
+        assert_eq!(doc["dotted"].as_table().unwrap()["key"].as_integer(), Some(3));
+    }
+
+    #[test]
+    fn test_parse_tables() {
+        let doc = parse(
+            "[blinds]\nsmall = 1\nbig = 2\n\n\
+             [[seats]]\nname = \"Alice\"\n\
+             [[seats]]\nname = \"Bob\"\n\
+             [seats.strategy]\nkind = \"random\"\n",
+        ).unwrap();
+        assert_eq!(doc["blinds"].as_table().unwrap()["big"].as_integer(), Some(2));
+        let seats = doc["seats"].as_array().unwrap();
+        assert_eq!(seats.len(), 2);
+        let bob = seats[1].as_table().unwrap();
+        assert_eq!(bob["name"].as_str(), Some("Bob"));
+        assert_eq!(bob["strategy"].as_table().unwrap()["kind"].as_str(), Some("random"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("a = 1\na = 2\n").is_err());
+        assert!(parse("a = \"open\n").is_err());
+        assert!(parse("a = [1 2]\n").is_err());
+        assert!(parse("a = 1 b\n").is_err());
+        assert_eq!(parse("\n\na = nope\n").unwrap_err(), "line 3: invalid value 'nope'");
+    }
+
+    #[test]
+    fn test_quote() {
+        let text = format!("a = {}\n", quote("say \"hi\"\\"));
+        assert_eq!(parse(&text).unwrap()["a"].as_str(), Some("say \"hi\"\\"));
+    }
+}
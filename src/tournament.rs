@@ -0,0 +1,434 @@
+use std::cmp::Reverse;
+use std::time::Duration;
+
+use crate::bounty::{Bounties, BountyFormat};
+use crate::error::PokerError;
+use crate::game::{Ante, Game};
+use crate::icm;
+
+// The blinds and ante for one level of a tournament.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct BlindLevel {
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub ante: Ante,
+}
+
+impl BlindLevel {
+    pub fn new(small_blind: u32, big_blind: u32, ante: Ante) -> BlindLevel {
+        BlindLevel { small_blind, big_blind, ante }
+    }
+}
+
+// How long every level lasts, in hands dealt or in tournament time.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum LevelLength {
+    Hands(u64),
+    Time(Duration),
+}
+
+// Levels in the order they are played; the last one lasts until the end.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BlindSchedule {
+    pub levels: Vec<BlindLevel>,
+    pub length: LevelLength,
+}
+
+impl BlindSchedule {
+    // the level played after this many hands and this much time
+    pub fn level_at(&self, hands: u64, elapsed: Duration) -> usize {
+        let level = match self.length {
+            LevelLength::Hands(hands_per_level) => hands / hands_per_level.max(1),
+            LevelLength::Time(per_level) => (elapsed.as_secs_f64() / per_level.as_secs_f64().max(1e-9)) as u64,
+        };
+        (level as usize).min(self.levels.len().saturating_sub(1))
+    }
+}
+
+// Where a player finished and what they won. Players who bust in the same
+// hand finish in order of the stacks they started it with, and share the
+// places and prizes when those were equal.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Finish {
+    pub player_id: usize,
+    pub name: String,
+    pub place: usize,
+    // the hand they busted in, or the last hand for the winner
+    pub hand_id: u64,
+    pub prize: u32,
+    // bounties paid out to them in a bounty tournament
+    pub bounties: u32,
+}
+
+// Prizes for first place on down out of a pool, by whole percentages. What
+// rounding leaves over goes to first place.
+pub fn split_prize_pool(pool: u32, percentages: &[u32]) -> Vec<u32> {
+    let mut prizes: Vec<u32> = percentages.iter().map(|&p| (pool as u64 * p as u64 / 100) as u32).collect();
+    let paid: u32 = prizes.iter().sum();
+    if let Some(first) = prizes.first_mut() {
+        *first += pool.saturating_sub(paid);
+    }
+    prizes
+}
+
+// A single table tournament played on a Game until one player has every chip.
+// The blinds and ante follow the schedule, and a simulated hand takes a
+// minute of tournament time unless set otherwise.
+pub struct Tournament {
+    game: Game,
+    schedule: BlindSchedule,
+    payouts: Vec<u32>,
+    hands_played: u64,
+    elapsed: Duration,
+    hand_duration: Duration,
+    entrants: usize,
+    // eliminations in the order they happened, the winner last
+    finishes: Vec<Finish>,
+    bounties: Option<Bounties>,
+}
+
+impl Tournament {
+    pub fn new(game: Game, schedule: BlindSchedule) -> Tournament {
+        let entrants = game.players().len();
+        Tournament {
+            game,
+            schedule,
+            payouts: Vec::new(),
+            hands_played: 0,
+            elapsed: Duration::ZERO,
+            hand_duration: Duration::from_secs(60),
+            entrants,
+            finishes: Vec::new(),
+            bounties: None,
+        }
+    }
+
+    // prizes for first place on down; places past the end win nothing
+    pub fn set_payouts(&mut self, payouts: Vec<u32>) {
+        self.payouts = payouts;
+    }
+
+    pub fn set_hand_duration(&mut self, duration: Duration) {
+        self.hand_duration = duration;
+    }
+
+    // Makes this a bounty tournament with the same bounty on everyone still
+    // in, paid on top of the prizes.
+    pub fn set_bounties(&mut self, format: BountyFormat, bounty: u32) {
+        let bounties = Bounties::new(format, self.game.players().iter().map(|p| p.id), bounty);
+        self.game.set_bounties(bounties.bounties().clone());
+        self.bounties = Some(bounties);
+    }
+
+    pub fn bounties(&self) -> Option<&Bounties> {
+        self.bounties.as_ref()
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    // e.g. to set strategies or sit players out
+    pub fn game_mut(&mut self) -> &mut Game {
+        &mut self.game
+    }
+
+    pub fn payouts(&self) -> &[u32] {
+        &self.payouts
+    }
+
+    pub fn entrants(&self) -> usize {
+        self.entrants
+    }
+
+    pub fn hands_played(&self) -> u64 {
+        self.hands_played
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    // the index into the schedule of the level the next hand is played at
+    pub fn level(&self) -> usize {
+        self.schedule.level_at(self.hands_played, self.elapsed)
+    }
+
+    // Each remaining player's ICM share of the prizes still to be won, by
+    // player id in seat order, e.g. for a bot weighing a push on the bubble.
+    pub fn icm_equity(&self) -> Vec<(usize, f64)> {
+        let players = self.game.players();
+        let stacks: Vec<u32> = players.iter().map(|p| p.chips).collect();
+        let prizes: Vec<u32> = self.payouts.iter().take(players.len()).copied().collect();
+        players.iter().map(|p| p.id).zip(icm::equity(&stacks, &prizes)).collect()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.game.players().len() <= 1
+    }
+
+    // Plays one hand at the current level and records who busted. Returns
+    // false without playing once the tournament is over or too few players
+    // are sitting in to deal a hand.
+    pub fn play_hand(&mut self) -> Result<bool, PokerError> {
+        if self.is_finished() || self.game.table().dealt_in().len() < 2 {
+            return Ok(false);
+        }
+        if let Some(level) = self.schedule.levels.get(self.level()) {
+            self.game.set_blinds(level.small_blind, level.big_blind);
+            self.game.set_ante(level.ante);
+        }
+        self.game.play_round()?;
+        self.hands_played += 1;
+        self.elapsed += self.hand_duration;
+
+        let Some(record) = self.game.last_hand() else { return Ok(true) };
+        let busted: Vec<(u32, Finish)> = busted_in(&self.game).collect();
+        let remaining = self.game.players().len();
+        let winner = self.game.players()[0];
+        if let Some(bounties) = &mut self.bounties {
+            bounties.knock_out(record, &busted.iter().map(|b| b.1.player_id).collect::<Vec<_>>());
+            if remaining == 1 {
+                bounties.crown(winner.id);
+            }
+        }
+        self.finishes.extend(place_busts(busted, remaining));
+        if remaining == 1 {
+            self.finishes.push(Finish { player_id: winner.id, name: winner.name.clone(), place: 1, hand_id: record.hand_id, prize: 0, bounties: 0 });
+        }
+        if let Some(bounties) = &self.bounties {
+            self.game.set_bounties(bounties.bounties().clone());
+        }
+        Ok(true)
+    }
+
+    // plays hands until there is a winner, or until no hand can be dealt
+    pub fn play(&mut self) -> Result<(), PokerError> {
+        while self.play_hand()? {}
+        Ok(())
+    }
+
+    // Everyone who has finished so far, best place first, with the payouts
+    // and any bounties applied. Players tied for a place split the prizes of
+    // the places they cover, with odd chips to the lowest seat.
+    pub fn results(&self) -> Vec<Finish> {
+        with_bounties(pay(&self.finishes, &self.payouts), self.bounties.as_ref())
+    }
+}
+
+// Everyone who busted in the game's last hand with the stack they started
+// it with, to place with place_busts.
+pub(crate) fn busted_in(game: &Game) -> impl Iterator<Item = (u32, Finish)> + '_ {
+    let record = game.last_hand();
+    game.busted().iter().map(move |p| {
+        let starting_stack = record.and_then(|r| r.seat(p.id)).map_or(0, |s| s.stack);
+        let hand_id = record.map_or(0, |r| r.hand_id);
+        (starting_stack, Finish { player_id: p.id, name: p.name.clone(), place: 0, hand_id, prize: 0, bounties: 0 })
+    })
+}
+
+// what each finisher was paid in bounties, if it was a bounty tournament
+pub(crate) fn with_bounties(mut results: Vec<Finish>, bounties: Option<&Bounties>) -> Vec<Finish> {
+    if let Some(bounties) = bounties {
+        for finish in &mut results {
+            finish.bounties = bounties.won(finish.player_id);
+        }
+    }
+    results
+}
+
+// Places players who busted at the same time below the players remaining,
+// the bigger starting stacks higher and equal ones tied.
+pub(crate) fn place_busts(mut busted: Vec<(u32, Finish)>, remaining: usize) -> Vec<Finish> {
+    busted.sort_by_key(|b| Reverse(b.0));
+    let mut finishes = Vec::new();
+    let mut place = remaining + 1;
+    for tied in busted.chunk_by(|a, b| a.0 == b.0) {
+        finishes.extend(tied.iter().map(|(_, finish)| Finish { place, ..finish.clone() }));
+        place += tied.len();
+    }
+    finishes
+}
+
+// The finishes best place first with their prizes. Players tied for a place
+// split the prizes of the places they cover, with odd chips to the first.
+pub(crate) fn pay(finishes: &[Finish], payouts: &[u32]) -> Vec<Finish> {
+    let mut results = finishes.to_vec();
+    results.sort_by_key(|f| f.place);
+    for tied in results.chunk_by_mut(|a, b| a.place == b.place) {
+        let first = tied[0].place - 1;
+        let pool: u32 = payouts.iter().skip(first).take(tied.len()).sum();
+        let share = pool / tied.len() as u32;
+        let odd_chips = (pool % tied.len() as u32) as usize;
+        for (n, finish) in tied.iter_mut().enumerate() {
+            finish.prize = share + if n < odd_chips { 1 } else { 0 };
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::deck::Card;
+    use crate::player::{Action, Player, Strategy};
+    use crate::strategy::{Aggressive, CallingStation};
+
+    fn schedule(length: LevelLength) -> BlindSchedule {
+        BlindSchedule {
+            levels: vec![BlindLevel::new(1, 2, Ante::None), BlindLevel::new(2, 4, Ante::None), BlindLevel::new(5, 10, Ante::BigBlind(10))],
+            length,
+        }
+    }
+
+    fn tournament(stacks: &[u32], length: LevelLength) -> Tournament {
+        let players = stacks.iter().enumerate().map(|(id, &chips)| Player::new(id, format!("P{}", id + 1), chips)).collect();
+        let mut game = Game::with_players(players, 1, 2).unwrap();
+        game.set_verbose(false);
+        for id in 0..stacks.len() {
+            game.set_strategy(id, Box::new(CallingStation));
+        }
+        Tournament::new(game, schedule(length))
+    }
+
+    #[test]
+    fn test_levels() {
+        let mut by_hands = tournament(&[1000, 1000, 1000], LevelLength::Hands(3));
+        let mut blinds = Vec::new();
+        for _ in 0..8 {
+            by_hands.play_hand().unwrap();
+            blinds.push(by_hands.game().last_hand().unwrap().big_blind);
+        }
+        assert_eq!(blinds, [2, 2, 2, 4, 4, 4, 10, 10]);
+        assert_eq!(by_hands.game().last_hand().unwrap().ante, 10);
+
+        let mut by_time = tournament(&[1000, 1000, 1000], LevelLength::Time(Duration::from_secs(600)));
+        by_time.set_hand_duration(Duration::from_secs(300));
+        let mut blinds = Vec::new();
+        for _ in 0..5 {
+            by_time.play_hand().unwrap();
+            blinds.push(by_time.game().last_hand().unwrap().big_blind);
+        }
+        assert_eq!(blinds, [2, 2, 4, 4, 10]);
+        assert_eq!(by_time.elapsed(), Duration::from_secs(1500));
+    }
+
+    fn cards(codes: &str) -> Vec<Card> {
+        codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect()
+    }
+
+    // everyone all in on the first hand, which the first player wins
+    fn shove(stacks: &[u32]) -> Tournament {
+        shove_for_bounties(stacks, None)
+    }
+
+    fn shove_for_bounties(stacks: &[u32], bounties: Option<(BountyFormat, u32)>) -> Tournament {
+        let mut tournament = tournament(stacks, LevelLength::Hands(100));
+        tournament.set_payouts(split_prize_pool(100, &[60, 30, 10]));
+        if let Some((format, bounty)) = bounties {
+            tournament.set_bounties(format, bounty);
+        }
+        let game = tournament.game_mut();
+        for id in 0..stacks.len() {
+            game.set_strategy(id, Box::new(Aggressive { pot_fraction: 100.0 }));
+        }
+        let hole_cards = HashMap::from([(0, cards("Ah Ad")), (1, cards("7c 2d")), (2, cards("8c 3d")), (3, cards("9c 4d"))]);
+        game.force_cards(hole_cards, cards("Ks Qs 5h Jd 6c")).unwrap();
+        tournament.play_hand().unwrap();
+        tournament
+    }
+
+    #[test]
+    fn test_simultaneous_busts() {
+        let tournament = shove(&[500, 50, 80, 30]);
+        assert!(tournament.is_finished());
+        let places: Vec<(usize, usize, u32)> = tournament.results().iter().map(|f| (f.player_id, f.place, f.prize)).collect();
+        // the bigger of the stacks that busted together finishes higher
+        assert_eq!(places, [(0, 1, 60), (2, 2, 30), (1, 3, 10), (3, 4, 0)]);
+        assert!(tournament.results().iter().all(|f| f.hand_id == 1));
+    }
+
+    #[test]
+    fn test_icm_equity() {
+        let mut tournament = tournament(&[500, 300, 200], LevelLength::Hands(100));
+        tournament.set_payouts(vec![50, 30, 20]);
+        let equity = tournament.icm_equity();
+        assert_eq!(equity.iter().map(|e| e.0).collect::<Vec<_>>(), [0, 1, 2]);
+        assert!((equity[0].1 - 38.392857).abs() < 1e-5);
+        assert!((equity.iter().map(|e| e.1).sum::<f64>() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tied_busts_share_prizes() {
+        let tournament = shove(&[500, 50, 50, 30]);
+        let places: Vec<(usize, usize, u32)> = tournament.results().iter().map(|f| (f.player_id, f.place, f.prize)).collect();
+        assert_eq!(places, [(0, 1, 60), (1, 2, 20), (2, 2, 20), (3, 4, 0)]);
+    }
+
+    #[test]
+    fn test_play_to_the_end() {
+        let mut tournament = tournament(&[200, 210, 220, 230], LevelLength::Hands(5));
+        tournament.set_payouts(split_prize_pool(401, &[65, 35]));
+        assert_eq!(tournament.payouts(), [261, 140]);
+        tournament.play().unwrap();
+        assert!(tournament.is_finished());
+        let results = tournament.results();
+        assert_eq!(results.len(), 4);
+        assert_eq!((results[0].place, results[0].player_id), (1, tournament.game().players()[0].id));
+        assert_eq!(results.iter().map(|f| f.prize).sum::<u32>(), 401);
+        assert_eq!(tournament.game().players()[0].chips, 860);
+        assert!(!tournament.play_hand().unwrap());
+    }
+
+    #[test]
+    fn test_progressive_knockout() {
+        let tournament = shove_for_bounties(&[500, 50, 80, 30], Some((BountyFormat::Progressive, 20)));
+        let bounties = tournament.bounties().unwrap();
+        // the smallest stack is knocked out first
+        let knockouts: Vec<(usize, usize, u32, u32)> = bounties.knockouts().iter().map(|k| (k.player_id, k.by, k.won, k.added)).collect();
+        assert_eq!(knockouts, [(3, 0, 10, 10), (1, 0, 10, 10), (2, 0, 10, 10)]);
+        // the winner collects the 50 built up on their own head too
+        let results: Vec<(usize, u32, u32)> = tournament.results().iter().map(|f| (f.player_id, f.prize, f.bounties)).collect();
+        assert_eq!(results, [(0, 60, 80), (2, 30, 0), (1, 10, 0), (3, 0, 0)]);
+        assert!(bounties.bounties().is_empty());
+    }
+
+    // calls whenever there is a bounty to win and folds otherwise
+    struct BountyHunter(Vec<(usize, u32)>);
+
+    impl Strategy for BountyHunter {
+        fn decide(&mut self, player: &Player, _pot: u32, _board: &[Card], to_call: u32, _action: &[Vec<Action>]) -> Action {
+            let hunting = self.0.iter().any(|&(id, bounty)| id != player.id && bounty > 0);
+            match to_call {
+                0 => Action::Check,
+                _ if hunting => Action::Call,
+                _ => Action::Fold,
+            }
+        }
+
+        fn see_bounties(&mut self, bounties: &[(usize, u32)]) {
+            self.0 = bounties.to_vec();
+        }
+    }
+
+    #[test]
+    fn test_strategies_see_bounties() {
+        let play = |bounty: Option<u32>| {
+            let mut tournament = tournament(&[100, 100], LevelLength::Hands(100));
+            if let Some(bounty) = bounty {
+                tournament.set_bounties(BountyFormat::Knockout, bounty);
+            }
+            let game = tournament.game_mut();
+            game.set_strategy(0, Box::new(Aggressive { pot_fraction: 100.0 }));
+            game.set_strategy(1, Box::new(BountyHunter(Vec::new())));
+            game.force_cards(HashMap::from([(0, cards("Ah Ad")), (1, cards("7c 2d"))]), cards("Ks Qs 5h Jd 6c")).unwrap();
+            tournament.play_hand().unwrap();
+            tournament
+        };
+        assert!(!play(None).is_finished());
+        let tournament = play(Some(25));
+        assert!(tournament.is_finished());
+        assert_eq!(tournament.bounties().unwrap().won(0), 50);
+    }
+}
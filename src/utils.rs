@@ -1,4 +1,5 @@
 use crate::deck::{Card,Rank,Suit}; 
+use std::cmp::Ordering;
 use Rank::*; 
 use Suit::*;
 #[derive(Eq, PartialEq, Debug, Clone, Copy)]
@@ -14,7 +15,7 @@ pub enum Hand {
     Pair, 
     HighCard
 }
-static HAND_ORDER: &[Hand] = &[
+pub(crate) static HAND_ORDER: &[Hand] = &[
     Hand::RoyalFlush, 
     Hand::StraightFlush, 
     Hand::Quads, 
@@ -39,9 +40,121 @@ impl PartialOrd for Hand {
 }
 use Hand::*;
 
+// How a game ranks its hands. Short deck hold'em takes the twos through
+// fives out of the deck, so the ace plays low below the six instead and a
+// flush, harder to make with nine cards of a suit, beats a full house; some
+// rooms also rank three of a kind above a straight. Ace-to-five lowball, as
+// in Razz, wants the lowest hand with aces low and straights and flushes
+// not counting, so five different ranks beat any pair. Deuce-to-seven
+// lowball turns the usual order upside down: aces are only high, and
+// straights and flushes count against a hand, so 7-5-4-3-2 not all of one
+// suit is the best.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Ranking {
+    Standard,
+    ShortDeck { trips_beat_straight: bool },
+    AceToFive,
+    DeuceToSeven,
+}
+static SHORT_DECK_ORDER: &[Hand] = &[
+    Hand::RoyalFlush,
+    Hand::StraightFlush,
+    Hand::Quads,
+    Hand::Flush,
+    Hand::FullHouse,
+    Hand::Straight,
+    Hand::Trips,
+    Hand::TwoPair,
+    Hand::Pair,
+    Hand::HighCard
+];
+static SHORT_DECK_TRIPS_ORDER: &[Hand] = &[
+    Hand::RoyalFlush,
+    Hand::StraightFlush,
+    Hand::Quads,
+    Hand::Flush,
+    Hand::FullHouse,
+    Hand::Trips,
+    Hand::Straight,
+    Hand::TwoPair,
+    Hand::Pair,
+    Hand::HighCard
+];
+// straights and flushes are never made in ace-to-five
+static ACE_TO_FIVE_ORDER: &[Hand] = &[
+    Hand::HighCard,
+    Hand::Pair,
+    Hand::TwoPair,
+    Hand::Trips,
+    Hand::FullHouse,
+    Hand::Quads,
+    Hand::Straight,
+    Hand::Flush,
+    Hand::StraightFlush,
+    Hand::RoyalFlush
+];
+static DEUCE_TO_SEVEN_ORDER: &[Hand] = &[
+    Hand::HighCard,
+    Hand::Pair,
+    Hand::TwoPair,
+    Hand::Trips,
+    Hand::Straight,
+    Hand::Flush,
+    Hand::FullHouse,
+    Hand::Quads,
+    Hand::StraightFlush,
+    Hand::RoyalFlush
+];
+impl Ranking {
+    // the hands from best to worst
+    pub fn order(self) -> &'static [Hand] {
+        match self {
+            Ranking::Standard => HAND_ORDER,
+            Ranking::ShortDeck { trips_beat_straight: false } => SHORT_DECK_ORDER,
+            Ranking::ShortDeck { trips_beat_straight: true } => SHORT_DECK_TRIPS_ORDER,
+            Ranking::AceToFive => ACE_TO_FIVE_ORDER,
+            Ranking::DeuceToSeven => DEUCE_TO_SEVEN_ORDER,
+        }
+    }
+
+    // whether the lower ranks win
+    pub fn is_low(self) -> bool {
+        matches!(self, Ranking::AceToFive | Ranking::DeuceToSeven)
+    }
+
+    // a rank's place in the game's order, aces low in ace-to-five
+    pub(crate) fn value(self, rank: Rank) -> u8 {
+        match self {
+            Ranking::AceToFive => low_rank(rank),
+            _ => rank as u8,
+        }
+    }
+
+    pub fn compare(self, pro: Hand, opp: Hand) -> Ordering {
+        let order = self.order();
+        order.iter().position(|&h| h == opp).unwrap()
+            .cmp(&order.iter().position(|&h| h == pro).unwrap())
+    }
+
+    // the rank an ace plays below to make the lowest straight, if it can
+    fn ace_plays_below(self) -> Option<Rank> {
+        match self {
+            Ranking::ShortDeck { .. } => Some(Six),
+            Ranking::DeuceToSeven => None,
+            _ => Some(Two),
+        }
+    }
+}
+
+// a rank counted aces low, from 1 for an ace to 13 for a king
+fn low_rank(rank: Rank) -> u8 {
+    if rank == Ace { 1 } else { rank as u8 + 2 }
+}
+
 pub struct HandComparator {}
 impl HandComparator {
-    fn best_combination(rank_sorted_hand: &Vec<Card>) -> ([Card;5],Hand) {
+    #[allow(clippy::needless_return)]
+    fn best_combination(rank_sorted_hand: &[Card]) -> ([Card;5],Hand) {
         debug_assert!(rank_sorted_hand.windows(2).all(|w| w[0].0 >= w[1].0), "Hand must be sorted in decreasing order by rank");
         
         let mut ranks: Vec<Vec<Card>> = vec![Vec::new();13];
@@ -49,49 +162,61 @@ impl HandComparator {
             // card.0 as usize is index sorted by rank
             ranks[12-card.0 as usize].push(*card);
         }
-        // until we have 5 cards, return the max len then the next max len then the next etc
-        // break ties on rank 
+        // biggest groups first, ties broken on rank
         // sort by key does not change the ordering of equal elements so this works
         ranks.sort_by_key(|vec| std::cmp::Reverse(vec.len()) );
 
-        let mut hand = Vec::new();
-        let mut added_cards = 0; 
-        let mut idx = 0; 
-        let mut hand_name = HighCard;
-        while added_cards < 5 {
-            if ranks[idx].len() == 2 {
-                if hand_name==HighCard {hand_name = Pair;}
-                else if hand_name==Pair {hand_name = TwoPair;}
-                else if hand_name==Trips {hand_name = FullHouse;}
-            } 
-            else if ranks[idx].len() == 3 {
-                if hand_name==HighCard {hand_name = Trips;}
-                else if hand_name==Trips {hand_name = FullHouse;}
+        // the groups that make the hand; a full house takes its pair from the
+        // highest remaining group of two or more, which may be a second set of trips
+        let (hand_name, mut hand): (Hand, Vec<Card>) = match (ranks[0].len(), ranks[1].len()) {
+            (4, _) => (Quads, ranks[0].clone()),
+            (3, 2..) => {
+                let pair = (1..13)
+                    .filter(|&idx| ranks[idx].len() >= 2)
+                    .max_by_key(|&idx| ranks[idx][0].0)
+                    .unwrap();
+                (FullHouse, [&ranks[0][..], &ranks[pair][..2]].concat())
+            },
+            (3, _) => (Trips, ranks[0].clone()),
+            (2, 2) => (TwoPair, [&ranks[0][..], &ranks[1][..]].concat()),
+            (2, _) => (Pair, ranks[0].clone()),
+            _ => (HighCard, Vec::new()),
+        };
+
+        // kickers are simply the highest cards left over
+        for card in rank_sorted_hand {
+            if hand.len() == 5 {
+                break;
+            }
+            if !hand.contains(card) {
+                hand.push(*card);
             }
-            else if ranks[idx].len() == 4 {
-                if hand_name==HighCard {hand_name = Quads;}
-            } 
-            added_cards += ranks[idx].len();
-
-            hand.extend_from_slice(&ranks[idx]);          
-            idx+=1; 
         }
         
         return (hand[..5].try_into().unwrap(),hand_name);
     }
     
-    fn best_straight(rank_sorted_hand: &Vec<Card>) -> Option<([Card;5],Hand)> {
+    #[allow(clippy::needless_return)]
+    fn best_straight(rank_sorted_hand: &[Card], ranking: Ranking) -> Option<([Card;5],Hand)> {
         // must be sorted in decreasing order
         debug_assert!(rank_sorted_hand.windows(2).all(|w| w[0].0 >= w[1].0), "Hand must be sorted in decreasing order by rank");
         
         // ignoring straight flushes - those are accounted for in best_flush
-        let n = rank_sorted_hand.len(); 
+        // a pair would break the run, so only look at one card of each rank
+        let mut distinct: Vec<Card> = rank_sorted_hand.to_vec();
+        distinct.dedup_by_key(|card| card.0);
+        let n = distinct.len(); 
+        if n < 5 {
+            return None;
+        }
         let mut hand = Vec::new(); 
-        hand.push(rank_sorted_hand[0]);
+        hand.push(distinct[0]);
         for idx in 1..n+1 {
-            let card = rank_sorted_hand[idx%n];
-            let prev = rank_sorted_hand[(idx-1)%n];
-            if card.0 + 1 != Some(prev.0) {
+            let card = distinct[idx%n];
+            let prev = distinct[(idx-1)%n];
+            // the ace wraps round below the lowest rank only
+            let follows = if card.0 == Ace { ranking.ace_plays_below() == Some(prev.0) } else { card.0 + 1 == Some(prev.0) };
+            if !follows {
                 hand.clear(); 
             }
             hand.push(card);
@@ -100,7 +225,8 @@ impl HandComparator {
         return None;
     }
     
-    fn best_flush(rank_sorted_hand: &Vec<Card>) -> Option<([Card;5],Hand)> {
+    #[allow(clippy::needless_return, clippy::needless_range_loop)]
+    fn best_flush(rank_sorted_hand: &[Card], ranking: Ranking) -> Option<([Card;5],Hand)> {
         // sorted in decreasing order
         debug_assert!(rank_sorted_hand.windows(2).all(|w| w[0].0 >= w[1].0), "Hand must be sorted in decreasing order by rank");
 
@@ -124,7 +250,7 @@ impl HandComparator {
         for idx in 0..4{
             if suits[idx].len() >= 5 {
                 suits[idx].sort_by_key(|c| std::cmp::Reverse(c.0));
-                let straight_flush = HandComparator::best_straight(&suits[idx]);
+                let straight_flush = HandComparator::best_straight(&suits[idx], ranking);
                 match straight_flush {
                     Some((vec,_)) => {
                         match vec[0] {
@@ -140,24 +266,134 @@ impl HandComparator {
         return None;
     }
 
-    pub fn best_hand(mut hand: Vec<Card> ) -> ([Card;5],Hand) {
+    pub fn best_hand(hand: Vec<Card> ) -> ([Card;5],Hand) {
+        HandComparator::best_ranked_hand(hand, Ranking::Standard)
+    }
+
+    // Groups the cards by rank for the lowball games, pairs and sets first,
+    // then the bigger groups and higher ranks first, aces at the bottom.
+    // Straights and flushes are ignored.
+    fn ace_to_five(cards: &[Card]) -> ([Card;5],Hand) {
+        let mut sorted = cards.to_vec();
+        sorted.sort_by_key(|card| {
+            let count = cards.iter().filter(|c| c.0 == card.0).count();
+            std::cmp::Reverse((count, low_rank(card.0)))
+        });
+        let mut counts: Vec<usize> = sorted.chunk_by(|a, b| a.0 == b.0).map(|group| group.len()).collect();
+        counts.resize(2, 0);
+        let hand = match (counts[0], counts[1]) {
+            (4, _) => Quads,
+            (3, 2) => FullHouse,
+            (3, _) => Trips,
+            (2, 2) => TwoPair,
+            (2, _) => Pair,
+            _ => HighCard,
+        };
+        (sorted.try_into().unwrap(), hand)
+    }
+
+    // the best five cards when hands are ranked as in the given game
+    pub fn best_ranked_hand(mut hand: Vec<Card>, ranking: Ranking) -> ([Card;5],Hand) {
+        // a lowball hand is the best of any five, however high they rank
+        if ranking.is_low() && hand.len() > 5 {
+            return HandComparator::five_card_combinations(&hand)
+                .into_iter()
+                .map(|cards| HandComparator::best_ranked_hand(cards, ranking))
+                .max_by(|pro, opp| HandComparator::compare_ranked(pro, opp, ranking))
+                .expect("a hand needs five cards");
+        }
+        if ranking == Ranking::AceToFive {
+            return HandComparator::ace_to_five(&hand);
+        }
         // best 5 card hand from 7
         
         hand.sort_by_key(|card| std::cmp::Reverse(card.0));
             
-        let flush = HandComparator::best_flush(&hand);
-        let straight = HandComparator::best_straight(&hand);
+        let flush = HandComparator::best_flush(&hand, ranking);
+        let straight = HandComparator::best_straight(&hand, ranking);
         let combination = HandComparator::best_combination(&hand);
 
+        // five deuce-to-seven cards are whatever they make as a high hand
+        let high = if ranking.is_low() { Ranking::Standard } else { ranking };
         let mut best_hand = combination; 
-        if let Some((v,h)) = flush {
-            if h > best_hand.1 {best_hand = (v,h)}
+        if let Some((v,h)) = flush && high.compare(h, best_hand.1).is_gt() {
+            best_hand = (v,h)
         }
-        if let Some((v,h)) = straight {
-            if h > best_hand.1 {best_hand = (v,h)}
+        if let Some((v,h)) = straight && high.compare(h, best_hand.1).is_gt() {
+            best_hand = (v,h)
         }
 
-        return best_hand;
+        best_hand
+    }
+
+    // every five of the cards
+    fn five_card_combinations(cards: &[Card]) -> Vec<Vec<Card>> {
+        let mut combinations = vec![Vec::new()];
+        for &card in cards {
+            for idx in 0..combinations.len() {
+                if combinations[idx].len() < 5 {
+                    let mut with = combinations[idx].clone();
+                    with.push(card);
+                    combinations.push(with);
+                }
+            }
+        }
+        combinations.retain(|cards| cards.len() == 5);
+        combinations
+    }
+
+    // every five cards an Omaha hand can play: two of the hole cards and
+    // three of the board
+    fn omaha_combinations(hole_cards: &[Card], board: &[Card]) -> Vec<Vec<Card>> {
+        let mut combinations = Vec::new();
+        for (i, &first) in hole_cards.iter().enumerate() {
+            for &second in &hole_cards[i+1..] {
+                for (a, &x) in board.iter().enumerate() {
+                    for (b, &y) in board.iter().enumerate().skip(a+1) {
+                        for &z in &board[b+1..] {
+                            combinations.push(vec![first, second, x, y, z]);
+                        }
+                    }
+                }
+            }
+        }
+        combinations
+    }
+
+    // Omaha's best hand uses exactly two of the hole cards and exactly three
+    // of the board, whatever else the player holds. None without enough of
+    // either.
+    pub fn best_omaha_hand(hole_cards: &[Card], board: &[Card]) -> Option<([Card;5],Hand)> {
+        HandComparator::omaha_combinations(hole_cards, board)
+            .into_iter()
+            .map(HandComparator::best_hand)
+            .max_by(HandComparator::compare_best)
+    }
+
+    // The best ace-to-five low of eight or better: the lowest five cards of
+    // different ranks no higher than an eight, aces low and straights and
+    // flushes not counting against it, highest first. None if it does not
+    // qualify.
+    pub fn best_low(cards: &[Card]) -> Option<[Card;5]> {
+        let mut low: Vec<Card> = cards.iter().copied().filter(|c| low_rank(c.0) <= 8).collect();
+        low.sort_by_key(|c| low_rank(c.0));
+        low.dedup_by_key(|c| c.0);
+        low.truncate(5);
+        low.reverse();
+        low.try_into().ok()
+    }
+
+    // the best Omaha low of exactly two hole cards and three from the board
+    pub fn best_omaha_low(hole_cards: &[Card], board: &[Card]) -> Option<[Card;5]> {
+        HandComparator::omaha_combinations(hole_cards, board)
+            .iter()
+            .filter_map(|cards| HandComparator::best_low(cards))
+            .max_by(HandComparator::compare_low)
+    }
+
+    // orders two lows so the better, lower one is greater, highest card first
+    pub fn compare_low(pro: &[Card;5], opp: &[Card;5]) -> Ordering {
+        opp.iter().map(|c| low_rank(c.0)).cmp(pro.iter().map(|c| low_rank(c.0)))
     }
 
     pub fn compare_hand(pro_7: Vec<Card>, opp_7: Vec<Card> ) -> i8 {
@@ -165,26 +401,77 @@ impl HandComparator {
         let pro = HandComparator::best_hand(pro_7);
         let opp = HandComparator::best_hand(opp_7);
 
-        if pro.1 > opp.1 {
-            return 1; 
+        match HandComparator::compare_best(&pro, &opp) {
+            Ordering::Greater => 1,
+            Ordering::Less => -1,
+            Ordering::Equal => 0,
         }
-        else if pro.1 < opp.1 {
-            return -1; 
+    }
+
+    // orders two results of best_hand: by hand name first, then rank by rank
+    pub fn compare_best(pro: &([Card;5],Hand), opp: &([Card;5],Hand)) -> Ordering {
+        HandComparator::compare_ranked(pro, opp, Ranking::Standard)
+    }
+
+    // compare_best with the hand names ranked as in the given game, where a
+    // low straight counts as five high with the ace below the lowest rank
+    pub fn compare_ranked(pro: &([Card;5],Hand), opp: &([Card;5],Hand), ranking: Ranking) -> Ordering {
+        let ranks = |hand: &([Card;5],Hand)| hand.0.map(|c| ranking.value(c.0));
+        let by_rank = ranks(pro).cmp(&ranks(opp));
+        ranking.compare(pro.1, opp.1)
+            .then(if ranking.is_low() { by_rank.reverse() } else { by_rank })
+    }
+
+    // Orders what two stud players show, the same number of cards and fewer
+    // than five, for who acts first: pairs and sets count and then rank by
+    // rank, highest first, but straights and flushes do not. In a lowball
+    // game the lower hand is greater.
+    pub fn compare_showing(pro: &[Card], opp: &[Card], ranking: Ranking) -> Ordering {
+        let groups = |cards: &[Card]| {
+            let mut groups: Vec<(usize, u8)> = cards.iter().map(|card| (cards.iter().filter(|c| c.0 == card.0).count(), ranking.value(card.0))).collect();
+            groups.sort_unstable_by(|a, b| b.cmp(a));
+            groups
+        };
+        let by_groups = groups(pro).cmp(&groups(opp));
+        if ranking.is_low() { by_groups.reverse() } else { by_groups }
+    }
+
+}
+
+// A badugi plays as many cards as it can of different suits and different
+// ranks, four at most, and then the lowest of them, aces low. The best of
+// the cards given, highest first.
+pub fn best_badugi(cards: &[Card]) -> Vec<Card> {
+    let mut best = Vec::new();
+    find_badugis(cards, &mut Vec::new(), &mut best);
+    best
+}
+
+// tries every set of the cards with no suit or rank in common
+fn find_badugis(cards: &[Card], chosen: &mut Vec<Card>, best: &mut Vec<Card>) {
+    for (i, &card) in cards.iter().enumerate() {
+        if chosen.iter().any(|c| c.0 == card.0 || c.1 == card.1) {
+            continue;
         }
-        
-        for idx in 0..5 {
-            if pro.0[idx].0 > opp.0[idx].0 {
-                return 1; 
-            }
-            else if pro.0[idx].0 < opp.0[idx].0 {
-                return -1;
-            }
+        chosen.push(card);
+        let mut badugi = chosen.clone();
+        badugi.sort_by_key(|c| std::cmp::Reverse(low_rank(c.0)));
+        if compare_badugi(&badugi, best).is_gt() {
+            *best = badugi;
         }
-        return 0; 
+        find_badugis(&cards[i+1..], chosen, best);
+        chosen.pop();
     }
+}
 
+// orders two results of best_badugi so the better is greater: more cards
+// beat fewer, then the lower wins, highest card first
+pub fn compare_badugi(pro: &[Card], opp: &[Card]) -> Ordering {
+    pro.len().cmp(&opp.len())
+        .then_with(|| opp.iter().map(|c| low_rank(c.0)).cmp(pro.iter().map(|c| low_rank(c.0))))
 }
 
+#[cfg(test)]
 mod tests {
     use crate::utils::*;
     use crate::deck::*; 
@@ -201,7 +488,7 @@ mod tests {
         );
 
         hand.sort_by_key(|x| std::cmp::Reverse(x.0));
-        let flush = HandComparator::best_flush(&hand);
+        let flush = HandComparator::best_flush(&hand, Ranking::Standard);
         assert!(flush.is_none());
     }
     
@@ -218,7 +505,7 @@ mod tests {
         );
 
         hand.sort_by_key(|x| std::cmp::Reverse(x.0));
-        let flush = HandComparator::best_flush(&hand);
+        let flush = HandComparator::best_flush(&hand, Ranking::Standard);
         assert!(flush.unwrap() == ([Card(Ace,Spades),Card(King,Spades),Card(Eight,Spades),Card(Six,Spades),Card(Four,Spades)],Flush));
     }
 
@@ -235,7 +522,7 @@ mod tests {
         );
 
         hand.sort_by_key(|x| std::cmp::Reverse(x.0));
-        let flush = HandComparator::best_flush(&hand);
+        let flush = HandComparator::best_flush(&hand, Ranking::Standard);
         assert!(flush.unwrap() == ([Card(Six,Spades),Card(Five,Spades),Card(Four,Spades),Card(Three,Spades),Card(Two,Spades)],StraightFlush));
     }
 
@@ -253,7 +540,7 @@ mod tests {
         );
 
         hand.sort_by_key(|x| std::cmp::Reverse(x.0));
-        let flush = HandComparator::best_flush(&hand);
+        let flush = HandComparator::best_flush(&hand, Ranking::Standard);
         assert!(flush.unwrap() == ([Card(Ace,Spades),Card(King,Spades),Card(Four,Spades),Card(Three,Spades),Card(Two,Spades)],Flush));
 
     }
@@ -271,7 +558,7 @@ mod tests {
         );
 
         hand.sort_by_key(|card| std::cmp::Reverse(card.0));
-        let straight = HandComparator::best_straight(&hand);
+        let straight = HandComparator::best_straight(&hand, Ranking::Standard);
         assert!(straight == Some(([Card(Jack,Hearts),Card(Ten,Spades),Card(Nine,Spades),Card(Eight,Spades),Card(Seven,Hearts)],Straight)))
     }
 
@@ -288,7 +575,7 @@ mod tests {
         );
 
         hand.sort_by_key(|card| std::cmp::Reverse(card.0));
-        let straight = HandComparator::best_straight(&hand);
+        let straight = HandComparator::best_straight(&hand, Ranking::Standard);
         assert!(straight == Some(([Card(Five,Hearts),Card(Four,Spades),Card(Three,Diamonds),Card(Two,Spades),Card(Ace,Spades)],Straight)));
 
         hand = vec!(
@@ -302,7 +589,7 @@ mod tests {
         );
 
         hand.sort_by_key(|card| std::cmp::Reverse(card.0));
-        let straight = HandComparator::best_straight(&hand);
+        let straight = HandComparator::best_straight(&hand, Ranking::Standard);
         assert!(straight == Some(([Card(Ace,Spades),Card(King,Spades),Card(Queen,Diamonds),Card(Jack,Hearts),Card(Ten,Spades)],Straight)));
     }
 
@@ -319,7 +606,7 @@ mod tests {
         );
 
         hand.sort_by_key(|card| std::cmp::Reverse(card.0));
-        let straight = HandComparator::best_straight(&hand);
+        let straight = HandComparator::best_straight(&hand, Ranking::Standard);
         assert!(straight == Some(([Card(Ten,Hearts),Card(Nine,Clubs),Card(Eight,Hearts),Card(Seven,Diamonds),Card(Six,Hearts)],Straight)))
     }
 
@@ -336,10 +623,111 @@ mod tests {
         );
 
         hand.sort_by_key(|x| std::cmp::Reverse(x.0));
-        let straight = HandComparator::best_straight(&hand);
+        let straight = HandComparator::best_straight(&hand, Ranking::Standard);
         assert!(straight.is_none());
     }
 
+    #[test]
+    fn test_short_deck_straights() {
+        let short_deck = Ranking::ShortDeck { trips_beat_straight: false };
+        let mut hand = vec!(
+            Card(Ace,Spades),
+            Card(Six,Hearts),
+            Card(Seven,Spades),
+            Card(Eight,Diamonds),
+            Card(Nine,Clubs),
+            Card(King,Hearts),
+            Card(King,Spades)
+        );
+        hand.sort_by_key(|x| std::cmp::Reverse(x.0));
+        let straight = HandComparator::best_straight(&hand, short_deck);
+        assert!(straight == Some(([Card(Nine,Clubs),Card(Eight,Diamonds),Card(Seven,Spades),Card(Six,Hearts),Card(Ace,Spades)],Straight)));
+        assert!(HandComparator::best_straight(&hand, Ranking::Standard).is_none());
+
+        // the ace still only wraps round at the bottom, and the lowest
+        // straight loses to the next one up
+        let mut hand = vec!(Card(King,Spades), Card(Ace,Hearts), Card(Six,Clubs), Card(Seven,Diamonds), Card(Eight,Hearts));
+        hand.sort_by_key(|x| std::cmp::Reverse(x.0));
+        assert!(HandComparator::best_straight(&hand, short_deck).is_none());
+        let low = HandComparator::best_ranked_hand(vec!(Card(Nine,Clubs),Card(Eight,Diamonds),Card(Seven,Spades),Card(Six,Hearts),Card(Ace,Spades)), short_deck);
+        let next = HandComparator::best_ranked_hand(vec!(Card(Ten,Clubs),Card(Nine,Diamonds),Card(Eight,Spades),Card(Seven,Hearts),Card(Six,Spades)), short_deck);
+        assert_eq!(HandComparator::compare_ranked(&next, &low, short_deck), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_ace_to_five() {
+        let low = Ranking::AceToFive;
+        // the wheel is the best low, its straight and flush ignored
+        let (wheel, hand) = HandComparator::best_ranked_hand(vec!(Card(Five,Hearts),Card(Four,Hearts),Card(Three,Hearts),Card(Two,Hearts),Card(Ace,Hearts),Card(King,Clubs),Card(King,Spades)), low);
+        assert_eq!((wheel[0], wheel[4], hand), (Card(Five,Hearts), Card(Ace,Hearts), HighCard));
+        // pairs only play when they have to
+        let paired = HandComparator::best_ranked_hand(vec!(Card(Ace,Spades),Card(Ace,Clubs),Card(Two,Spades),Card(Two,Clubs),Card(Three,Hearts),Card(Three,Diamonds),Card(Four,Spades)), low);
+        assert_eq!(paired.1, Pair);
+        let six = HandComparator::best_ranked_hand(vec!(Card(Six,Spades),Card(Four,Clubs),Card(Three,Spades),Card(Two,Clubs),Card(Ace,Diamonds)), low);
+        let seven = HandComparator::best_ranked_hand(vec!(Card(Seven,Spades),Card(Four,Clubs),Card(Three,Spades),Card(Two,Clubs),Card(Ace,Diamonds)), low);
+        assert_eq!(HandComparator::compare_ranked(&six, &seven, low), Ordering::Greater);
+        assert_eq!(HandComparator::compare_ranked(&six, &paired, low), Ordering::Greater);
+        assert_eq!(HandComparator::compare_ranked(&(wheel, hand), &six, low), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_deuce_to_seven() {
+        let low = Ranking::DeuceToSeven;
+        let hand = |cards: [Card;5]| HandComparator::best_ranked_hand(cards.to_vec(), low);
+        let number_one = hand([Card(Seven,Spades),Card(Five,Hearts),Card(Four,Hearts),Card(Three,Hearts),Card(Two,Hearts)]);
+        let eight = hand([Card(Eight,Spades),Card(Five,Hearts),Card(Four,Hearts),Card(Three,Hearts),Card(Two,Hearts)]);
+        // the ace is high, so A-2-3-4-5 is no straight but an ace high
+        let wheel = hand([Card(Ace,Spades),Card(Five,Hearts),Card(Four,Hearts),Card(Three,Hearts),Card(Two,Hearts)]);
+        let straight = hand([Card(Six,Spades),Card(Five,Hearts),Card(Four,Hearts),Card(Three,Hearts),Card(Two,Hearts)]);
+        let flush = hand([Card(Seven,Hearts),Card(Five,Hearts),Card(Four,Hearts),Card(Three,Hearts),Card(Two,Hearts)]);
+        let pair = hand([Card(Two,Spades),Card(Two,Hearts),Card(Four,Hearts),Card(Three,Hearts),Card(Five,Clubs)]);
+        assert_eq!((wheel.1, straight.1, flush.1), (HighCard, Straight, Flush));
+        let best_first = [number_one, eight, wheel, pair, straight, flush];
+        for pair in best_first.windows(2) {
+            assert_eq!(HandComparator::compare_ranked(&pair[0], &pair[1], low), Ordering::Greater);
+        }
+        // with more cards the best five play
+        let seven = HandComparator::best_ranked_hand(vec!(Card(Seven,Spades),Card(Five,Hearts),Card(Four,Hearts),Card(Three,Hearts),Card(Two,Hearts),Card(Six,Clubs),Card(Two,Clubs)), low);
+        assert_eq!((seven.0[0], seven.1), (Card(Seven,Spades), HighCard));
+    }
+
+    #[test]
+    fn test_badugi() {
+        // the king of diamonds shares a suit with the three, so the best is
+        // three cards, and the lower of the two
+        let three = best_badugi(&[Card(Ace,Spades),Card(Two,Clubs),Card(Three,Diamonds),Card(King,Diamonds)]);
+        assert_eq!(three, [Card(Three,Diamonds),Card(Two,Clubs),Card(Ace,Spades)]);
+        let king_high = best_badugi(&[Card(King,Clubs),Card(Queen,Diamonds),Card(Jack,Hearts),Card(Ten,Spades)]);
+        assert_eq!(king_high.len(), 4);
+        let wheel = best_badugi(&[Card(Four,Clubs),Card(Three,Diamonds),Card(Two,Hearts),Card(Ace,Spades)]);
+        let paired = best_badugi(&[Card(Ace,Clubs),Card(Ace,Diamonds),Card(Ace,Hearts),Card(Ace,Spades)]);
+        assert_eq!(paired.len(), 1);
+        // any four cards beat three, and suits never break a tie
+        let best_first = [wheel, king_high, three, paired];
+        for pair in best_first.windows(2) {
+            assert_eq!(compare_badugi(&pair[0], &pair[1]), Ordering::Greater);
+        }
+        let other_suits = best_badugi(&[Card(Four,Diamonds),Card(Three,Clubs),Card(Two,Spades),Card(Ace,Hearts)]);
+        assert_eq!(compare_badugi(&best_first[0], &other_suits), Ordering::Equal);
+        // from more cards the best four play, one deuce at most
+        let seven = best_badugi(&[Card(King,Clubs),Card(Queen,Diamonds),Card(Jack,Hearts),Card(Ten,Spades),Card(Five,Spades),Card(Two,Clubs),Card(Two,Hearts)]);
+        assert_eq!(seven, [Card(Queen,Diamonds),Card(Jack,Hearts),Card(Five,Spades),Card(Two,Clubs)]);
+    }
+
+    #[test]
+    fn test_compare_showing() {
+        let kings = [Card(King,Spades),Card(King,Hearts)];
+        let aces = [Card(Ace,Spades),Card(Seven,Hearts)];
+        let flush = [Card(Two,Spades),Card(Three,Spades),Card(Four,Spades),Card(Five,Spades)];
+        let pair = [Card(Two,Hearts),Card(Two,Diamonds),Card(Three,Clubs),Card(Four,Diamonds)];
+        // a pair beats an ace high, and four to a straight flush is nothing
+        assert_eq!(HandComparator::compare_showing(&kings, &aces, Ranking::Standard), Ordering::Greater);
+        assert_eq!(HandComparator::compare_showing(&pair, &flush, Ranking::Standard), Ordering::Greater);
+        // in razz the unpaired low cards show best, aces lowest
+        assert_eq!(HandComparator::compare_showing(&aces, &kings, Ranking::AceToFive), Ordering::Greater);
+        assert_eq!(HandComparator::compare_showing(&flush, &pair, Ranking::AceToFive), Ordering::Greater);
+    }
+
     #[test]
     fn test_high_card() {
         let mut hand = vec!(
@@ -444,8 +832,138 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_straight_with_pair() {
+        let mut hand = vec!(
+            Card(Nine,Spades),
+            Card(Eight,Hearts),
+            Card(Eight,Clubs),
+            Card(Seven,Diamonds),
+            Card(Six,Hearts),
+            Card(Five,Spades),
+            Card(Two,Hearts)
+        );
+
+        hand.sort_by_key(|x| std::cmp::Reverse(x.0));
+        let straight = HandComparator::best_straight(&hand, Ranking::Standard);
+        assert!(straight == Some(([Card(Nine,Spades),Card(Eight,Hearts),Card(Seven,Diamonds),Card(Six,Hearts),Card(Five,Spades)],Straight)));
+    }
+
+    #[test]
+    fn test_two_pair_kicker_over_third_pair() {
+        let mut hand = vec!(
+            Card(King,Spades),
+            Card(King,Hearts),
+            Card(Queen,Diamonds),
+            Card(Queen,Spades),
+            Card(Two,Hearts),
+            Card(Two,Spades),
+            Card(Ace,Clubs)
+        );
+
+        hand.sort_by_key(|x| std::cmp::Reverse(x.0));
+        let combination = HandComparator::best_combination(&hand);
+        assert!(combination == ([Card(King,Spades),Card(King,Hearts),Card(Queen,Diamonds),Card(Queen,Spades),Card(Ace,Clubs)],TwoPair))
+    }
+
+    #[test]
+    fn test_full_house_takes_highest_pair() {
+        let mut hand = vec!(
+            Card(Seven,Spades),
+            Card(Seven,Hearts),
+            Card(Seven,Diamonds),
+            Card(Three,Spades),
+            Card(Three,Hearts),
+            Card(Three,Clubs),
+            Card(Ace,Clubs)
+        );
+
+        hand.sort_by_key(|x| std::cmp::Reverse(x.0));
+        let combination = HandComparator::best_combination(&hand);
+        assert!(combination == ([Card(Seven,Spades),Card(Seven,Hearts),Card(Seven,Diamonds),Card(Three,Spades),Card(Three,Hearts)],FullHouse));
+
+        hand = vec!(
+            Card(Seven,Spades),
+            Card(Seven,Hearts),
+            Card(Seven,Diamonds),
+            Card(Three,Spades),
+            Card(Three,Hearts),
+            Card(Ace,Clubs),
+            Card(Ace,Hearts)
+        );
+        hand.sort_by_key(|x| std::cmp::Reverse(x.0));
+        let combination = HandComparator::best_combination(&hand);
+        assert!(combination == ([Card(Seven,Spades),Card(Seven,Hearts),Card(Seven,Diamonds),Card(Ace,Clubs),Card(Ace,Hearts)],FullHouse));
+    }
+
+    #[test]
+    fn test_quads_kicker() {
+        let mut hand = vec!(
+            Card(Two,Spades),
+            Card(Two,Hearts),
+            Card(Two,Diamonds),
+            Card(Two,Clubs),
+            Card(Five,Hearts),
+            Card(Five,Spades),
+            Card(King,Clubs)
+        );
+
+        hand.sort_by_key(|x| std::cmp::Reverse(x.0));
+        let combination = HandComparator::best_combination(&hand);
+        assert!(combination == ([Card(Two,Spades),Card(Two,Hearts),Card(Two,Diamonds),Card(Two,Clubs),Card(King,Clubs)],Quads))
+    }
+
+    #[test]
+    fn test_omaha_two_and_three() {
+        // four of a kind in the hand plays as a pair, and a board straight
+        // does not play without two hole cards to make it
+        let hole = vec!(Card(Nine,Spades),Card(Nine,Hearts),Card(Nine,Diamonds),Card(Nine,Clubs));
+        let board = vec!(Card(Five,Hearts),Card(Six,Spades),Card(Seven,Diamonds),Card(Eight,Clubs),Card(Ace,Clubs));
+        let (cards, hand) = HandComparator::best_omaha_hand(&hole, &board).unwrap();
+        assert_eq!(hand, Pair);
+        assert_eq!(cards.iter().filter(|c| hole.contains(c)).count(), 2);
+
+        let hole = vec!(Card(Four,Spades),Card(Two,Hearts),Card(King,Diamonds),Card(King,Clubs),Card(Three,Clubs));
+        assert_eq!(HandComparator::best_omaha_hand(&hole, &board).unwrap().1, Straight);
+        // the eight high straight would take only the four from the hand
+        assert_eq!(HandComparator::best_omaha_hand(&hole, &board).unwrap().0[0], Card(Seven,Diamonds));
+        // one hole card or two on the board make no hand at all
+        assert_eq!(HandComparator::best_omaha_hand(&hole[..1], &board), None);
+        assert_eq!(HandComparator::best_omaha_hand(&hole, &board[..2]), None);
+    }
+
+    #[test]
+    fn test_low() {
+        let low = HandComparator::best_low(&[Card(Ace,Spades),Card(Ace,Hearts),Card(Five,Clubs),Card(Four,Clubs),Card(Three,Clubs),Card(Two,Clubs),Card(King,Hearts)]);
+        // the wheel is the best low, flush and straight or not
+        assert_eq!(low, Some([Card(Five,Clubs),Card(Four,Clubs),Card(Three,Clubs),Card(Two,Clubs),Card(Ace,Spades)]));
+        assert_eq!(HandComparator::best_low(&[Card(Ace,Spades),Card(Two,Hearts),Card(Three,Clubs),Card(Four,Clubs),Card(Nine,Clubs)]), None);
+        assert_eq!(HandComparator::best_low(&[Card(Ace,Spades),Card(Two,Hearts),Card(Three,Clubs),Card(Three,Hearts),Card(Eight,Clubs)]), None);
+
+        // 8-5-4-2-A loses to 7-6-5-4-3, and 6-5-4-3-2 loses to 6-4-3-2-A
+        let eight = [Card(Eight,Clubs),Card(Five,Clubs),Card(Four,Clubs),Card(Two,Clubs),Card(Ace,Clubs)];
+        let seven = [Card(Seven,Hearts),Card(Six,Hearts),Card(Five,Hearts),Card(Four,Hearts),Card(Three,Hearts)];
+        assert_eq!(HandComparator::compare_low(&seven, &eight), Ordering::Greater);
+        let six = [Card(Six,Hearts),Card(Five,Hearts),Card(Four,Hearts),Card(Three,Hearts),Card(Two,Hearts)];
+        let better_six = [Card(Six,Clubs),Card(Four,Clubs),Card(Three,Clubs),Card(Two,Clubs),Card(Ace,Clubs)];
+        assert_eq!(HandComparator::compare_low(&six, &better_six), Ordering::Less);
+    }
+
+    #[test]
+    fn test_omaha_low() {
+        // three low cards on the board and two different ones in the hand
+        let board = vec!(Card(Two,Hearts),Card(Five,Spades),Card(Seven,Diamonds),Card(King,Clubs),Card(King,Hearts));
+        let hole = vec!(Card(Ace,Spades),Card(Two,Clubs),Card(Three,Diamonds),Card(Queen,Clubs));
+        let low = HandComparator::best_omaha_low(&hole, &board).unwrap();
+        assert_eq!(low, [Card(Seven,Diamonds),Card(Five,Spades),Card(Three,Diamonds),Card(Two,Hearts),Card(Ace,Spades)]);
+        // a single low card in the hand makes no low
+        let hole = vec!(Card(Ace,Spades),Card(King,Diamonds),Card(Queen,Clubs),Card(Jack,Clubs));
+        assert_eq!(HandComparator::best_omaha_low(&hole, &board), None);
+    }
+
     // for looking at random tests because its cool 
     // #[test]
+    #[allow(dead_code)]
     fn test_loop() {
         loop {
 
@@ -461,7 +979,7 @@ mod tests {
             ];
             Deck::print_cards(&hand);
             let best_hand = HandComparator::best_hand(hand);
-            Deck::print_cards(&best_hand.0);
+            Deck::print_cards(best_hand.0);
 
             let mut input = String::new();
             println!("Press Enter to continue or type 'exit' to quit: ");
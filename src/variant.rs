@@ -0,0 +1,280 @@
+use std::ops::RangeInclusive;
+
+use crate::deck::{Card, Deck};
+use crate::error::PokerError;
+use crate::utils::{best_badugi, Hand, HandComparator, Ranking};
+
+// The game a table deals. Hold'em players make their best five cards out of
+// two hole cards and the board however they like; Omaha players are dealt
+// four, five or six hole cards and must use exactly two of them with exactly
+// three from the board. In Omaha hi-lo each pot is split between the best
+// high hand and the best ace-to-five low of eight or better, made the same
+// way, and the high hand takes it all when nobody has a low. Short deck
+// hold'em deals from sixes through aces and ranks hands to match, see
+// Ranking, and is played with everyone anteing. Seven card stud has no
+// board: each player gets two cards down and one up, three more up and a
+// last one down, and makes the best five of their seven. Razz deals the
+// same way and plays them for the best ace-to-five low. The draw games deal
+// five cards with blinds and no board, and between betting rounds everyone
+// throws away what they like for new cards: once in five card draw, three
+// times in deuce-to-seven triple draw, which plays for the best 2-7 low.
+// Badugi draws three times to four cards for the best badugi, see
+// utils::best_badugi.
+// Omaha is only dealt four to six hole cards; build one with
+// Variant::omaha_with, and Game::set_variant refuses any other count.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Variant {
+    Holdem,
+    Omaha { hole_cards: usize },
+    OmahaHiLo { hole_cards: usize },
+    ShortDeck { trips_beat_straight: bool },
+    Stud,
+    Razz,
+    FiveCardDraw,
+    DeuceToSevenTripleDraw,
+    Badugi,
+}
+
+// A player's best hand at showdown: five cards and what they make, or a
+// badugi of up to four cards, ordered by utils::compare_badugi.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BestHand {
+    Five([Card; 5], Hand),
+    Badugi(Vec<Card>),
+}
+
+impl BestHand {
+    pub fn cards(&self) -> &[Card] {
+        match self {
+            BestHand::Five(cards, _) => cards,
+            BestHand::Badugi(cards) => cards,
+        }
+    }
+
+    // a badugi has no five card rank and counts as high card
+    pub fn hand(&self) -> Hand {
+        match self {
+            BestHand::Five(_, hand) => *hand,
+            BestHand::Badugi(_) => Hand::HighCard,
+        }
+    }
+}
+
+const OMAHA_HOLE_CARDS: RangeInclusive<usize> = 4..=6;
+
+impl Variant {
+    // the usual four card Omaha
+    pub fn omaha() -> Variant {
+        Variant::Omaha { hole_cards: 4 }
+    }
+
+    // Omaha, or Omaha hi-lo, with four, five or six hole cards
+    pub fn omaha_with(hole_cards: usize, hi_lo: bool) -> Result<Variant, PokerError> {
+        let variant = if hi_lo { Variant::OmahaHiLo { hole_cards } } else { Variant::Omaha { hole_cards } };
+        variant.check()?;
+        Ok(variant)
+    }
+
+    // an error for an Omaha dealt a number of hole cards it is not played with
+    pub fn check(self) -> Result<(), PokerError> {
+        match self {
+            Variant::Omaha { hole_cards } | Variant::OmahaHiLo { hole_cards } if !OMAHA_HOLE_CARDS.contains(&hole_cards) => {
+                Err(PokerError::HoleCardsOutOfRange { hole_cards, min: *OMAHA_HOLE_CARDS.start(), max: *OMAHA_HOLE_CARDS.end() })
+            },
+            _ => Ok(()),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Holdem => "holdem",
+            Variant::Omaha { hole_cards: 5 } => "omaha5",
+            Variant::Omaha { hole_cards: 6 } => "omaha6",
+            Variant::Omaha { .. } => "omaha",
+            Variant::OmahaHiLo { hole_cards: 5 } => "omaha5-hi-lo",
+            Variant::OmahaHiLo { hole_cards: 6 } => "omaha6-hi-lo",
+            Variant::OmahaHiLo { .. } => "omaha-hi-lo",
+            Variant::ShortDeck { trips_beat_straight: false } => "short-deck",
+            Variant::ShortDeck { trips_beat_straight: true } => "short-deck-trips",
+            Variant::Stud => "stud",
+            Variant::Razz => "razz",
+            Variant::FiveCardDraw => "five-card-draw",
+            Variant::DeuceToSevenTripleDraw => "2-7-triple-draw",
+            Variant::Badugi => "badugi",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Variant> {
+        match name {
+            "holdem" => Some(Variant::Holdem),
+            "omaha" => Some(Variant::omaha()),
+            "omaha5" => Some(Variant::Omaha { hole_cards: 5 }),
+            "omaha6" => Some(Variant::Omaha { hole_cards: 6 }),
+            "omaha-hi-lo" => Some(Variant::OmahaHiLo { hole_cards: 4 }),
+            "omaha5-hi-lo" => Some(Variant::OmahaHiLo { hole_cards: 5 }),
+            "omaha6-hi-lo" => Some(Variant::OmahaHiLo { hole_cards: 6 }),
+            "short-deck" => Some(Variant::ShortDeck { trips_beat_straight: false }),
+            "short-deck-trips" => Some(Variant::ShortDeck { trips_beat_straight: true }),
+            "stud" => Some(Variant::Stud),
+            "razz" => Some(Variant::Razz),
+            "five-card-draw" => Some(Variant::FiveCardDraw),
+            "2-7-triple-draw" => Some(Variant::DeuceToSevenTripleDraw),
+            "badugi" => Some(Variant::Badugi),
+            _ => None,
+        }
+    }
+
+    pub fn hole_cards(self) -> usize {
+        match self {
+            Variant::Holdem | Variant::ShortDeck { .. } => 2,
+            Variant::Omaha { hole_cards } | Variant::OmahaHiLo { hole_cards } => hole_cards,
+            Variant::Stud | Variant::Razz => 7,
+            Variant::FiveCardDraw | Variant::DeuceToSevenTripleDraw => 5,
+            Variant::Badugi => 4,
+        }
+    }
+
+    // how many times the players draw new cards
+    pub fn draws(self) -> usize {
+        match self {
+            Variant::FiveCardDraw => 1,
+            Variant::DeuceToSevenTripleDraw | Variant::Badugi => 3,
+            _ => 0,
+        }
+    }
+
+    // dealt street by street with some cards face up, and no board
+    pub fn is_stud(self) -> bool {
+        matches!(self, Variant::Stud | Variant::Razz)
+    }
+
+    // whether pots are split between a high and a low hand
+    pub fn is_hi_lo(self) -> bool {
+        matches!(self, Variant::OmahaHiLo { .. })
+    }
+
+    // a fresh deck to deal the variant from
+    pub fn deck(self) -> Deck {
+        match self {
+            Variant::ShortDeck { .. } => Deck::short(),
+            _ => Deck::new(),
+        }
+    }
+
+    pub fn ranking(self) -> Ranking {
+        match self {
+            Variant::ShortDeck { trips_beat_straight } => Ranking::ShortDeck { trips_beat_straight },
+            // badugi counts aces low as well
+            Variant::Razz | Variant::Badugi => Ranking::AceToFive,
+            Variant::DeuceToSevenTripleDraw => Ranking::DeuceToSeven,
+            _ => Ranking::Standard,
+        }
+    }
+
+    // the most players one deck can deal a hand to, as for hold'em leaving
+    // enough for the board and a burn card before each street; draws are
+    // dealt from the discards when the deck runs out
+    pub fn max_players(self) -> usize {
+        let board = if self.is_stud() || self.draws() > 0 { 0 } else { 8 };
+        // none at all for an Omaha dealt no hole cards
+        (self.deck().deck.len() - board).checked_div(self.hole_cards()).unwrap_or(0)
+    }
+
+    // a player's best hand on a full board, None if an Omaha player is short
+    // of two hole cards
+    pub fn best_hand(self, hole_cards: &[Card], board: &[Card]) -> Option<BestHand> {
+        match self {
+            Variant::Badugi => Some(BestHand::Badugi(best_badugi(hole_cards))),
+            Variant::Holdem | Variant::ShortDeck { .. } | Variant::Stud | Variant::Razz | Variant::FiveCardDraw | Variant::DeuceToSevenTripleDraw => {
+                let (cards, hand) = HandComparator::best_ranked_hand([board, hole_cards].concat(), self.ranking());
+                Some(BestHand::Five(cards, hand))
+            },
+            Variant::Omaha { .. } | Variant::OmahaHiLo { .. } => {
+                HandComparator::best_omaha_hand(hole_cards, board).map(|(cards, hand)| BestHand::Five(cards, hand))
+            },
+        }
+    }
+
+    // a player's best qualifying low on a full board, in a hi-lo game
+    pub fn best_low(self, hole_cards: &[Card], board: &[Card]) -> Option<[Card; 5]> {
+        match self {
+            Variant::OmahaHiLo { .. } => HandComparator::best_omaha_low(hole_cards, board),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(codes: &str) -> Vec<Card> {
+        codes.split_whitespace().map(|c| Card::from_code(c).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_names_and_sizes() {
+        for variant in [Variant::Holdem, Variant::omaha(), Variant::Omaha { hole_cards: 5 }, Variant::Omaha { hole_cards: 6 }, Variant::OmahaHiLo { hole_cards: 4 }, Variant::OmahaHiLo { hole_cards: 5 }, Variant::ShortDeck { trips_beat_straight: false }, Variant::ShortDeck { trips_beat_straight: true }, Variant::Stud, Variant::Razz, Variant::FiveCardDraw, Variant::DeuceToSevenTripleDraw, Variant::Badugi] {
+            assert_eq!(Variant::from_name(variant.name()), Some(variant));
+        }
+        assert_eq!(Variant::from_name("courchevel"), None);
+        assert_eq!(Variant::Holdem.max_players(), crate::game::MAX_PLAYERS);
+        assert_eq!(Variant::omaha().max_players(), 11);
+        assert_eq!(Variant::Omaha { hole_cards: 6 }.max_players(), 7);
+        assert_eq!(Variant::ShortDeck { trips_beat_straight: false }.max_players(), 14);
+        assert_eq!(Variant::Razz.max_players(), 7);
+        assert_eq!(Variant::DeuceToSevenTripleDraw.max_players(), 10);
+        assert_eq!(Variant::Badugi.max_players(), 13);
+    }
+
+    #[test]
+    fn test_omaha_hole_cards() {
+        assert_eq!(Variant::omaha_with(5, false), Ok(Variant::Omaha { hole_cards: 5 }));
+        assert_eq!(Variant::omaha_with(6, true), Ok(Variant::OmahaHiLo { hole_cards: 6 }));
+        for hole_cards in [0, 3, 7] {
+            assert_eq!(Variant::omaha_with(hole_cards, false), Err(PokerError::HoleCardsOutOfRange { hole_cards, min: 4, max: 6 }));
+        }
+        assert_eq!(Variant::Omaha { hole_cards: 0 }.max_players(), 0);
+    }
+
+    #[test]
+    fn test_best_hand() {
+        // four hearts on the board make a hold'em flush with one heart, but
+        // an Omaha hand needs two
+        let board = cards("2h 7h 9h Jh Kc");
+        assert_eq!(Variant::Holdem.best_hand(&cards("Ah Kd"), &board).unwrap().hand(), Hand::Flush);
+        assert_eq!(Variant::omaha().best_hand(&cards("Ah Kd Qs Qd"), &board).unwrap().hand(), Hand::Pair);
+        assert_eq!(Variant::omaha().best_hand(&cards("Ah 3h Qs Qd"), &board).unwrap().hand(), Hand::Flush);
+        assert_eq!(Variant::omaha().best_low(&cards("Ah 3h Qs Qd"), &board), None);
+        // two low cards on the board are not enough for a low
+        let hi_lo = Variant::OmahaHiLo { hole_cards: 4 };
+        assert_eq!(hi_lo.best_low(&cards("Ah 3h Qs Qd"), &board), None);
+        let low = hi_lo.best_low(&cards("Ah 3h Qs Qd"), &cards("2h 7h 4c Jh Kc")).unwrap();
+        assert_eq!(low.to_vec(), cards("7h 4c 3h 2h Ah"));
+        // a badugi is its own hand, whatever the board
+        let badugi = Variant::Badugi.best_hand(&cards("Kc Qd 5d 2h"), &[]).unwrap();
+        assert_eq!(badugi, BestHand::Badugi(cards("Kc 5d 2h")));
+        assert_eq!(badugi.hand(), Hand::HighCard);
+    }
+
+    #[test]
+    fn test_short_deck() {
+        let short_deck = Variant::ShortDeck { trips_beat_straight: false };
+        let triton = Variant::ShortDeck { trips_beat_straight: true };
+        // the ace plays below the six
+        let wheel = short_deck.best_hand(&cards("As 7d"), &cards("6c 8h 9s Kd Kc")).unwrap();
+        assert_eq!((wheel.cards().to_vec(), wheel.hand()), (cards("9s 8h 7d 6c As"), Hand::Straight));
+        assert_eq!(Variant::Holdem.best_hand(&cards("As 7d"), &cards("6c 8h 9s Kd Kc")).unwrap().hand(), Hand::Pair);
+        // a flush beats a full house
+        let board = cards("Ah Kh Kc 7h 6s");
+        assert_eq!(short_deck.best_hand(&cards("8h 9h"), &board).unwrap().hand(), Hand::Flush);
+        assert_eq!(short_deck.best_hand(&cards("As Ad"), &board).unwrap().hand(), Hand::FullHouse);
+        assert!(short_deck.ranking().compare(Hand::Flush, Hand::FullHouse).is_gt());
+        assert!(Variant::Holdem.ranking().compare(Hand::Flush, Hand::FullHouse).is_lt());
+        // and trips a straight, if the game says so
+        let (hole, board) = (cards("Qd Qs"), cards("Qc Jd Th 9s 8c"));
+        assert_eq!(short_deck.best_hand(&hole, &board).unwrap().hand(), Hand::Straight);
+        assert_eq!(triton.best_hand(&hole, &board).unwrap().hand(), Hand::Trips);
+        assert_eq!(short_deck.deck().deck.len(), 36);
+    }
+}
@@ -0,0 +1,48 @@
+// Uses the crate only through its public API, the way a downstream project would.
+use poker::prelude::*;
+use poker::pokerstars::{self, PokerStarsWriter};
+
+struct AlwaysCall;
+
+impl Strategy for AlwaysCall {
+    fn decide(&mut self, _player: &Player, _pot: u32, _board: &[Card], to_call: u32, _action: &[Vec<Action>]) -> Action {
+        if to_call == 0 { Action::Check } else { Action::Call }
+    }
+}
+
+#[test]
+fn test_play_with_strategies() {
+    let mut game = Game::new(3, 100).unwrap();
+    for player_id in 0..3 {
+        game.set_strategy(player_id, Box::new(AlwaysCall));
+    }
+    game.play_round().unwrap();
+    let hand = game.last_hand().unwrap();
+    assert_eq!(hand.board.len(), 5);
+    assert_eq!(hand.total_pot(), 6);
+    assert_eq!(game.players().iter().map(|p| p.chips).sum::<u32>(), 300);
+}
+
+#[test]
+fn test_export_and_import() {
+    let mut game = Game::new(4, 500).unwrap();
+    game.add_history_writer(Box::new(PokerStarsWriter::new(std::io::sink()))).unwrap();
+    game.play_round().unwrap();
+    let hand = game.last_hand().unwrap();
+    // the history is the first seat's, so only their cards are dealt face up
+    let hero = &hand.seats[0];
+    let text = pokerstars::format_hand(hand, Some(hero.seat));
+    let parsed = pokerstars::parse_hand(&text).unwrap();
+    assert_eq!((&parsed.actions, &parsed.pots, &parsed.board), (&hand.actions, &hand.pots, &hand.board));
+    assert_eq!(parsed.seats[0].cards, hero.cards);
+    assert!(!poker::VERSION.is_empty());
+}
+
+#[test]
+fn test_evaluate() {
+    let cards: Vec<Card> = ["Ah", "Kh", "Qh", "Jh", "Th", "2c", "3d"]
+        .iter()
+        .map(|code| Card::from_code(code).unwrap())
+        .collect();
+    assert_eq!(HandComparator::best_hand(cards).1, Hand::RoyalFlush);
+}